@@ -0,0 +1,96 @@
+use chrono::{DateTime, Utc};
+
+use crate::config::Config;
+use crate::error::Error;
+use crate::feed_state::FeedStates;
+use crate::state_store::{self, StateStore};
+use crate::Result;
+
+/// Feed states as pretty printed JSON
+pub async fn show_state(config: &Config) -> Result<String> {
+    let store = state_store::from_config(&config.state)?;
+    Ok(serde_json::to_string_pretty(&store.load().await?)?)
+}
+
+/// Forgets the state of one or all feeds, all offers in the feed are announced again
+pub async fn reset_state(config: &Config, feed_id: Option<&str>) -> Result<()> {
+    let store = state_store::from_config(&config.state)?;
+    reset(store.as_ref(), feed_id).await
+}
+
+/// Announces only offers of a configured feed published after the date
+pub async fn set_watermark(config: &Config, feed_id: &str, date: DateTime<Utc>) -> Result<()> {
+    if !config.feeds.iter().any(|feed| feed.id == feed_id) {
+        return Err(Error::custom(format!("Unknown feed {feed_id}")));
+    }
+
+    let store = state_store::from_config(&config.state)?;
+    watermark(store.as_ref(), feed_id, date).await
+}
+
+async fn reset(store: &dyn StateStore, feed_id: Option<&str>) -> Result<()> {
+    let Some(feed_id) = feed_id else {
+        info!("Resetting the state of all feeds");
+        return store.save(&FeedStates::default()).await;
+    };
+
+    let mut states = store.load().await?;
+    if !states.remove(feed_id) {
+        return Err(Error::custom(format!("No state stored for feed {feed_id}")));
+    }
+    info!("Resetting the state of feed {feed_id}");
+    store.save(&states).await
+}
+
+async fn watermark(store: &dyn StateStore, feed_id: &str, date: DateTime<Utc>) -> Result<()> {
+    let mut states = store.load().await?;
+    let state = states.set_watermark(feed_id, date);
+    info!("Setting the watermark of feed {feed_id} to {date}");
+    store.update(&[(feed_id, state)]).await
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::state_store::json::JsonFileStore;
+    use crate::state_store::tests::create_states;
+
+    use super::*;
+
+    fn create_store(dir: &tempfile::TempDir) -> JsonFileStore {
+        JsonFileStore::new(dir.path().join("feed_state.json"))
+    }
+
+    #[tokio::test]
+    async fn test_reset() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = create_store(&dir);
+        store.save(&create_states()).await.unwrap();
+
+        reset(&store, Some("other")).await.unwrap();
+        let states = store.load().await.unwrap();
+        assert!(states.feeds().contains_key("netcup"));
+        assert!(!states.feeds().contains_key("other"));
+
+        assert!(reset(&store, Some("other")).await.is_err());
+
+        reset(&store, None).await.unwrap();
+        assert_eq!(store.load().await.unwrap(), FeedStates::default());
+    }
+
+    #[tokio::test]
+    async fn test_watermark() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = create_store(&dir);
+        store.save(&create_states()).await.unwrap();
+        let date = DateTime::from_timestamp(1736935200, 0).unwrap();
+
+        watermark(&store, "other", date).await.unwrap();
+
+        let states = store.load().await.unwrap();
+        assert_eq!(states.feeds()["other"].last_update(), Some(date));
+        assert_eq!(
+            states.feeds()["netcup"].last_update(),
+            create_states().feeds()["netcup"].last_update()
+        );
+    }
+}
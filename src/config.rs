@@ -1,24 +1,526 @@
 use crate::error::Error;
+use crate::filter::{Filter, FilterConfig};
+use crate::locale::Locale;
+use crate::notifier::mention::{MentionConfig, MentionRule};
+use crate::notifier::template::{MessageTemplate, TemplateConfig};
+use crate::notifier::{telegram, webhook};
+use crate::schedule::{DigestConfig, DigestSchedule};
+use crate::state_store::redis::DEFAULT_KEY;
+use crate::state_store::{FEED_STATE_DATABASE, FEED_STATE_FILE};
+use ed25519_dalek::VerifyingKey;
 use secrecy::SecretBox;
+use std::collections::HashSet;
 use std::net::SocketAddr;
+use std::path::{Path, PathBuf};
 use std::time::Duration;
 
+const ENV_CONFIG_FILE: &str = "CONFIG_FILE";
+
+const DEFAULT_CONFIG_FILE: &str = "./config";
 const DEFAULT_METRIC_IP: &str = "127.0.0.1";
 const DEFAULT_METRIC_PORT: u16 = 9184;
+const DEFAULT_SHUTDOWN_TIMEOUT: u64 = 30;
+
+const DEFAULT_DESTINATION_NAME: &str = "default";
+
+const DEFAULT_INTERACTIONS_LISTEN: &str = "0.0.0.0:8080";
+
+pub const DEFAULT_DATA_DIR: &str = "./data";
+
+pub const DEFAULT_FEED_ID: &str = "netcup";
+const DEFAULT_FEED_NAME: &str = "Netcup";
+const DEFAULT_FEED_URL: &str = "https://www.netcup.com/special-offers.xml?locale=de";
 
 #[derive(Debug, serde::Deserialize)]
 struct RawConfig {
-    web_hook: SecretBox<String>,
+    web_hook: Option<SecretBox<String>>,
     check_interval: u64,
     metric_ip: Option<String>,
     metric_port: Option<u16>,
+    /// Seconds to finish the running check and save the state on shutdown
+    shutdown_timeout: Option<u64>,
+    feeds: Option<Vec<FeedConfig>>,
+    filters: Option<FilterConfig>,
+    /// Batching of the WEB_HOOK destination
+    batch: Option<bool>,
+    destinations: Option<Vec<RawDestinationConfig>>,
+    interactions: Option<RawInteractionsConfig>,
+    state: Option<RawStateConfig>,
+    /// Directory of the state, history and subscription files
+    data_dir: Option<PathBuf>,
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, serde::Deserialize)]
+#[serde(rename_all = "lowercase")]
+enum StateKind {
+    #[default]
+    Json,
+    Sqlite,
+    Redis,
+}
+
+#[derive(Debug, serde::Deserialize)]
+#[serde(deny_unknown_fields)]
+struct RawStateConfig {
+    #[serde(rename = "type", default)]
+    kind: StateKind,
+    /// Redis connection url
+    url: Option<SecretBox<String>>,
+    /// Redis key of the feed states
+    key: Option<String>,
+    /// JSON or SQLite file, defaults to a file in the data directory
+    path: Option<PathBuf>,
+}
+
+fn parse_state(value: Option<RawStateConfig>, data_dir: &Path) -> crate::Result<StateConfig> {
+    let Some(value) = value else {
+        return Ok(StateConfig::Json {
+            path: data_dir.join(FEED_STATE_FILE),
+        });
+    };
+
+    if value.kind != StateKind::Redis && (value.url.is_some() || value.key.is_some()) {
+        return Err(Error::ConfigVar(format!(
+            "State type {:?} doesn't support url or key",
+            value.kind
+        )));
+    }
+
+    Ok(match value.kind {
+        StateKind::Json => StateConfig::Json {
+            path: value.path.unwrap_or_else(|| data_dir.join(FEED_STATE_FILE)),
+        },
+        StateKind::Sqlite => StateConfig::Sqlite {
+            path: value
+                .path
+                .unwrap_or_else(|| data_dir.join(FEED_STATE_DATABASE)),
+        },
+        StateKind::Redis => {
+            if value.path.is_some() {
+                return Err(Error::ConfigVar(
+                    "State type Redis doesn't support path".to_string(),
+                ));
+            }
+            StateConfig::Redis {
+                url: value
+                    .url
+                    .ok_or_else(|| Error::ConfigVar("State type Redis requires url".to_string()))?,
+                key: value.key.unwrap_or_else(|| DEFAULT_KEY.to_string()),
+            }
+        }
+    })
+}
+
+#[derive(Debug, serde::Deserialize)]
+#[serde(deny_unknown_fields)]
+struct RawInteractionsConfig {
+    /// Hex encoded public key of the Discord application
+    public_key: String,
+    listen: Option<String>,
+    /// Slash commands are only registered if both are set
+    application_id: Option<String>,
+    bot_token: Option<SecretBox<String>>,
+}
+
+impl TryFrom<RawInteractionsConfig> for InteractionsConfig {
+    type Error = crate::Error;
+
+    fn try_from(value: RawInteractionsConfig) -> Result<Self, Self::Error> {
+        let listen = value
+            .listen
+            .unwrap_or_else(|| DEFAULT_INTERACTIONS_LISTEN.to_string());
+        let socket = listen.parse::<SocketAddr>().map_err(|_| {
+            Error::ConfigVar(format!("Invalid interactions listen address: {listen}"))
+        })?;
+
+        let public_key = hex::decode(value.public_key.trim())
+            .ok()
+            .and_then(|key| <[u8; 32]>::try_from(key).ok())
+            .and_then(|key| VerifyingKey::from_bytes(&key).ok())
+            .ok_or_else(|| Error::ConfigVar("Invalid interactions public key".to_string()))?;
+
+        Ok(Self {
+            socket,
+            public_key,
+            application_id: value.application_id,
+            bot_token: value.bot_token,
+        })
+    }
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, serde::Deserialize)]
+#[serde(rename_all = "lowercase")]
+enum NotifierKind {
+    #[default]
+    Discord,
+    Slack,
+    Telegram,
+    Matrix,
+    Webhook,
+}
+
+#[derive(Debug, serde::Deserialize)]
+#[serde(deny_unknown_fields)]
+struct RawDestinationConfig {
+    name: String,
+    #[serde(rename = "type", default)]
+    kind: NotifierKind,
+    /// Discord, Slack or generic webhook url
+    web_hook: Option<SecretBox<String>>,
+    /// Body template of the generic webhook
+    body: Option<String>,
+    bot_token: Option<SecretBox<String>>,
+    chat_id: Option<String>,
+    api_url: Option<String>,
+    homeserver: Option<String>,
+    access_token: Option<SecretBox<String>>,
+    room_id: Option<String>,
+    /// Combine multiple items into one message, only supported by Discord
+    batch: Option<bool>,
+    /// Layout of the messages, only supported by Discord
+    template: Option<TemplateConfig>,
+    /// Pings for matching offers, only supported by Discord
+    mentions: Option<Vec<MentionConfig>>,
+    /// Send the items as a scheduled summary instead of immediately
+    digest: Option<DigestConfig>,
+    feeds: Option<Vec<String>>,
+    filters: Option<FilterConfig>,
+}
+
+impl RawDestinationConfig {
+    fn missing(&self, field: &str) -> Error {
+        Error::ConfigVar(format!(
+            "Destination {} of type {:?} requires {field}",
+            self.name, self.kind
+        ))
+    }
+
+    fn notifier(&mut self) -> crate::Result<NotifierConfig> {
+        for (option, set) in [
+            ("batch", self.batch.is_some()),
+            ("template", self.template.is_some()),
+            ("mentions", self.mentions.is_some()),
+        ] {
+            if set && self.kind != NotifierKind::Discord {
+                return Err(Error::ConfigVar(format!(
+                    "Destination {} of type {:?} doesn't support {option}",
+                    self.name, self.kind
+                )));
+            }
+        }
+
+        let notifier = match self.kind {
+            NotifierKind::Discord => NotifierConfig::Discord {
+                web_hook: self
+                    .web_hook
+                    .take()
+                    .ok_or_else(|| self.missing("web_hook"))?,
+                batch: self.batch.unwrap_or_default(),
+                template: self
+                    .template
+                    .take()
+                    .map(MessageTemplate::try_from)
+                    .transpose()
+                    .map_err(|e| Error::ConfigVar(format!("Destination {}: {e}", self.name)))?,
+                mentions: self
+                    .mentions
+                    .take()
+                    .unwrap_or_default()
+                    .into_iter()
+                    .map(MentionRule::try_from)
+                    .collect::<crate::Result<Vec<MentionRule>>>()
+                    .map_err(|e| Error::ConfigVar(format!("Destination {}: {e}", self.name)))?,
+            },
+            NotifierKind::Slack => NotifierConfig::Slack {
+                web_hook: self
+                    .web_hook
+                    .take()
+                    .ok_or_else(|| self.missing("web_hook"))?,
+            },
+            NotifierKind::Telegram => NotifierConfig::Telegram {
+                api_url: self
+                    .api_url
+                    .take()
+                    .unwrap_or_else(|| telegram::DEFAULT_API_URL.to_string()),
+                bot_token: self
+                    .bot_token
+                    .take()
+                    .ok_or_else(|| self.missing("bot_token"))?,
+                chat_id: self.chat_id.take().ok_or_else(|| self.missing("chat_id"))?,
+            },
+            NotifierKind::Matrix => {
+                let homeserver = self
+                    .homeserver
+                    .take()
+                    .ok_or_else(|| self.missing("homeserver"))?;
+                if let Err(e) = reqwest::Url::parse(&homeserver) {
+                    return Err(Error::ConfigVar(format!(
+                        "Invalid homeserver for destination {}: {e}",
+                        self.name
+                    )));
+                }
+                NotifierConfig::Matrix {
+                    homeserver,
+                    access_token: self
+                        .access_token
+                        .take()
+                        .ok_or_else(|| self.missing("access_token"))?,
+                    room_id: self.room_id.take().ok_or_else(|| self.missing("room_id"))?,
+                }
+            }
+            NotifierKind::Webhook => {
+                let body = self
+                    .body
+                    .take()
+                    .unwrap_or_else(|| webhook::DEFAULT_BODY_TEMPLATE.to_string());
+                webhook::validate_template(&body)
+                    .map_err(|e| Error::ConfigVar(format!("Destination {}: {e}", self.name)))?;
+                NotifierConfig::Webhook {
+                    web_hook: self
+                        .web_hook
+                        .take()
+                        .ok_or_else(|| self.missing("web_hook"))?,
+                    body,
+                }
+            }
+        };
+
+        // The options of the type were taken above, the rest belong to other types
+        for (option, set) in [
+            ("web_hook", self.web_hook.is_some()),
+            ("body", self.body.is_some()),
+            ("bot_token", self.bot_token.is_some()),
+            ("chat_id", self.chat_id.is_some()),
+            ("api_url", self.api_url.is_some()),
+            ("homeserver", self.homeserver.is_some()),
+            ("access_token", self.access_token.is_some()),
+            ("room_id", self.room_id.is_some()),
+        ] {
+            if set {
+                return Err(Error::ConfigVar(format!(
+                    "Destination {} of type {:?} doesn't support {option}",
+                    self.name, self.kind
+                )));
+            }
+        }
+
+        Ok(notifier)
+    }
 }
 
 #[derive(Debug)]
 pub struct Config {
-    pub discord_webhook_url: SecretBox<String>,
     pub check_interval: Duration,
+    pub shutdown_timeout: Duration,
     pub metric_socket: SocketAddr,
+    pub feeds: Vec<FeedConfig>,
+    pub destinations: Vec<DestinationConfig>,
+    /// Endpoint for Discord slash commands, disabled if not set
+    pub interactions: Option<InteractionsConfig>,
+    pub state: StateConfig,
+    pub data_dir: PathBuf,
+}
+
+/// Backend of the feed states
+#[derive(Debug)]
+pub enum StateConfig {
+    Json { path: PathBuf },
+    Sqlite { path: PathBuf },
+    Redis { url: SecretBox<String>, key: String },
+}
+
+#[derive(Debug)]
+pub struct InteractionsConfig {
+    pub socket: SocketAddr,
+    pub public_key: VerifyingKey,
+    pub application_id: Option<String>,
+    pub bot_token: Option<SecretBox<String>>,
+}
+
+#[derive(Debug)]
+pub struct DestinationConfig {
+    pub name: String,
+    pub notifier: NotifierConfig,
+    /// Ids of the subscribed feeds, all feeds if not set
+    pub feeds: Option<Vec<String>>,
+    pub filter: Filter,
+    /// Items are collected and sent at the scheduled times, immediately if not set
+    pub digest: Option<DigestSchedule>,
+}
+
+#[derive(Debug)]
+pub enum NotifierConfig {
+    Discord {
+        web_hook: SecretBox<String>,
+        batch: bool,
+        template: Option<MessageTemplate>,
+        mentions: Vec<MentionRule>,
+    },
+    Slack {
+        web_hook: SecretBox<String>,
+    },
+    Telegram {
+        api_url: String,
+        bot_token: SecretBox<String>,
+        chat_id: String,
+    },
+    Matrix {
+        homeserver: String,
+        access_token: SecretBox<String>,
+        room_id: String,
+    },
+    Webhook {
+        web_hook: SecretBox<String>,
+        body: String,
+    },
+}
+
+#[derive(Debug, Clone, PartialEq, serde::Deserialize)]
+pub struct FeedConfig {
+    /// Stable identifier used to key the feed state, must not change between restarts
+    pub id: String,
+    /// Display name, defaults to the id
+    pub name: Option<String>,
+    pub url: String,
+    /// Locales to fetch, ordered by preference. The url either contains a `{locale}` placeholder
+    /// or the `locale` query parameter is set
+    #[serde(default)]
+    pub locales: Vec<Locale>,
+    #[serde(default = "default_enabled")]
+    pub enabled: bool,
+}
+
+fn default_enabled() -> bool {
+    true
+}
+
+impl FeedConfig {
+    pub fn name(&self) -> &str {
+        self.name.as_deref().unwrap_or(&self.id)
+    }
+
+    fn validate(&self) -> crate::Result<()> {
+        if self.id.trim().is_empty() {
+            return Err(Error::ConfigVar("Feed id can't be empty".to_string()));
+        }
+
+        if let Err(e) = reqwest::Url::parse(&self.url) {
+            return Err(Error::ConfigVar(format!(
+                "Invalid url for feed {}: {e}",
+                self.id
+            )));
+        }
+
+        let unique_locales = self.locales.iter().collect::<HashSet<&Locale>>();
+        if unique_locales.len() != self.locales.len() {
+            return Err(Error::ConfigVar(format!(
+                "Duplicate locale for feed {}",
+                self.id
+            )));
+        }
+
+        Ok(())
+    }
+}
+
+impl Default for FeedConfig {
+    fn default() -> Self {
+        Self {
+            id: DEFAULT_FEED_ID.to_string(),
+            name: Some(DEFAULT_FEED_NAME.to_string()),
+            url: DEFAULT_FEED_URL.to_string(),
+            locales: Vec::new(),
+            enabled: true,
+        }
+    }
+}
+
+fn parse_feeds(feeds: Option<Vec<FeedConfig>>) -> crate::Result<Vec<FeedConfig>> {
+    let feeds = match feeds {
+        Some(feeds) => feeds,
+        None => return Ok(vec![FeedConfig::default()]),
+    };
+
+    let mut ids = HashSet::new();
+    for feed in &feeds {
+        feed.validate()?;
+        if !ids.insert(feed.id.as_str()) {
+            return Err(Error::ConfigVar(format!("Duplicate feed id: {}", feed.id)));
+        }
+    }
+
+    Ok(feeds)
+}
+
+fn parse_destinations(
+    web_hook: Option<SecretBox<String>>,
+    batch: Option<bool>,
+    filters: Option<FilterConfig>,
+    raw_destinations: Option<Vec<RawDestinationConfig>>,
+    feeds: &[FeedConfig],
+) -> crate::Result<Vec<DestinationConfig>> {
+    let mut destinations = Vec::new();
+
+    // Single webhook from the environment, the top level filters apply to it
+    if let Some(web_hook) = web_hook {
+        destinations.push(DestinationConfig {
+            name: DEFAULT_DESTINATION_NAME.to_string(),
+            notifier: NotifierConfig::Discord {
+                web_hook,
+                batch: batch.unwrap_or_default(),
+                template: None,
+                mentions: Vec::new(),
+            },
+            feeds: None,
+            filter: Filter::try_from(filters.unwrap_or_default())?,
+            digest: None,
+        });
+    }
+
+    for mut destination in raw_destinations.unwrap_or_default() {
+        let notifier = destination.notifier()?;
+        let filter = Filter::try_from(destination.filters.unwrap_or_default())
+            .map_err(|e| Error::ConfigVar(format!("Destination {}: {e}", destination.name)))?;
+        let digest = destination
+            .digest
+            .map(DigestSchedule::try_from)
+            .transpose()
+            .map_err(|e| Error::ConfigVar(format!("Destination {}: {e}", destination.name)))?;
+        destinations.push(DestinationConfig {
+            name: destination.name,
+            notifier,
+            feeds: destination.feeds,
+            filter,
+            digest,
+        });
+    }
+
+    if destinations.is_empty() {
+        return Err(Error::ConfigVar(
+            "Either WEB_HOOK or destinations have to be set".to_string(),
+        ));
+    }
+
+    let mut names = HashSet::new();
+    for destination in &destinations {
+        if !names.insert(destination.name.as_str()) {
+            return Err(Error::ConfigVar(format!(
+                "Duplicate destination name: {}",
+                destination.name
+            )));
+        }
+
+        for feed_id in destination.feeds.iter().flatten() {
+            if !feeds.iter().any(|feed| &feed.id == feed_id) {
+                return Err(Error::ConfigVar(format!(
+                    "Destination {} subscribes unknown feed {feed_id}",
+                    destination.name
+                )));
+            }
+        }
+    }
+
+    Ok(destinations)
 }
 
 impl TryFrom<RawConfig> for Config {
@@ -26,6 +528,8 @@ impl TryFrom<RawConfig> for Config {
 
     fn try_from(value: RawConfig) -> Result<Self, Self::Error> {
         let check_interval = Duration::from_secs(value.check_interval);
+        let shutdown_timeout =
+            Duration::from_secs(value.shutdown_timeout.unwrap_or(DEFAULT_SHUTDOWN_TIMEOUT));
         let metric_ip = value
             .metric_ip
             .unwrap_or_else(|| DEFAULT_METRIC_IP.to_string());
@@ -40,17 +544,44 @@ impl TryFrom<RawConfig> for Config {
         };
 
         let metric_socket = SocketAddr::new(metric_ip, metric_port);
+        let feeds = parse_feeds(value.feeds)?;
+        // Destinations can keep subscribing feeds that are disabled for now
+        let destinations = parse_destinations(
+            value.web_hook,
+            value.batch,
+            value.filters,
+            value.destinations,
+            &feeds,
+        )?;
+        let feeds = feeds.into_iter().filter(|feed| feed.enabled).collect();
+        let interactions = value
+            .interactions
+            .map(InteractionsConfig::try_from)
+            .transpose()?;
+        let data_dir = value
+            .data_dir
+            .unwrap_or_else(|| PathBuf::from(DEFAULT_DATA_DIR));
+        let state = parse_state(value.state, &data_dir)?;
         Ok(Self {
-            discord_webhook_url: value.web_hook,
             check_interval,
+            shutdown_timeout,
             metric_socket,
+            feeds,
+            destinations,
+            interactions,
+            state,
+            data_dir,
         })
     }
 }
 
 impl Config {
     pub fn get_configurations() -> crate::Result<Self> {
+        let config_file =
+            std::env::var(ENV_CONFIG_FILE).unwrap_or_else(|_| DEFAULT_CONFIG_FILE.to_string());
+
         config::Config::builder()
+            .add_source(config::File::with_name(&config_file).required(false))
             .add_source(config::Environment::default())
             .build()
             .map_err(|e| Error::custom(format!("Can't parse config: {e}")))?
@@ -58,6 +589,34 @@ impl Config {
             .map_err(|e| Error::custom(format!("Failed to deserialize configuration: {e}")))?
             .try_into()
     }
+
+    /// Fails at startup instead of on the first save if a file can't be written
+    pub fn check_writable(&self) -> crate::Result<()> {
+        let mut directories = vec![self.data_dir.as_path()];
+        if let StateConfig::Json { path } | StateConfig::Sqlite { path } = &self.state {
+            if let Some(parent) = path
+                .parent()
+                .filter(|parent| !parent.as_os_str().is_empty())
+            {
+                directories.push(parent);
+            }
+        }
+
+        for directory in directories {
+            let probe = directory.join(".write_test");
+            std::fs::create_dir_all(directory)
+                .and_then(|_| std::fs::write(&probe, b""))
+                .and_then(|_| std::fs::remove_file(&probe))
+                .map_err(|e| {
+                    Error::custom(format!(
+                        "Data directory {} is not writable: {e}",
+                        directory.display()
+                    ))
+                })?;
+        }
+
+        Ok(())
+    }
 }
 
 #[cfg(test)]
@@ -70,11 +629,39 @@ mod tests {
     const ENV_CHECK_INTERVAL: &str = "CHECK_INTERVAL";
     const ENV_METRIC_IP: &str = "METRIC_IP";
     const ENV_METRIC_PORT: &str = "METRIC_PORT";
+    const ENV_SHUTDOWN_TIMEOUT: &str = "SHUTDOWN_TIMEOUT";
 
     const CORRECT_WEB_HOOK: &str = "https://discord.com/api/webhooks/";
     const CORRECT_CHECK_INTERVAL: &str = "42";
     const CORRECT_METRIC_IP: &str = "127.0.0.1";
     const CORRECT_METRIC_PORT: &str = "9184";
+    const CORRECT_SHUTDOWN_TIMEOUT: &str = "10";
+
+    fn write_config_file(dir: &tempfile::TempDir, content: &str) -> String {
+        let path = dir.path().join("config.yaml");
+        std::fs::write(&path, content).unwrap();
+        path.to_str().unwrap().to_string()
+    }
+
+    fn with_config_file<F: Fn()>(content: &str, f: F) {
+        let dir = tempfile::tempdir().unwrap();
+        let path = write_config_file(&dir, content);
+        temp_env::with_vars(
+            vec![
+                (ENV_CONFIG_FILE, Some(path.as_str())),
+                (ENV_WEB_HOOK, Some(CORRECT_WEB_HOOK)),
+                (ENV_CHECK_INTERVAL, Some(CORRECT_CHECK_INTERVAL)),
+            ],
+            f,
+        );
+    }
+
+    fn discord_web_hook(destination: &DestinationConfig) -> &str {
+        match &destination.notifier {
+            NotifierConfig::Discord { web_hook, .. } => web_hook.expose_secret(),
+            notifier => panic!("Expected Discord notifier, got {notifier:?}"),
+        }
+    }
 
     #[test]
     fn test_from_env_missing_env() {
@@ -84,6 +671,20 @@ mod tests {
         });
     }
 
+    #[test]
+    fn test_from_env_missing_web_hook() {
+        temp_env::with_vars(
+            vec![
+                (ENV_WEB_HOOK, None),
+                (ENV_CHECK_INTERVAL, Some(CORRECT_CHECK_INTERVAL)),
+            ],
+            || {
+                let result = Config::get_configurations();
+                assert!(result.is_err());
+            },
+        );
+    }
+
     #[test]
     fn test_from_env_minimal() {
         temp_env::with_vars(
@@ -96,11 +697,17 @@ mod tests {
                 assert!(result.is_ok());
 
                 let config = result.unwrap();
-                assert_eq!(config.discord_webhook_url.expose_secret(), CORRECT_WEB_HOOK);
+                assert_eq!(config.destinations.len(), 1);
+                assert_eq!(config.destinations[0].name, DEFAULT_DESTINATION_NAME);
+                assert_eq!(discord_web_hook(&config.destinations[0]), CORRECT_WEB_HOOK);
                 assert_eq!(
                     config.check_interval,
                     Duration::from_secs(CORRECT_CHECK_INTERVAL.parse().unwrap())
                 );
+                assert_eq!(
+                    config.shutdown_timeout,
+                    Duration::from_secs(DEFAULT_SHUTDOWN_TIMEOUT)
+                );
             },
         );
     }
@@ -113,13 +720,16 @@ mod tests {
                 (ENV_CHECK_INTERVAL, Some(CORRECT_CHECK_INTERVAL)),
                 (ENV_METRIC_IP, Some(CORRECT_METRIC_IP)),
                 (ENV_METRIC_PORT, Some(CORRECT_METRIC_PORT)),
+                (ENV_SHUTDOWN_TIMEOUT, Some(CORRECT_SHUTDOWN_TIMEOUT)),
             ],
             || {
                 let result = Config::get_configurations();
                 assert!(result.is_ok());
 
                 let config = result.unwrap();
-                assert_eq!(config.discord_webhook_url.expose_secret(), CORRECT_WEB_HOOK);
+                assert_eq!(config.destinations.len(), 1);
+                assert_eq!(config.destinations[0].name, DEFAULT_DESTINATION_NAME);
+                assert_eq!(discord_web_hook(&config.destinations[0]), CORRECT_WEB_HOOK);
                 assert_eq!(
                     config.check_interval,
                     Duration::from_secs(CORRECT_CHECK_INTERVAL.parse().unwrap())
@@ -131,6 +741,10 @@ mod tests {
                         CORRECT_METRIC_PORT.parse().unwrap(),
                     )
                 );
+                assert_eq!(
+                    config.shutdown_timeout,
+                    Duration::from_secs(CORRECT_SHUTDOWN_TIMEOUT.parse().unwrap())
+                );
             },
         );
     }
@@ -191,4 +805,622 @@ mod tests {
             },
         );
     }
+
+    #[test]
+    fn test_default_feeds() {
+        temp_env::with_vars(
+            vec![
+                (ENV_WEB_HOOK, Some(CORRECT_WEB_HOOK)),
+                (ENV_CHECK_INTERVAL, Some(CORRECT_CHECK_INTERVAL)),
+            ],
+            || {
+                let config = Config::get_configurations().unwrap();
+                assert_eq!(config.feeds, vec![FeedConfig::default()]);
+            },
+        );
+    }
+
+    #[test]
+    fn test_feeds_from_file() {
+        let content = r#"
+feeds:
+  - id: netcup-de
+    name: Netcup DE
+    url: https://www.netcup.com/special-offers.xml?locale=de
+  - id: netcup-en
+    url: https://www.netcup.com/special-offers.xml?locale=en
+  - id: disabled
+    url: https://example.com/feed.xml
+    enabled: false
+"#;
+        with_config_file(content, || {
+            let config = Config::get_configurations().unwrap();
+            assert_eq!(config.feeds.len(), 2);
+            assert_eq!(config.feeds[0].id, "netcup-de");
+            assert_eq!(config.feeds[0].name(), "Netcup DE");
+            assert_eq!(config.feeds[1].id, "netcup-en");
+            assert_eq!(config.feeds[1].name(), "netcup-en");
+        });
+    }
+
+    #[test]
+    fn test_feeds_duplicate_id() {
+        let content = r#"
+feeds:
+  - id: netcup
+    url: https://www.netcup.com/special-offers.xml?locale=de
+  - id: netcup
+    url: https://www.netcup.com/special-offers.xml?locale=en
+"#;
+        with_config_file(content, || {
+            assert!(Config::get_configurations().is_err());
+        });
+    }
+
+    #[test]
+    fn test_feeds_locales() {
+        let content = r#"
+feeds:
+  - id: netcup
+    url: https://www.netcup.com/special-offers.xml
+    locales: [en, de]
+"#;
+        with_config_file(content, || {
+            let config = Config::get_configurations().unwrap();
+            assert_eq!(config.feeds[0].locales, vec![Locale::En, Locale::De]);
+        });
+    }
+
+    #[test]
+    fn test_feeds_duplicate_locale() {
+        let content = r#"
+feeds:
+  - id: netcup
+    url: https://www.netcup.com/special-offers.xml
+    locales: [de, de]
+"#;
+        with_config_file(content, || {
+            assert!(Config::get_configurations().is_err());
+        });
+    }
+
+    #[test]
+    fn test_filters_from_file() {
+        let content = r#"
+filters:
+  include_keywords: [Root-Server]
+  title_regex: "^RS"
+  max_price: 20
+  min_ram_gb: 16
+"#;
+        with_config_file(content, || {
+            assert!(Config::get_configurations().is_ok());
+        });
+    }
+
+    #[test]
+    fn test_filters_invalid_regex() {
+        let content = r#"
+filters:
+  title_regex: "("
+"#;
+        with_config_file(content, || {
+            assert!(Config::get_configurations().is_err());
+        });
+    }
+
+    #[test]
+    fn test_filters_unknown_field() {
+        let content = r#"
+filters:
+  max_prize: 20
+"#;
+        with_config_file(content, || {
+            assert!(Config::get_configurations().is_err());
+        });
+    }
+
+    #[test]
+    fn test_destinations_from_file() {
+        let content = r#"
+feeds:
+  - id: netcup-de
+    url: https://www.netcup.com/special-offers.xml?locale=de
+  - id: netcup-en
+    url: https://www.netcup.com/special-offers.xml?locale=en
+destinations:
+  - name: root-servers
+    web_hook: https://discord.com/api/webhooks/1
+    feeds: [netcup-de]
+    filters:
+      min_ram_gb: 16
+  - name: everything
+    web_hook: https://discord.com/api/webhooks/2
+"#;
+        with_config_file(content, || {
+            let config = Config::get_configurations().unwrap();
+            let names = config
+                .destinations
+                .iter()
+                .map(|destination| destination.name.as_str())
+                .collect::<Vec<&str>>();
+            assert_eq!(
+                names,
+                vec![DEFAULT_DESTINATION_NAME, "root-servers", "everything"]
+            );
+            assert_eq!(
+                config.destinations[1].feeds,
+                Some(vec!["netcup-de".to_string()])
+            );
+            assert_eq!(config.destinations[2].feeds, None);
+        });
+    }
+
+    #[test]
+    fn test_destinations_without_web_hook() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = write_config_file(
+            &dir,
+            r#"
+destinations:
+  - name: main
+    web_hook: https://discord.com/api/webhooks/1
+"#,
+        );
+        temp_env::with_vars(
+            vec![
+                (ENV_CONFIG_FILE, Some(path.as_str())),
+                (ENV_WEB_HOOK, None),
+                (ENV_CHECK_INTERVAL, Some(CORRECT_CHECK_INTERVAL)),
+            ],
+            || {
+                let config = Config::get_configurations().unwrap();
+                assert_eq!(config.destinations.len(), 1);
+                assert_eq!(config.destinations[0].name, "main");
+            },
+        );
+    }
+
+    #[test]
+    fn test_destinations_duplicate_name() {
+        let content = r#"
+destinations:
+  - name: default
+    web_hook: https://discord.com/api/webhooks/1
+"#;
+        with_config_file(content, || {
+            assert!(Config::get_configurations().is_err());
+        });
+    }
+
+    #[test]
+    fn test_destinations_unknown_feed() {
+        let content = r#"
+destinations:
+  - name: main
+    web_hook: https://discord.com/api/webhooks/1
+    feeds: [unknown]
+"#;
+        with_config_file(content, || {
+            assert!(Config::get_configurations().is_err());
+        });
+    }
+
+    #[test]
+    fn test_destinations_disabled_feed() {
+        let content = r#"
+feeds:
+  - id: netcup
+    url: https://www.netcup.com/special-offers.xml
+  - id: paused
+    url: https://example.com/feed.xml
+    enabled: false
+destinations:
+  - name: main
+    web_hook: https://discord.com/api/webhooks/1
+    feeds: [netcup, paused]
+"#;
+        with_config_file(content, || {
+            let config = Config::get_configurations().unwrap();
+            assert_eq!(config.feeds.len(), 1);
+            assert_eq!(
+                config.destinations[1].feeds,
+                Some(vec!["netcup".to_string(), "paused".to_string()])
+            );
+        });
+    }
+
+    #[test]
+    fn test_destination_unknown_options() {
+        for option in ["filter: {}", "feed: [netcup]", "chat_id: \"-100123\""] {
+            let content = format!(
+                r#"
+destinations:
+  - name: main
+    web_hook: https://discord.com/api/webhooks/1
+    {option}
+"#
+            );
+            with_config_file(&content, || {
+                assert!(Config::get_configurations().is_err(), "{option}");
+            });
+        }
+    }
+
+    #[test]
+    fn test_destination_types() {
+        let content = r#"
+destinations:
+  - name: slack
+    type: slack
+    web_hook: https://hooks.slack.com/services/T000/B000/XXX
+  - name: telegram
+    type: telegram
+    bot_token: "123:abc"
+    chat_id: -100123
+  - name: matrix
+    type: matrix
+    homeserver: https://matrix.example.com
+    access_token: secret
+    room_id: "!room:example.com"
+  - name: webhook
+    type: webhook
+    web_hook: https://example.com/hook
+    body: '{"text": "{{title}}"}'
+"#;
+        with_config_file(content, || {
+            let config = Config::get_configurations().unwrap();
+            assert_eq!(config.destinations.len(), 5);
+            assert!(matches!(
+                config.destinations[1].notifier,
+                NotifierConfig::Slack { .. }
+            ));
+            match &config.destinations[2].notifier {
+                NotifierConfig::Telegram {
+                    api_url, chat_id, ..
+                } => {
+                    assert_eq!(api_url, telegram::DEFAULT_API_URL);
+                    assert_eq!(chat_id, "-100123");
+                }
+                notifier => panic!("Expected Telegram notifier, got {notifier:?}"),
+            }
+            assert!(matches!(
+                config.destinations[3].notifier,
+                NotifierConfig::Matrix { .. }
+            ));
+            assert!(matches!(
+                config.destinations[4].notifier,
+                NotifierConfig::Webhook { .. }
+            ));
+        });
+    }
+
+    #[test]
+    fn test_destination_batch() {
+        let content = r#"
+batch: true
+destinations:
+  - name: batched
+    web_hook: https://discord.com/api/webhooks/1
+    batch: true
+  - name: single
+    web_hook: https://discord.com/api/webhooks/2
+"#;
+        with_config_file(content, || {
+            let config = Config::get_configurations().unwrap();
+            let batches = config
+                .destinations
+                .iter()
+                .map(|destination| match destination.notifier {
+                    NotifierConfig::Discord { batch, .. } => batch,
+                    _ => panic!("Expected Discord notifier"),
+                })
+                .collect::<Vec<bool>>();
+            assert_eq!(batches, vec![true, true, false]);
+        });
+    }
+
+    #[test]
+    fn test_destination_batch_unsupported() {
+        let content = r#"
+destinations:
+  - name: slack
+    type: slack
+    web_hook: https://hooks.slack.com/services/T000/B000/XXX
+    batch: true
+"#;
+        with_config_file(content, || {
+            assert!(Config::get_configurations().is_err());
+        });
+    }
+
+    #[test]
+    fn test_destination_digest() {
+        let content = r#"
+destinations:
+  - name: daily
+    web_hook: https://discord.com/api/webhooks/1/a
+    digest:
+      schedule: "0 9 * * *"
+      timezone: Europe/Berlin
+  - name: immediate
+    web_hook: https://discord.com/api/webhooks/2/b
+"#;
+        with_config_file(content, || {
+            let config = Config::get_configurations().unwrap();
+
+            let digests = config
+                .destinations
+                .iter()
+                .map(|destination| (destination.name.as_str(), destination.digest.is_some()))
+                .collect::<Vec<_>>();
+            assert_eq!(
+                digests,
+                vec![("default", false), ("daily", true), ("immediate", false)]
+            );
+        });
+    }
+
+    #[test]
+    fn test_destination_invalid_digest() {
+        let content = r#"
+destinations:
+  - name: daily
+    web_hook: https://discord.com/api/webhooks/1/a
+    digest:
+      schedule: "0 9 * * *"
+      timezone: Mars/Olympus
+"#;
+        with_config_file(content, || {
+            let error = Config::get_configurations().unwrap_err();
+            assert!(error.to_string().contains("Destination daily"));
+        });
+    }
+
+    #[test]
+    fn test_destination_template() {
+        let content = r##"
+destinations:
+  - name: templated
+    web_hook: https://discord.com/api/webhooks/1/a
+    template:
+      username: "{{ feed.name }}"
+      color: "#ff0000"
+      fields:
+        - name: Price
+          value: "{{ offer.price }}"
+          inline: true
+"##;
+        with_config_file(content, || {
+            let config = Config::get_configurations().unwrap();
+
+            assert!(matches!(
+                config.destinations[1].notifier,
+                NotifierConfig::Discord {
+                    template: Some(_),
+                    ..
+                }
+            ));
+        });
+    }
+
+    #[test]
+    fn test_destination_invalid_template() {
+        let content = r#"
+destinations:
+  - name: templated
+    web_hook: https://discord.com/api/webhooks/1/a
+    template:
+      title: "{{ item.name }}"
+"#;
+        with_config_file(content, || {
+            let error = Config::get_configurations().unwrap_err().to_string();
+            assert!(error.contains("Destination templated"), "{error}");
+            assert!(error.contains("title"), "{error}");
+        });
+    }
+
+    #[test]
+    fn test_destination_mentions() {
+        let content = r#"
+destinations:
+  - name: pings
+    web_hook: https://discord.com/api/webhooks/1/a
+    mentions:
+      - roles: ["123456789012345678"]
+        when:
+          max_price: 5
+      - here: true
+        when:
+          categories: [Root-Server]
+"#;
+        with_config_file(content, || {
+            let config = Config::get_configurations().unwrap();
+
+            assert!(matches!(
+                &config.destinations[1].notifier,
+                NotifierConfig::Discord { mentions, .. } if mentions.len() == 2
+            ));
+        });
+    }
+
+    #[test]
+    fn test_destination_invalid_mentions() {
+        let content = r#"
+destinations:
+  - name: pings
+    web_hook: https://discord.com/api/webhooks/1/a
+    mentions:
+      - roles: ["@admins"]
+"#;
+        with_config_file(content, || {
+            let error = Config::get_configurations().unwrap_err().to_string();
+            assert!(error.contains("Destination pings"), "{error}");
+        });
+    }
+
+    #[test]
+    fn test_interactions() {
+        let content = r#"
+interactions:
+  public_key: 3b6a27bcceb6a42d62a3a8d02a6f0d73653215771de243a63ac048a18b59da29
+  listen: 127.0.0.1:8081
+"#;
+        with_config_file(content, || {
+            let interactions = Config::get_configurations().unwrap().interactions.unwrap();
+
+            assert_eq!(interactions.socket.to_string(), "127.0.0.1:8081");
+            assert!(interactions.application_id.is_none());
+        });
+    }
+
+    #[test]
+    fn test_state() {
+        with_config_file("", || {
+            let config = Config::get_configurations().unwrap();
+            assert_eq!(config.data_dir, PathBuf::from(DEFAULT_DATA_DIR));
+            match config.state {
+                StateConfig::Json { path } => {
+                    assert_eq!(path, Path::new(DEFAULT_DATA_DIR).join(FEED_STATE_FILE))
+                }
+                state => panic!("Expected JSON state, got {state:?}"),
+            }
+        });
+
+        let content = r#"
+data_dir: /var/lib/netcup-offer-bot
+state:
+  type: sqlite
+"#;
+        with_config_file(content, || {
+            match Config::get_configurations().unwrap().state {
+                StateConfig::Sqlite { path } => assert_eq!(
+                    path,
+                    Path::new("/var/lib/netcup-offer-bot").join(FEED_STATE_DATABASE)
+                ),
+                state => panic!("Expected SQLite state, got {state:?}"),
+            }
+        });
+
+        let content = r#"
+state:
+  path: /state/feeds.json
+"#;
+        with_config_file(content, || {
+            match Config::get_configurations().unwrap().state {
+                StateConfig::Json { path } => assert_eq!(path, Path::new("/state/feeds.json")),
+                state => panic!("Expected JSON state, got {state:?}"),
+            }
+        });
+
+        let content = r#"
+state:
+  type: redis
+  url: redis://localhost:6379
+"#;
+        with_config_file(content, || {
+            match Config::get_configurations().unwrap().state {
+                StateConfig::Redis { url, key } => {
+                    assert_eq!(url.expose_secret(), "redis://localhost:6379");
+                    assert_eq!(key, DEFAULT_KEY);
+                }
+                state => panic!("Expected Redis state, got {state:?}"),
+            }
+        });
+    }
+
+    #[test]
+    fn test_state_invalid() {
+        for content in [
+            "state:\n  type: redis\n",
+            "state:\n  type: sqlite\n  url: redis://localhost:6379\n",
+            "state:\n  type: redis\n  url: redis://localhost:6379\n  path: /state\n",
+        ] {
+            with_config_file(content, || {
+                assert!(Config::get_configurations().is_err());
+            });
+        }
+    }
+
+    #[test]
+    fn test_check_writable() {
+        let dir = tempfile::tempdir().unwrap();
+        let content = format!(
+            "data_dir: {}\nstate:\n  path: {}\n",
+            dir.path().join("data").display(),
+            dir.path().join("state").join("feeds.json").display()
+        );
+        with_config_file(&content, || {
+            Config::get_configurations()
+                .unwrap()
+                .check_writable()
+                .unwrap();
+        });
+        assert!(dir.path().join("data").is_dir());
+        assert!(dir.path().join("state").is_dir());
+        assert!(!dir.path().join("data").join(".write_test").exists());
+
+        // A file where the directory should be
+        std::fs::write(dir.path().join("file"), "").unwrap();
+        let content = format!("data_dir: {}\n", dir.path().join("file").display());
+        with_config_file(&content, || {
+            let error = Config::get_configurations()
+                .unwrap()
+                .check_writable()
+                .unwrap_err()
+                .to_string();
+            assert!(error.contains("not writable"), "{error}");
+        });
+    }
+
+    #[test]
+    fn test_interactions_invalid_public_key() {
+        let content = r#"
+interactions:
+  public_key: not-a-key
+"#;
+        with_config_file(content, || {
+            let error = Config::get_configurations().unwrap_err().to_string();
+            assert!(error.contains("public key"), "{error}");
+        });
+    }
+
+    #[test]
+    fn test_destination_missing_field() {
+        let content = r#"
+destinations:
+  - name: telegram
+    type: telegram
+    bot_token: "123:abc"
+"#;
+        with_config_file(content, || {
+            assert!(Config::get_configurations().is_err());
+        });
+    }
+
+    #[test]
+    fn test_destination_invalid_body_template() {
+        let content = r#"
+destinations:
+  - name: webhook
+    type: webhook
+    web_hook: https://example.com/hook
+    body: '{"text": {{title}}}'
+"#;
+        with_config_file(content, || {
+            assert!(Config::get_configurations().is_err());
+        });
+    }
+
+    #[test]
+    fn test_feeds_invalid_url() {
+        let content = r#"
+feeds:
+  - id: netcup
+    url: not a url
+"#;
+        with_config_file(content, || {
+            assert!(Config::get_configurations().is_err());
+        });
+    }
 }
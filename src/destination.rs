@@ -0,0 +1,472 @@
+use chrono::{DateTime, Utc};
+use reqwest_middleware::ClientWithMiddleware;
+
+use crate::config::DestinationConfig;
+use crate::feed::Feed;
+use crate::feed_item::FeedItem;
+use crate::filter::{Filter, FilterDecision};
+use crate::history::{DeliveryStatus, OfferHistory};
+use crate::metrics;
+use crate::notifier::{self, Notifier};
+use crate::outbox::PendingItem;
+use crate::schedule::DigestSchedule;
+
+/// Named target for offers with its own feed subscriptions and filters
+#[derive(Debug)]
+pub struct Destination {
+    name: String,
+    feeds: Option<Vec<String>>,
+    filter: Filter,
+    notifier: Box<dyn Notifier>,
+    digest: Option<DigestSchedule>,
+    history: Option<OfferHistory>,
+}
+
+impl Destination {
+    pub fn new(
+        name: &str,
+        feeds: Option<Vec<String>>,
+        filter: Filter,
+        notifier: Box<dyn Notifier>,
+    ) -> Self {
+        Self {
+            name: name.to_string(),
+            feeds,
+            filter,
+            notifier,
+            digest: None,
+            history: None,
+        }
+    }
+
+    /// Collect the items and send them as a summary at the scheduled times
+    pub fn with_digest(mut self, digest: Option<DigestSchedule>) -> Self {
+        self.digest = digest;
+        self
+    }
+
+    /// Records the delivery status of every item
+    pub fn with_history(mut self, history: Option<OfferHistory>) -> Self {
+        self.history = history;
+        self
+    }
+
+    pub fn from_config(config: &DestinationConfig, client: ClientWithMiddleware) -> Self {
+        Self::new(
+            &config.name,
+            config.feeds.clone(),
+            config.filter.clone(),
+            notifier::from_config(&config.notifier, client),
+        )
+        .with_digest(config.digest.clone())
+    }
+
+    fn record(
+        &self,
+        feed: &Feed,
+        pending: &PendingItem,
+        status: DeliveryStatus,
+        now: DateTime<Utc>,
+    ) {
+        let Some(history) = &self.history else {
+            return;
+        };
+        if let Err(e) = history.record_delivery(
+            feed,
+            &self.name,
+            &pending.item,
+            status,
+            pending.attempts,
+            now,
+        ) {
+            error!("Error recording delivery status: {e}");
+        }
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn subscribes(&self, feed: &Feed) -> bool {
+        self.feeds
+            .as_ref()
+            .is_none_or(|feeds| feeds.iter().any(|id| id == feed.id()))
+    }
+
+    pub fn filter<'a>(&self, items: &'a [FeedItem]) -> Vec<&'a FeedItem> {
+        items
+            .iter()
+            .filter(|item| {
+                let title = item.title.as_deref().unwrap_or("No title");
+                match self.filter.evaluate(item) {
+                    FilterDecision::Keep(reason) => {
+                        trace!("{}: Keeping item \"{title}\": {reason}", self.name);
+                        true
+                    }
+                    FilterDecision::Drop(reason) => {
+                        trace!("{}: Dropping item \"{title}\": {reason}", self.name);
+                        false
+                    }
+                }
+            })
+            .collect()
+    }
+
+    /// Pending deliveries for the items passing the filter, held back until the next digest if one is configured
+    pub fn enqueue(&self, feed: &Feed, items: &[FeedItem], now: DateTime<Utc>) -> Vec<PendingItem> {
+        if !self.subscribes(feed) {
+            return Vec::new();
+        }
+
+        let items = self.filter(items);
+        if items.is_empty() {
+            debug!("{}: All new items are filtered out", self.name);
+        }
+
+        let next_digest = self
+            .digest
+            .as_ref()
+            .and_then(|digest| digest.next_after(now));
+        items
+            .into_iter()
+            .map(|item| {
+                let mut pending = PendingItem::new(item.clone(), now);
+                pending.next_attempt = next_digest;
+                self.record(feed, &pending, DeliveryStatus::Queued, now);
+                pending
+            })
+            .collect()
+    }
+
+    /// Sends all due items and returns the ones that still have to be delivered.
+    /// Failed items are retried with a backoff and don't stop the remaining items
+    #[tracing::instrument(skip(pending), fields(destination = %self.name))]
+    pub async fn deliver(&self, feed: &Feed, pending: Vec<PendingItem>) -> Vec<PendingItem> {
+        let now = Utc::now();
+        let counter = metrics::DESTINATION_COUNTER.with_label_values(&[&self.name, feed.name()]);
+        let mut remaining = Vec::new();
+        let mut due = Vec::new();
+        for pending in pending {
+            if pending.is_expired(now) {
+                warn!(
+                    "Dropping item \"{}\" for {} after {} failed attempts",
+                    pending.item.title.as_deref().unwrap_or("No title"),
+                    self.name,
+                    pending.attempts
+                );
+                self.record(feed, &pending, DeliveryStatus::Dropped, now);
+            } else if pending.is_due(now) {
+                due.push(pending);
+            } else {
+                remaining.push(pending);
+            }
+        }
+
+        if !due.is_empty() {
+            let items = due
+                .iter()
+                .map(|pending| &pending.item)
+                .collect::<Vec<&FeedItem>>();
+            let deliveries = match self.digest {
+                Some(_) => self.notifier.notify_digest(feed, &items).await,
+                None => self.notifier.notify_all(feed, &items).await,
+            };
+
+            let mut statuses = vec![DeliveryStatus::Delivered; due.len()];
+            for delivery in deliveries {
+                match delivery.result {
+                    Ok(()) => counter.inc_by(delivery.items.len() as u64),
+                    Err(e) if e.is_permanent() => {
+                        error!(
+                            "Dropping {} items for feed {} to {}, they can't be delivered: {}",
+                            delivery.items.len(),
+                            feed.name(),
+                            self.name,
+                            e
+                        );
+                        for index in delivery.items {
+                            statuses[index] = DeliveryStatus::Dropped;
+                        }
+                    }
+                    Err(e) => {
+                        error!(
+                            "Error sending {} items for feed {} to {}: {}",
+                            delivery.items.len(),
+                            feed.name(),
+                            self.name,
+                            e
+                        );
+                        for index in delivery.items {
+                            statuses[index] = DeliveryStatus::Failed;
+                        }
+                    }
+                }
+            }
+
+            for (mut pending, status) in due.into_iter().zip(statuses) {
+                if status == DeliveryStatus::Failed {
+                    pending.record_failure(now);
+                }
+                self.record(feed, &pending, status, now);
+                if status == DeliveryStatus::Failed {
+                    remaining.push(pending);
+                }
+            }
+        }
+
+        metrics::PENDING_DELIVERIES
+            .with_label_values(&[&self.name, feed.name()])
+            .set(remaining.len() as i64);
+
+        remaining
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use wiremock::matchers::method;
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    use crate::filter::FilterConfig;
+    use crate::notifier::discord::DiscordWebhook;
+    use crate::notifier::tests::create_client;
+    use crate::notifier::webhook::{JsonWebhook, DEFAULT_BODY_TEMPLATE};
+    use crate::schedule::DigestConfig;
+
+    use super::*;
+
+    fn create_destination(feeds: Option<Vec<String>>, filter: Filter) -> Destination {
+        Destination::new(
+            "test",
+            feeds,
+            filter,
+            Box::new(DiscordWebhook::new(
+                create_client(),
+                "https://discord.com/api/webhooks/",
+            )),
+        )
+    }
+
+    fn create_feed(id: &str) -> Feed {
+        Feed::new(id, id, "https://example.com/feed.xml")
+    }
+
+    #[test]
+    fn test_subscribes_all() {
+        let destination = create_destination(None, Filter::default());
+
+        assert!(destination.subscribes(&create_feed("netcup")));
+        assert!(destination.subscribes(&create_feed("other")));
+    }
+
+    #[test]
+    fn test_subscribes_selected() {
+        let destination = create_destination(Some(vec!["netcup".to_string()]), Filter::default());
+
+        assert!(destination.subscribes(&create_feed("netcup")));
+        assert!(!destination.subscribes(&create_feed("other")));
+    }
+
+    #[test]
+    fn test_filter() {
+        let filter = Filter::try_from(FilterConfig {
+            include_keywords: vec!["RS".to_string()],
+            ..Default::default()
+        })
+        .unwrap();
+        let destination = create_destination(None, filter);
+        let items = vec![
+            FeedItem {
+                title: Some("RS 1000".to_string()),
+                ..Default::default()
+            },
+            FeedItem {
+                title: Some("VPS 1000".to_string()),
+                ..Default::default()
+            },
+        ];
+
+        let filtered = destination.filter(&items);
+
+        assert_eq!(filtered, vec![&items[0]]);
+    }
+
+    fn create_pending(now: DateTime<Utc>) -> PendingItem {
+        PendingItem::new(
+            FeedItem {
+                title: Some("RS 1000".to_string()),
+                ..Default::default()
+            },
+            now,
+        )
+    }
+
+    async fn create_server(status: u16) -> MockServer {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .respond_with(ResponseTemplate::new(status))
+            .mount(&server)
+            .await;
+        server
+    }
+
+    fn create_webhook_destination(server: &MockServer) -> Destination {
+        Destination::new(
+            "test",
+            None,
+            Filter::default(),
+            Box::new(JsonWebhook::new(
+                create_client(),
+                &server.uri(),
+                DEFAULT_BODY_TEMPLATE,
+            )),
+        )
+    }
+
+    #[test]
+    fn test_enqueue() {
+        let destination = create_destination(Some(vec!["netcup".to_string()]), Filter::default());
+        let items = vec![FeedItem::default()];
+        let now = Utc::now();
+
+        assert_eq!(
+            destination.enqueue(&create_feed("netcup"), &items, now),
+            vec![PendingItem::new(FeedItem::default(), now)]
+        );
+        assert!(destination
+            .enqueue(&create_feed("other"), &items, now)
+            .is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_deliver_success() {
+        let server = create_server(200).await;
+        let destination = create_webhook_destination(&server);
+
+        let remaining = destination
+            .deliver(&create_feed("netcup"), vec![create_pending(Utc::now())])
+            .await;
+
+        assert!(remaining.is_empty());
+        assert_eq!(server.received_requests().await.unwrap().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_deliver_failure_is_retried() {
+        let server = create_server(500).await;
+        let destination = create_webhook_destination(&server);
+
+        let remaining = destination
+            .deliver(&create_feed("netcup"), vec![create_pending(Utc::now())])
+            .await;
+
+        assert_eq!(remaining.len(), 1);
+        assert_eq!(remaining[0].attempts, 1);
+        assert!(remaining[0].next_attempt.is_some());
+
+        // Not due yet, no new request is sent
+        let remaining = destination.deliver(&create_feed("netcup"), remaining).await;
+        assert_eq!(remaining[0].attempts, 1);
+        assert_eq!(server.received_requests().await.unwrap().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_deliver_drops_expired() {
+        let server = create_server(500).await;
+        let destination = create_webhook_destination(&server);
+
+        let remaining = destination
+            .deliver(
+                &create_feed("netcup"),
+                vec![{
+                    let mut pending = create_pending(Utc::now() - chrono::Duration::days(30));
+                    pending.record_failure(Utc::now() - chrono::Duration::days(1));
+                    pending
+                }],
+            )
+            .await;
+
+        assert!(remaining.is_empty());
+        assert!(server.received_requests().await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_deliver_drops_permanent_failure() {
+        let server = create_server(400).await;
+        let destination = Destination::new(
+            "test",
+            None,
+            Filter::default(),
+            Box::new(DiscordWebhook::new(create_client(), &server.uri())),
+        );
+
+        let remaining = destination
+            .deliver(&create_feed("netcup"), vec![create_pending(Utc::now())])
+            .await;
+
+        assert!(remaining.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_deliver_records_history() {
+        let server = create_server(500).await;
+        let history = OfferHistory::open_in_memory().unwrap();
+        let destination = create_webhook_destination(&server).with_history(Some(history.clone()));
+        let feed = create_feed("netcup");
+        let now = Utc::now();
+        let item = create_pending(now).item;
+
+        let pending = destination.enqueue(&feed, std::slice::from_ref(&item), now);
+        assert_eq!(
+            history.delivery_status(&feed, "test", &item).as_deref(),
+            Some("queued")
+        );
+
+        destination.deliver(&feed, pending).await;
+        assert_eq!(
+            history.delivery_status(&feed, "test", &item).as_deref(),
+            Some("failed")
+        );
+    }
+
+    fn create_digest() -> DigestSchedule {
+        DigestSchedule::try_from(DigestConfig {
+            schedule: "0 9 * * *".to_string(),
+            timezone: None,
+        })
+        .unwrap()
+    }
+
+    #[test]
+    fn test_enqueue_digest() {
+        let destination =
+            create_destination(None, Filter::default()).with_digest(Some(create_digest()));
+        let now = Utc::now();
+
+        let pending = destination.enqueue(&create_feed("netcup"), &[FeedItem::default()], now);
+
+        assert_eq!(pending.len(), 1);
+        assert_eq!(pending[0].next_attempt, create_digest().next_after(now));
+        assert!(!pending[0].is_due(now));
+    }
+
+    #[tokio::test]
+    async fn test_deliver_digest() {
+        let server = create_server(200).await;
+        let destination = create_webhook_destination(&server).with_digest(Some(create_digest()));
+        let now = Utc::now();
+
+        let remaining = destination
+            .deliver(
+                &create_feed("netcup"),
+                vec![create_pending(now), create_pending(now)],
+            )
+            .await;
+
+        assert!(remaining.is_empty());
+        let requests = server.received_requests().await.unwrap();
+        assert_eq!(requests.len(), 1);
+        let body: serde_json::Value = serde_json::from_slice(&requests[0].body).unwrap();
+        assert_eq!(body["title"], "2 offers");
+    }
+}
@@ -0,0 +1,65 @@
+/// Line based diff in the format of a `diff` code block, unchanged lines are prefixed with spaces
+pub fn line_diff(before: &str, after: &str) -> String {
+    let before = before.lines().collect::<Vec<&str>>();
+    let after = after.lines().collect::<Vec<&str>>();
+
+    // Longest common subsequence table, descriptions are short enough for the quadratic approach
+    let mut lcs = vec![vec![0usize; after.len() + 1]; before.len() + 1];
+    for i in (0..before.len()).rev() {
+        for j in (0..after.len()).rev() {
+            lcs[i][j] = if before[i] == after[j] {
+                lcs[i + 1][j + 1] + 1
+            } else {
+                lcs[i + 1][j].max(lcs[i][j + 1])
+            };
+        }
+    }
+
+    let mut lines = Vec::new();
+    let (mut i, mut j) = (0, 0);
+    while i < before.len() && j < after.len() {
+        if before[i] == after[j] {
+            lines.push(format!("  {}", before[i]));
+            i += 1;
+            j += 1;
+        } else if lcs[i + 1][j] >= lcs[i][j + 1] {
+            lines.push(format!("- {}", before[i]));
+            i += 1;
+        } else {
+            lines.push(format!("+ {}", after[j]));
+            j += 1;
+        }
+    }
+    lines.extend(before[i..].iter().map(|line| format!("- {line}")));
+    lines.extend(after[j..].iter().map(|line| format!("+ {line}")));
+
+    lines.join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_line_diff_unchanged() {
+        assert_eq!(line_diff("a\nb", "a\nb"), "  a\n  b");
+    }
+
+    #[test]
+    fn test_line_diff_changed_line() {
+        let before = "RS 1000\n8,74 € / Monat\n8 GB RAM";
+        let after = "RS 1000\n7,99 € / Monat\n8 GB RAM";
+
+        assert_eq!(
+            line_diff(before, after),
+            "  RS 1000\n- 8,74 € / Monat\n+ 7,99 € / Monat\n  8 GB RAM"
+        );
+    }
+
+    #[test]
+    fn test_line_diff_added_and_removed() {
+        assert_eq!(line_diff("a\nb", "b\nc"), "- a\n  b\n+ c");
+        assert_eq!(line_diff("", "a"), "+ a");
+        assert_eq!(line_diff("a", ""), "- a");
+    }
+}
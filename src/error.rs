@@ -10,6 +10,18 @@ pub enum Error {
     Parse(#[from] std::num::ParseIntError),
     #[error("Rss: {0}")]
     Rss(String),
+    #[error("Atom: {0}")]
+    Atom(String),
+    #[error("Unsupported feed format: {0}")]
+    UnsupportedFeedFormat(String),
+    #[error("Notifier error: {0}")]
+    Notifier(String),
+    #[error("Message too long: {0}")]
+    MessageTooLong(String),
+    #[error("Discord error: {0}")]
+    Discord(#[from] DiscordError),
+    #[error("Template error: {0}")]
+    Template(#[from] minijinja::Error),
     #[error("Reqwest error")]
     Reqwest(#[from] reqwest::Error),
     #[error("Reqwest middleware error")]
@@ -20,6 +32,10 @@ pub enum Error {
     IO(#[from] std::io::Error),
     #[error("Serde error")]
     Serde(#[from] serde_json::Error),
+    #[error("SQLite error: {0}")]
+    Sqlite(#[from] rusqlite::Error),
+    #[error("Redis error: {0}")]
+    Redis(#[from] redis::RedisError),
     #[error("Prometheus error")]
     Prometheus(#[from] prometheus::Error),
     #[error("Prometheus exporter error")]
@@ -32,6 +48,30 @@ impl Error {
     pub fn custom(msg: String) -> Self {
         Self::Custom(msg)
     }
+
+    /// Whether retrying the same request can't succeed
+    pub fn is_permanent(&self) -> bool {
+        matches!(
+            self,
+            Self::Discord(DiscordError::PayloadTooLarge(_) | DiscordError::BadRequest(_))
+                | Self::MessageTooLong(_)
+                | Self::Template(_)
+        )
+    }
+}
+
+#[derive(Error, Debug)]
+pub enum DiscordError {
+    #[error("Rate limited, retry after {0:?}")]
+    RateLimited(std::time::Duration),
+    #[error("Invalid webhook: {0}")]
+    InvalidWebhook(String),
+    #[error("Payload too large: {0}")]
+    PayloadTooLarge(String),
+    #[error("Bad request: {0}")]
+    BadRequest(String),
+    #[error("Unexpected status {status}: {message}")]
+    Unexpected { status: u16, message: String },
 }
 
 impl From<rss::Error> for Error {
@@ -46,6 +86,12 @@ impl From<rss::validation::ValidationError> for Error {
     }
 }
 
+impl From<atom_syndication::Error> for Error {
+    fn from(err: atom_syndication::Error) -> Self {
+        Self::Atom(err.to_string())
+    }
+}
+
 impl From<std::env::VarError> for Error {
     fn from(err: std::env::VarError) -> Self {
         Self::ConfigVar(err.to_string())
@@ -1,35 +1,140 @@
 use std::fmt;
 
+use reqwest::header::CONTENT_TYPE;
 use reqwest_middleware::ClientWithMiddleware;
-use rss::validation::Validate;
-use rss::Channel;
-use serde::{Deserialize, Serialize};
-use strum_macros::EnumIter;
-
-#[derive(Debug, PartialEq, EnumIter, Clone, Copy, Serialize, Deserialize, Eq, Hash)]
-pub enum Feed {
-    Netcup,
+
+use crate::config::FeedConfig;
+use crate::error::Error;
+use crate::feed_item::FeedItem;
+use crate::feed_parser::FeedFormat;
+use crate::locale::Locale;
+
+const LOCALE_PLACEHOLDER: &str = "{locale}";
+const LOCALE_QUERY_KEY: &str = "locale";
+
+#[derive(Debug, PartialEq, Clone, Eq, Hash)]
+pub struct Feed {
+    id: String,
+    name: String,
+    url: String,
+    locales: Vec<Locale>,
 }
 
 impl Feed {
-    pub fn name(&self) -> &str {
-        match self {
-            Feed::Netcup => "Netcup",
+    pub fn new(id: &str, name: &str, url: &str) -> Self {
+        Self {
+            id: id.to_string(),
+            name: name.to_string(),
+            url: url.to_string(),
+            locales: Vec::new(),
         }
     }
 
+    pub fn with_locales(mut self, locales: Vec<Locale>) -> Self {
+        self.locales = locales;
+        self
+    }
+
+    pub fn id(&self) -> &str {
+        &self.id
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
     pub fn url(&self) -> &str {
-        match self {
-            Feed::Netcup => "https://www.netcup.com/special-offers.xml?locale=de",
+        &self.url
+    }
+
+    /// Configured locales, ordered by preference
+    pub fn locales(&self) -> &[Locale] {
+        &self.locales
+    }
+
+    /// Position of the locale in the preference list, lower is preferred
+    pub fn locale_priority(&self, locale: Option<Locale>) -> usize {
+        locale
+            .and_then(|locale| self.locales.iter().position(|l| *l == locale))
+            .unwrap_or(usize::MAX)
+    }
+
+    pub fn locale_url(&self, locale: Locale) -> String {
+        if self.url.contains(LOCALE_PLACEHOLDER) {
+            return self.url.replace(LOCALE_PLACEHOLDER, locale.code());
+        }
+
+        match reqwest::Url::parse(&self.url) {
+            Ok(mut url) => {
+                let pairs = url
+                    .query_pairs()
+                    .filter(|(key, _)| key != LOCALE_QUERY_KEY)
+                    .map(|(key, value)| (key.into_owned(), value.into_owned()))
+                    .collect::<Vec<(String, String)>>();
+                url.query_pairs_mut()
+                    .clear()
+                    .extend_pairs(pairs)
+                    .append_pair(LOCALE_QUERY_KEY, locale.code());
+                url.to_string()
+            }
+            Err(_) => self.url.clone(),
         }
     }
 
     #[tracing::instrument]
-    pub async fn fetch(&self, client: &ClientWithMiddleware) -> crate::Result<Channel> {
-        let content = client.get(self.url()).send().await?.bytes().await?;
-        let channel = Channel::read_from(&content[..])?;
-        channel.validate()?;
-        Ok(channel)
+    pub async fn fetch(&self, client: &ClientWithMiddleware) -> crate::Result<Vec<FeedItem>> {
+        if self.locales.is_empty() {
+            return Self::fetch_items(client, &self.url).await;
+        }
+
+        // A failing locale only loses its alternates, the offers are usually in the other locales too
+        let mut items = Vec::new();
+        let mut error = None;
+        for locale in &self.locales {
+            match Self::fetch_items(client, &self.locale_url(*locale)).await {
+                Ok(locale_items) => items.extend(
+                    locale_items
+                        .into_iter()
+                        .map(|item| item.with_locale(Some(*locale))),
+                ),
+                Err(e) => {
+                    warn!(
+                        "Error fetching locale {} of feed {}: {e}",
+                        locale.code(),
+                        self
+                    );
+                    error = Some(e);
+                }
+            }
+        }
+
+        match error {
+            Some(e) if items.is_empty() => Err(e),
+            _ => Ok(items),
+        }
+    }
+
+    async fn fetch_items(client: &ClientWithMiddleware, url: &str) -> crate::Result<Vec<FeedItem>> {
+        let response = client.get(url).send().await?;
+        let content_type = response
+            .headers()
+            .get(CONTENT_TYPE)
+            .and_then(|value| value.to_str().ok())
+            .map(str::to_string);
+        let content = response.bytes().await?;
+
+        let format = FeedFormat::detect(content_type.as_deref(), &content).ok_or_else(|| {
+            Error::UnsupportedFeedFormat(content_type.unwrap_or_else(|| "unknown".to_string()))
+        })?;
+        trace!("Detected {:?} feed format for {}", format, url);
+
+        format.parse(&content)
+    }
+}
+
+impl From<&FeedConfig> for Feed {
+    fn from(config: &FeedConfig) -> Self {
+        Self::new(&config.id, config.name(), &config.url).with_locales(config.locales.clone())
     }
 }
 
@@ -38,3 +143,82 @@ impl fmt::Display for Feed {
         write!(f, "{}", self.name())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use reqwest_middleware::ClientBuilder;
+    use wiremock::matchers::query_param;
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    use super::*;
+
+    const NETCUP_DE: &str = include_str!("../tests/fixtures/netcup_de.xml");
+
+    #[test]
+    fn test_locale_url_placeholder() {
+        let feed = Feed::new("netcup", "Netcup", "https://example.com/{locale}/feed.xml");
+
+        assert_eq!(
+            feed.locale_url(Locale::En),
+            "https://example.com/en/feed.xml"
+        );
+    }
+
+    #[test]
+    fn test_locale_url_replace_query() {
+        let feed = Feed::new(
+            "netcup",
+            "Netcup",
+            "https://www.netcup.com/special-offers.xml?locale=de",
+        );
+
+        assert_eq!(
+            feed.locale_url(Locale::En),
+            "https://www.netcup.com/special-offers.xml?locale=en"
+        );
+    }
+
+    #[test]
+    fn test_locale_url_append_query() {
+        let feed = Feed::new("netcup", "Netcup", "https://example.com/feed.xml?a=b");
+
+        assert_eq!(
+            feed.locale_url(Locale::At),
+            "https://example.com/feed.xml?a=b&locale=at"
+        );
+    }
+
+    #[test]
+    fn test_locale_priority() {
+        let feed = Feed::new("netcup", "Netcup", "https://example.com/feed.xml")
+            .with_locales(vec![Locale::En, Locale::De]);
+
+        assert_eq!(feed.locale_priority(Some(Locale::En)), 0);
+        assert_eq!(feed.locale_priority(Some(Locale::De)), 1);
+        assert_eq!(feed.locale_priority(Some(Locale::At)), usize::MAX);
+        assert_eq!(feed.locale_priority(None), usize::MAX);
+    }
+
+    #[tokio::test]
+    async fn test_fetch_failing_locale() {
+        let server = MockServer::start().await;
+        Mock::given(query_param("locale", "de"))
+            .respond_with(ResponseTemplate::new(200).set_body_raw(NETCUP_DE, "application/rss+xml"))
+            .mount(&server)
+            .await;
+        Mock::given(query_param("locale", "en"))
+            .respond_with(ResponseTemplate::new(500))
+            .mount(&server)
+            .await;
+        let client = ClientBuilder::new(reqwest::Client::new()).build();
+        let url = format!("{}/feed.xml", server.uri());
+
+        let feed = Feed::new("netcup", "Netcup", &url).with_locales(vec![Locale::En, Locale::De]);
+        let items = feed.fetch(&client).await.unwrap();
+        assert!(!items.is_empty());
+        assert!(items.iter().all(|item| item.locale == Some(Locale::De)));
+
+        let feed = Feed::new("netcup", "Netcup", &url).with_locales(vec![Locale::En]);
+        assert!(feed.fetch(&client).await.is_err());
+    }
+}
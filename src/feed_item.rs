@@ -0,0 +1,391 @@
+use chrono::{DateTime, FixedOffset};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use crate::locale::Locale;
+use crate::offer::Offer;
+
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+pub struct AlternateLink {
+    pub locale: Locale,
+    pub url: String,
+}
+
+/// Format independent feed entry, created from RSS items, Atom entries and JSON Feed items
+#[derive(Debug, PartialEq, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct FeedItem {
+    pub guid: Option<String>,
+    pub title: Option<String>,
+    pub link: Option<String>,
+    pub description: Option<String>,
+    pub pub_date: Option<DateTime<FixedOffset>>,
+    pub categories: Vec<String>,
+    pub locale: Option<Locale>,
+    pub alternates: Vec<AlternateLink>,
+    /// Content of the already announced version if this item is an update
+    pub previous: Option<ItemSnapshot>,
+}
+
+/// Announced content of an item, used to detect and show edits
+#[derive(Debug, PartialEq, Clone, Default, Serialize, Deserialize)]
+pub struct ItemSnapshot {
+    pub title: Option<String>,
+    pub description: Option<String>,
+}
+
+impl ItemSnapshot {
+    /// Hash of the content, ignoring whitespace only changes
+    pub fn fingerprint(&self) -> String {
+        let mut hasher = Sha256::new();
+        for value in [&self.title, &self.description] {
+            let normalized = value
+                .as_deref()
+                .unwrap_or_default()
+                .split_whitespace()
+                .collect::<Vec<&str>>()
+                .join(" ");
+            hasher.update(normalized);
+            hasher.update([0]);
+        }
+
+        format!("{:x}", hasher.finalize())
+    }
+}
+
+impl FeedItem {
+    pub fn with_locale(mut self, locale: Option<Locale>) -> Self {
+        self.locale = locale;
+        self
+    }
+
+    pub fn snapshot(&self) -> ItemSnapshot {
+        ItemSnapshot {
+            title: self.title.clone(),
+            description: self.description.clone(),
+        }
+    }
+
+    /// Price and specs parsed from the description
+    pub fn offer(&self) -> Offer {
+        self.description
+            .as_deref()
+            .map(Offer::parse)
+            .unwrap_or_default()
+    }
+
+    pub fn is_update(&self) -> bool {
+        self.previous.is_some()
+    }
+
+    /// Stable identity of the item, based on the guid, falling back to the link or a hash of the content
+    pub fn identity(&self) -> String {
+        if let Some(guid) = self.guid.as_deref().filter(|guid| !guid.is_empty()) {
+            return format!("guid:{guid}");
+        }
+
+        if let Some(link) = self.link.as_deref().filter(|link| !link.is_empty()) {
+            return format!("link:{link}");
+        }
+
+        let mut hasher = Sha256::new();
+        for value in [&self.title, &self.description] {
+            hasher.update(value.as_deref().unwrap_or_default());
+            hasher.update([0]);
+        }
+        if let Some(date) = self.pub_date {
+            hasher.update(date.to_rfc3339());
+        }
+        hasher.update([0]);
+        hasher.update(self.categories.join("\n"));
+
+        format!("hash:{:x}", hasher.finalize())
+    }
+
+    /// Locale independent key used to find the same offer in the feeds of different locales
+    pub fn correlation_key(&self) -> Option<String> {
+        let value = self.guid.as_deref().or(self.link.as_deref())?;
+
+        Some(strip_locale(value))
+    }
+
+    /// Attaches the other item as alternate language version of this item
+    pub fn add_alternate(&mut self, other: FeedItem) {
+        if let (Some(locale), Some(url)) = (other.locale, other.link) {
+            self.alternates.push(AlternateLink { locale, url });
+        }
+
+        for alternate in other.alternates {
+            if Some(alternate.locale) != self.locale {
+                self.alternates.push(alternate);
+            }
+        }
+    }
+}
+
+fn strip_locale(value: &str) -> String {
+    let url = match reqwest::Url::parse(value) {
+        Ok(url) => url,
+        Err(_) => return value.to_string(),
+    };
+
+    let path = url
+        .path_segments()
+        .map(|segments| {
+            segments
+                .filter(|segment| Locale::from_code(segment).is_none())
+                .collect::<Vec<&str>>()
+                .join("/")
+        })
+        .unwrap_or_default();
+
+    let query = url
+        .query_pairs()
+        .filter(|(key, _)| key != "locale")
+        .map(|(key, value)| format!("{key}={value}"))
+        .collect::<Vec<String>>()
+        .join("&");
+
+    format!("{}/{}?{}", url.host_str().unwrap_or_default(), path, query)
+}
+
+impl From<rss::Item> for FeedItem {
+    fn from(item: rss::Item) -> Self {
+        let pub_date =
+            item.pub_date
+                .as_deref()
+                .and_then(|date| match DateTime::parse_from_rfc2822(date) {
+                    Ok(date) => Some(date),
+                    Err(e) => {
+                        warn!("Error parsing date {date}: {e}");
+                        None
+                    }
+                });
+
+        Self {
+            guid: item.guid.map(|guid| guid.value),
+            title: item.title,
+            link: item.link,
+            description: item.description.or(item.content),
+            pub_date,
+            categories: item
+                .categories
+                .into_iter()
+                .map(|category| category.name)
+                .collect(),
+            ..Default::default()
+        }
+    }
+}
+
+impl From<atom_syndication::Entry> for FeedItem {
+    fn from(entry: atom_syndication::Entry) -> Self {
+        let link = entry
+            .links()
+            .iter()
+            .find(|link| link.rel() == "alternate")
+            .or_else(|| entry.links().first())
+            .map(|link| link.href().to_string());
+        let description = entry
+            .summary()
+            .map(|summary| summary.as_str().to_string())
+            .or_else(|| {
+                entry
+                    .content()
+                    .and_then(|content| content.value())
+                    .map(str::to_string)
+            });
+
+        Self {
+            guid: Some(entry.id().to_string()),
+            title: Some(entry.title().as_str().to_string()),
+            link,
+            description,
+            pub_date: Some(*entry.published().unwrap_or(entry.updated())),
+            categories: entry
+                .categories()
+                .iter()
+                .map(|category| category.label().unwrap_or(category.term()).to_string())
+                .collect(),
+            ..Default::default()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn create_item(link: &str, locale: Locale) -> FeedItem {
+        FeedItem {
+            link: Some(link.to_string()),
+            locale: Some(locale),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_identity_guid() {
+        let item = FeedItem {
+            guid: Some("offer-42".to_string()),
+            link: Some("https://www.netcup.com/de/deals/rs-1000".to_string()),
+            ..Default::default()
+        };
+
+        assert_eq!(item.identity(), "guid:offer-42");
+    }
+
+    #[test]
+    fn test_identity_link() {
+        let item = FeedItem {
+            guid: Some(String::new()),
+            link: Some("https://www.netcup.com/de/deals/rs-1000".to_string()),
+            ..Default::default()
+        };
+
+        assert_eq!(
+            item.identity(),
+            "link:https://www.netcup.com/de/deals/rs-1000"
+        );
+    }
+
+    #[test]
+    fn test_identity_hash() {
+        let item = FeedItem {
+            title: Some("RS 1000".to_string()),
+            description: Some("Root server".to_string()),
+            ..Default::default()
+        };
+        let same = item.clone();
+        let other = FeedItem {
+            title: Some("RS 2000".to_string()),
+            ..item.clone()
+        };
+
+        assert!(item.identity().starts_with("hash:"));
+        assert_eq!(item.identity(), same.identity());
+        assert_ne!(item.identity(), other.identity());
+    }
+
+    #[test]
+    fn test_fingerprint() {
+        let snapshot = ItemSnapshot {
+            title: Some("RS 1000".to_string()),
+            description: Some("8,74 € / Monat".to_string()),
+        };
+        let whitespace = ItemSnapshot {
+            title: Some(" RS  1000 ".to_string()),
+            description: Some("8,74 €\n/ Monat".to_string()),
+        };
+        let changed = ItemSnapshot {
+            description: Some("7,99 € / Monat".to_string()),
+            ..snapshot.clone()
+        };
+
+        assert_eq!(snapshot.fingerprint(), whitespace.fingerprint());
+        assert_ne!(snapshot.fingerprint(), changed.fingerprint());
+    }
+
+    #[test]
+    fn test_correlation_key_path_locale() {
+        let de = create_item("https://www.netcup.com/de/deals/rs-1000", Locale::De);
+        let en = create_item("https://www.netcup.com/en/deals/rs-1000", Locale::En);
+
+        assert!(de.correlation_key().is_some());
+        assert_eq!(de.correlation_key(), en.correlation_key());
+    }
+
+    #[test]
+    fn test_correlation_key_query_locale() {
+        let de = create_item("https://www.netcup.com/deals?id=42&locale=de", Locale::De);
+        let en = create_item("https://www.netcup.com/deals?locale=en&id=42", Locale::En);
+        let other = create_item("https://www.netcup.com/deals?locale=en&id=43", Locale::En);
+
+        assert_eq!(de.correlation_key(), en.correlation_key());
+        assert_ne!(de.correlation_key(), other.correlation_key());
+    }
+
+    #[test]
+    fn test_correlation_key_prefers_guid() {
+        let item = FeedItem {
+            guid: Some("offer-42".to_string()),
+            link: Some("https://www.netcup.com/de/deals/rs-1000".to_string()),
+            locale: Some(Locale::De),
+            ..Default::default()
+        };
+
+        assert_eq!(item.correlation_key(), Some("offer-42".to_string()));
+    }
+
+    #[test]
+    fn test_correlation_key_none() {
+        let item = FeedItem::default();
+        assert_eq!(item.correlation_key(), None);
+    }
+
+    #[test]
+    fn test_from_rss_item() {
+        let item = rss::Item {
+            guid: Some(rss::Guid {
+                value: "offer-42".to_string(),
+                permalink: false,
+            }),
+            title: Some("RS 1000".to_string()),
+            link: Some("https://www.netcup.com/de/deals/rs-1000".to_string()),
+            description: Some("Description".to_string()),
+            pub_date: Some("Tue, 14 Jan 2025 10:00:00 +0100".to_string()),
+            categories: vec![rss::Category {
+                name: "Root-Server".to_string(),
+                domain: None,
+            }],
+            ..Default::default()
+        };
+
+        let item = FeedItem::from(item);
+
+        assert_eq!(item.guid.as_deref(), Some("offer-42"));
+        assert_eq!(item.title.as_deref(), Some("RS 1000"));
+        assert_eq!(item.description.as_deref(), Some("Description"));
+        assert_eq!(
+            item.pub_date,
+            Some(DateTime::parse_from_rfc2822("Tue, 14 Jan 2025 10:00:00 +0100").unwrap())
+        );
+        assert_eq!(item.categories, vec!["Root-Server".to_string()]);
+    }
+
+    #[test]
+    fn test_from_rss_item_invalid_date() {
+        let item = rss::Item {
+            pub_date: Some("yesterday".to_string()),
+            ..Default::default()
+        };
+
+        assert_eq!(FeedItem::from(item).pub_date, None);
+    }
+
+    #[test]
+    fn test_add_alternate() {
+        let mut de = create_item("https://www.netcup.com/de/deals/rs-1000", Locale::De);
+        let mut en = create_item("https://www.netcup.com/en/deals/rs-1000", Locale::En);
+        en.alternates.push(AlternateLink {
+            locale: Locale::At,
+            url: "https://www.netcup.com/at/deals/rs-1000".to_string(),
+        });
+
+        de.add_alternate(en);
+
+        assert_eq!(
+            de.alternates,
+            vec![
+                AlternateLink {
+                    locale: Locale::En,
+                    url: "https://www.netcup.com/en/deals/rs-1000".to_string(),
+                },
+                AlternateLink {
+                    locale: Locale::At,
+                    url: "https://www.netcup.com/at/deals/rs-1000".to_string(),
+                },
+            ]
+        );
+    }
+}
@@ -0,0 +1,308 @@
+use chrono::{DateTime, FixedOffset};
+use rss::validation::Validate;
+use rss::Channel;
+use serde::Deserialize;
+
+use crate::error::Error;
+use crate::feed_item::FeedItem;
+
+const UTF8_BOM: &str = "\u{feff}";
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum FeedFormat {
+    Rss,
+    Atom,
+    JsonFeed,
+}
+
+impl FeedFormat {
+    /// Detects the format based on the content type, falling back to the root element of the body
+    pub fn detect(content_type: Option<&str>, body: &[u8]) -> Option<Self> {
+        content_type
+            .and_then(Self::from_content_type)
+            .or_else(|| Self::sniff(body))
+    }
+
+    fn from_content_type(content_type: &str) -> Option<Self> {
+        let mime = content_type
+            .split(';')
+            .next()
+            .unwrap_or_default()
+            .trim()
+            .to_ascii_lowercase();
+
+        match mime.as_str() {
+            "application/rss+xml" => Some(Self::Rss),
+            "application/atom+xml" => Some(Self::Atom),
+            "application/feed+json" => Some(Self::JsonFeed),
+            // Generic types like text/xml need to be sniffed
+            _ => None,
+        }
+    }
+
+    fn sniff(body: &[u8]) -> Option<Self> {
+        let text = String::from_utf8_lossy(body);
+        let mut rest = text.trim_start_matches(UTF8_BOM).trim_start();
+
+        if rest.starts_with('{') {
+            return Some(Self::JsonFeed);
+        }
+
+        // Skip xml declaration, processing instructions, comments and doctype
+        while let Some(stripped) = rest.strip_prefix('<') {
+            let end = if stripped.starts_with("!--") {
+                rest.find("-->").map(|end| end + 3)
+            } else if stripped.starts_with('?') || stripped.starts_with('!') {
+                rest.find('>').map(|end| end + 1)
+            } else {
+                let name = stripped
+                    .split(|c: char| c.is_whitespace() || c == '>' || c == '/')
+                    .next()
+                    .unwrap_or_default();
+                // Ignore the namespace prefix
+                let name = name.rsplit(':').next().unwrap_or_default();
+                return match name.to_ascii_lowercase().as_str() {
+                    "rss" | "rdf" => Some(Self::Rss),
+                    "feed" => Some(Self::Atom),
+                    _ => None,
+                };
+            };
+
+            rest = rest[end?..].trim_start();
+        }
+
+        None
+    }
+
+    pub fn parse(&self, body: &[u8]) -> crate::Result<Vec<FeedItem>> {
+        match self {
+            FeedFormat::Rss => {
+                let channel = Channel::read_from(body)?;
+                channel.validate()?;
+                Ok(channel.items.into_iter().map(FeedItem::from).collect())
+            }
+            FeedFormat::Atom => {
+                let feed = atom_syndication::Feed::read_from(body)?;
+                Ok(feed.entries.into_iter().map(FeedItem::from).collect())
+            }
+            FeedFormat::JsonFeed => {
+                let feed: JsonFeed = serde_json::from_slice(body)?;
+                if !feed.version.starts_with(JSON_FEED_VERSION_PREFIX) {
+                    return Err(Error::UnsupportedFeedFormat(format!(
+                        "JSON Feed version {}",
+                        feed.version
+                    )));
+                }
+                Ok(feed.items.into_iter().map(FeedItem::from).collect())
+            }
+        }
+    }
+}
+
+const JSON_FEED_VERSION_PREFIX: &str = "https://jsonfeed.org/version/1";
+
+/// JSON Feed 1.x, only the fields used by the bot
+#[derive(Debug, Deserialize)]
+struct JsonFeed {
+    version: String,
+    #[serde(default)]
+    items: Vec<JsonFeedItem>,
+}
+
+#[derive(Debug, Deserialize)]
+struct JsonFeedItem {
+    id: Option<serde_json::Value>,
+    url: Option<String>,
+    title: Option<String>,
+    content_html: Option<String>,
+    content_text: Option<String>,
+    summary: Option<String>,
+    date_published: Option<String>,
+    date_modified: Option<String>,
+    #[serde(default)]
+    tags: Vec<String>,
+}
+
+impl From<JsonFeedItem> for FeedItem {
+    fn from(item: JsonFeedItem) -> Self {
+        // The spec requires a string id, but some feeds use numbers
+        let guid = item.id.map(|id| match id {
+            serde_json::Value::String(id) => id,
+            id => id.to_string(),
+        });
+        let pub_date = item
+            .date_published
+            .or(item.date_modified)
+            .and_then(|date| parse_rfc3339(&date));
+
+        FeedItem {
+            guid,
+            title: item.title,
+            link: item.url,
+            description: item.content_html.or(item.content_text).or(item.summary),
+            pub_date,
+            categories: item.tags,
+            ..Default::default()
+        }
+    }
+}
+
+fn parse_rfc3339(date: &str) -> Option<DateTime<FixedOffset>> {
+    match DateTime::parse_from_rfc3339(date) {
+        Ok(date) => Some(date),
+        Err(e) => {
+            warn!("Error parsing date {date}: {e}");
+            None
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const RSS: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<!-- Netcup special offers -->
+<rss version="2.0">
+  <channel>
+    <title>Netcup</title>
+    <link>https://www.netcup.com</link>
+    <description>Offers</description>
+    <item>
+      <title>RS 1000</title>
+      <link>https://www.netcup.com/de/deals/rs-1000</link>
+      <guid>rs-1000</guid>
+      <pubDate>Tue, 14 Jan 2025 10:00:00 +0100</pubDate>
+    </item>
+  </channel>
+</rss>"#;
+
+    const ATOM: &str = r#"<?xml version="1.0" encoding="utf-8"?>
+<feed xmlns="http://www.w3.org/2005/Atom">
+  <title>Offers</title>
+  <id>urn:offers</id>
+  <updated>2025-01-14T10:00:00+01:00</updated>
+  <entry>
+    <title>RS 1000</title>
+    <id>urn:offer:rs-1000</id>
+    <link rel="alternate" href="https://example.com/rs-1000"/>
+    <updated>2025-01-15T10:00:00+01:00</updated>
+    <published>2025-01-14T10:00:00+01:00</published>
+    <summary>Root server</summary>
+    <category term="root" label="Root-Server"/>
+  </entry>
+</feed>"#;
+
+    const JSON_FEED: &str = r#"{
+  "version": "https://jsonfeed.org/version/1.1",
+  "title": "Offers",
+  "items": [
+    {
+      "id": "rs-1000",
+      "url": "https://example.com/rs-1000",
+      "title": "RS 1000",
+      "content_html": "<p>Root server</p>",
+      "date_published": "2025-01-14T10:00:00+01:00",
+      "tags": ["Root-Server"]
+    }
+  ]
+}"#;
+
+    fn expected_date() -> Option<DateTime<FixedOffset>> {
+        Some(DateTime::parse_from_rfc3339("2025-01-14T10:00:00+01:00").unwrap())
+    }
+
+    #[test]
+    fn test_detect_content_type() {
+        assert_eq!(
+            FeedFormat::detect(Some("application/rss+xml; charset=utf-8"), b""),
+            Some(FeedFormat::Rss)
+        );
+        assert_eq!(
+            FeedFormat::detect(Some("application/atom+xml"), b""),
+            Some(FeedFormat::Atom)
+        );
+        assert_eq!(
+            FeedFormat::detect(Some("application/feed+json"), b""),
+            Some(FeedFormat::JsonFeed)
+        );
+    }
+
+    #[test]
+    fn test_detect_sniff() {
+        assert_eq!(
+            FeedFormat::detect(Some("text/xml"), RSS.as_bytes()),
+            Some(FeedFormat::Rss)
+        );
+        assert_eq!(
+            FeedFormat::detect(None, ATOM.as_bytes()),
+            Some(FeedFormat::Atom)
+        );
+        assert_eq!(
+            FeedFormat::detect(Some("application/json"), JSON_FEED.as_bytes()),
+            Some(FeedFormat::JsonFeed)
+        );
+        assert_eq!(
+            FeedFormat::detect(None, b"<rdf:RDF xmlns:rdf=\"\"></rdf:RDF>"),
+            Some(FeedFormat::Rss)
+        );
+    }
+
+    #[test]
+    fn test_detect_unknown() {
+        assert_eq!(
+            FeedFormat::detect(Some("text/html"), b"<html></html>"),
+            None
+        );
+        assert_eq!(FeedFormat::detect(None, b""), None);
+        assert_eq!(FeedFormat::detect(None, b"<!-- unterminated"), None);
+    }
+
+    #[test]
+    fn test_parse_rss() {
+        let items = FeedFormat::Rss.parse(RSS.as_bytes()).unwrap();
+
+        assert_eq!(items.len(), 1);
+        assert_eq!(items[0].guid.as_deref(), Some("rs-1000"));
+        assert_eq!(items[0].title.as_deref(), Some("RS 1000"));
+        assert_eq!(items[0].pub_date, expected_date());
+    }
+
+    #[test]
+    fn test_parse_atom() {
+        let items = FeedFormat::Atom.parse(ATOM.as_bytes()).unwrap();
+
+        assert_eq!(items.len(), 1);
+        assert_eq!(items[0].guid.as_deref(), Some("urn:offer:rs-1000"));
+        assert_eq!(items[0].title.as_deref(), Some("RS 1000"));
+        assert_eq!(
+            items[0].link.as_deref(),
+            Some("https://example.com/rs-1000")
+        );
+        assert_eq!(items[0].description.as_deref(), Some("Root server"));
+        assert_eq!(items[0].pub_date, expected_date());
+        assert_eq!(items[0].categories, vec!["Root-Server".to_string()]);
+    }
+
+    #[test]
+    fn test_parse_json_feed() {
+        let items = FeedFormat::JsonFeed.parse(JSON_FEED.as_bytes()).unwrap();
+
+        assert_eq!(items.len(), 1);
+        assert_eq!(items[0].guid.as_deref(), Some("rs-1000"));
+        assert_eq!(items[0].title.as_deref(), Some("RS 1000"));
+        assert_eq!(
+            items[0].link.as_deref(),
+            Some("https://example.com/rs-1000")
+        );
+        assert_eq!(items[0].description.as_deref(), Some("<p>Root server</p>"));
+        assert_eq!(items[0].pub_date, expected_date());
+        assert_eq!(items[0].categories, vec!["Root-Server".to_string()]);
+    }
+
+    #[test]
+    fn test_parse_json_feed_unsupported_version() {
+        let body = r#"{"version": "https://jsonfeed.org/version/2", "items": []}"#;
+        assert!(FeedFormat::JsonFeed.parse(body.as_bytes()).is_err());
+    }
+}
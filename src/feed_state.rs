@@ -1,44 +1,104 @@
-use std::collections::HashMap;
-use std::path::Path;
+use std::collections::{HashMap, HashSet};
 
-use chrono::serde::ts_seconds_option;
-use chrono::{DateTime, FixedOffset, Utc};
-use rss::Item;
+use chrono::serde::{ts_seconds, ts_seconds_option};
+use chrono::{DateTime, Duration, FixedOffset, Utc};
 use serde::{Deserialize, Serialize};
 
+use crate::config::DEFAULT_FEED_ID;
 use crate::feed::Feed;
+use crate::feed_item::{FeedItem, ItemSnapshot};
+use crate::locale::Locale;
+use crate::outbox::PendingItem;
+use crate::state_store::StateStore;
+
+// Key of the netcup feed before feeds became configurable
+const LEGACY_NETCUP_FEED_ID: &str = "Netcup";
+
+// Upper bound of remembered items per feed, the oldest entries are dropped first
+const MAX_SEEN_ITEMS: usize = 1000;
+// Items not present in the feed for this long are forgotten
+const SEEN_ITEM_EXPIRY_DAYS: i64 = 90;
+// Avoid rewriting the state file on every check just to refresh the last seen time
+const SEEN_ITEM_REFRESH_HOURS: i64 = 24;
+
+/// Upgrades of the serialized feed states, applied in order.
+/// The number of applied migrations is stored as `version`, files without it are version 0
+const MIGRATIONS: [fn(&mut serde_json::Value); 1] = [migrate_legacy_ids];
+
+/// Format version written by this version of the bot
+pub const STATE_VERSION: u64 = MIGRATIONS.len() as u64;
+
+/// Feeds were keyed by name before they became configurable
+fn migrate_legacy_ids(content: &mut serde_json::Value) {
+    let Some(feeds) = content
+        .get_mut("feeds")
+        .and_then(|feeds| feeds.as_object_mut())
+    else {
+        return;
+    };
+    if feeds.contains_key(DEFAULT_FEED_ID) {
+        return;
+    }
 
-const FEED_STATE_FILE: &str = "./data/feed_state.json";
+    if let Some(state) = feeds.remove(LEGACY_NETCUP_FEED_ID) {
+        info!("Migrating legacy feed state to feed id {DEFAULT_FEED_ID}");
+        feeds.insert(DEFAULT_FEED_ID.to_string(), state);
+    }
+}
+
+/// Upgrades serialized feed states of an older version, returns whether anything was migrated
+pub fn migrate(content: &mut serde_json::Value) -> crate::Result<bool> {
+    let version = content
+        .get("version")
+        .and_then(|version| version.as_u64())
+        .unwrap_or_default();
+    if version > STATE_VERSION {
+        return Err(crate::Error::custom(format!(
+            "Feed state has version {version}, this version of the bot supports up to {STATE_VERSION}"
+        )));
+    }
+
+    for (index, migration) in MIGRATIONS.iter().enumerate().skip(version as usize) {
+        debug!("Migrating feed state to version {}", index + 1);
+        migration(content);
+    }
+    if let Some(content) = content.as_object_mut() {
+        content.insert("version".to_string(), STATE_VERSION.into());
+    }
+
+    Ok(version < STATE_VERSION)
+}
+
+fn current_version() -> u64 {
+    STATE_VERSION
+}
 
 #[derive(Serialize, Deserialize, Debug, PartialEq)]
 pub struct FeedStates {
-    feeds: HashMap<Feed, FeedState>,
+    /// See [`migrate`]
+    #[serde(default = "current_version")]
+    version: u64,
+    feeds: HashMap<String, FeedState>,
 }
 
-impl FeedStates {
-    pub fn load() -> crate::Result<Self> {
-        let path = Path::new(FEED_STATE_FILE);
-        FeedStates::load_from_path(path)
+impl Default for FeedStates {
+    fn default() -> Self {
+        Self::from_feeds(HashMap::new())
     }
+}
 
-    #[tracing::instrument]
-    fn load_from_path(file: &Path) -> crate::Result<Self> {
-        if file.exists() {
-            info!("Loading feed state from file");
-
-            let content = std::fs::read_to_string(file)?;
-            serde_json::from_str(&content).map_err(|e| e.into())
-        } else {
-            // Ensure that the path exists
-            let prefix = file.parent().ok_or("Invalid FEED_SATE_FILE path")?;
-            std::fs::create_dir_all(prefix)?;
-
-            Ok(Self {
-                feeds: HashMap::new(),
-            })
+impl FeedStates {
+    pub fn from_feeds(feeds: HashMap<String, FeedState>) -> Self {
+        Self {
+            version: STATE_VERSION,
+            feeds,
         }
     }
 
+    pub fn feeds(&self) -> &HashMap<String, FeedState> {
+        &self.feeds
+    }
+
     fn un_dirty(&mut self) {
         for state in self.feeds.values_mut() {
             state.dirty = false;
@@ -46,79 +106,266 @@ impl FeedStates {
     }
 
     pub fn get_feed_or_create(&mut self, feed: &Feed) -> &mut FeedState {
-        self.feeds.entry(*feed).or_default()
+        self.feeds.entry(feed.id().to_string()).or_default()
+    }
+
+    /// Merges the same offer fetched in multiple locales into the item of the preferred locale
+    fn correlate_locales(feed: &Feed, items: Vec<FeedItem>) -> Vec<FeedItem> {
+        if feed.locales().len() < 2 {
+            return items;
+        }
+
+        let mut correlated: Vec<FeedItem> = Vec::new();
+        let mut positions: HashMap<String, usize> = HashMap::new();
+        for item in items {
+            let key = match item.correlation_key() {
+                Some(key) => key,
+                None => {
+                    correlated.push(item);
+                    continue;
+                }
+            };
+
+            match positions.get(&key) {
+                Some(position) => {
+                    let existing = &mut correlated[*position];
+                    if feed.locale_priority(item.locale) < feed.locale_priority(existing.locale) {
+                        let other = std::mem::replace(existing, item);
+                        existing.add_alternate(other);
+                    } else {
+                        existing.add_alternate(item);
+                    }
+                }
+                None => {
+                    positions.insert(key, correlated.len());
+                    correlated.push(item);
+                }
+            }
+        }
+
+        correlated
+    }
+
+    /// Key of the item in the seen items. Offers of feeds with multiple locales are keyed locale
+    /// independent, the preferred locale can be missing in a check
+    fn seen_key(feed: &Feed, item: &FeedItem) -> String {
+        if feed.locales().len() < 2 {
+            return item.identity();
+        }
+
+        match item.correlation_key() {
+            Some(key) => format!("offer:{key}"),
+            None => item.identity(),
+        }
     }
 
     #[tracing::instrument]
-    pub fn get_new_feed(&mut self, feed: &Feed, items: Vec<Item>) -> Vec<Item> {
+    pub fn get_new_feed(&mut self, feed: &Feed, items: Vec<FeedItem>) -> Vec<FeedItem> {
         if items.is_empty() {
             return items;
         }
 
+        let items = Self::correlate_locales(feed, items);
+
+        let now = Utc::now();
         let mut last_date = None;
         let mut sorted = Vec::new();
 
         let feed_state = self.get_feed_or_create(feed);
-
-        for item in items {
-            match &item.pub_date {
-                Some(date) => {
-                    let date = DateTime::parse_from_rfc2822(date);
-                    match date {
-                        Ok(date) => {
-                            if feed_state.is_before(&date) {
-                                trace!("Skipping item, already seen {:?}", date);
-                                continue;
-                            }
-
-                            trace!("Found new item {:?}", date);
-
-                            if last_date.is_none() || date > last_date.unwrap() {
-                                last_date = Some(date);
-                            }
-                            sorted.push(item);
-                        }
-                        Err(e) => {
-                            error!("Error parsing date for feed {}: {}", feed.name(), e);
-                        }
-                    }
+        // States created before the seen items were tracked only know the date watermark
+        let watermark_only = feed_state.seen.is_empty() && feed_state.last_update.is_some();
+
+        let mut current = HashSet::new();
+        for mut item in items {
+            let identity = Self::seen_key(feed, &item);
+            current.insert(identity.clone());
+            // Items seen before the offers were keyed locale independent
+            feed_state.rekey_seen(&item.identity(), &identity);
+
+            let snapshot = item.snapshot();
+            let status = match feed_state.check_locale(feed, &identity, &item, &snapshot, now) {
+                Some(status) => status,
+                None => feed_state.check_seen(&identity, &snapshot, now),
+            };
+            match status {
+                SeenStatus::Unchanged => {
+                    trace!("Skipping item, already seen {}", identity);
+                    continue;
                 }
-                None => {
-                    info!("Skipping item without date on feed {}", feed.name());
+                SeenStatus::Changed(previous) => {
+                    debug!("Found updated item {}", identity);
+                    item.previous = Some(previous);
+                    sorted.push(item);
+                    continue;
                 }
+                SeenStatus::New => {}
             }
+
+            feed_state.mark_seen(identity, snapshot, item.link.clone(), item.locale, now);
+
+            if watermark_only
+                && item
+                    .pub_date
+                    .is_some_and(|date| feed_state.is_before(&date))
+            {
+                trace!("Skipping item, before last update {:?}", item.pub_date);
+                continue;
+            }
+
+            trace!("Found new item {:?}", item.pub_date);
+
+            if let Some(date) = item.pub_date {
+                if last_date.is_none() || date > last_date.unwrap() {
+                    last_date = Some(date);
+                }
+            }
+            sorted.push(item);
         }
 
         // Store new last date if found
         if let Some(date) = last_date {
             // Convert to UTC
             let date = date.with_timezone(&Utc);
-            feed_state.set_last_update(date);
+            if !feed_state.is_before(&date.fixed_offset()) {
+                feed_state.set_last_update(date);
+            }
         }
 
+        feed_state.prune_seen(&current, now);
+
         sorted
     }
 
-    pub fn is_dirty(&self) -> bool {
-        self.feeds.values().any(|state| state.dirty)
+    /// Items of the feed still waiting to be delivered to the destination
+    pub fn outbox(&self, feed: &Feed, destination: &str) -> Vec<PendingItem> {
+        self.feeds
+            .get(feed.id())
+            .and_then(|state| state.outbox.get(destination))
+            .cloned()
+            .unwrap_or_default()
     }
 
-    #[tracing::instrument]
-    pub async fn save(&mut self) -> crate::Result<()> {
-        self.save_to_path(Path::new(FEED_STATE_FILE)).await
+    pub fn enqueue(&mut self, feed: &Feed, destination: &str, pending: Vec<PendingItem>) {
+        if pending.is_empty() {
+            return;
+        }
+
+        let feed_state = self.get_feed_or_create(feed);
+        feed_state
+            .outbox
+            .entry(destination.to_string())
+            .or_default()
+            .extend(pending);
+        feed_state.dirty = true;
     }
 
-    #[tracing::instrument]
-    async fn save_to_path(&mut self, file: &Path) -> crate::Result<()> {
+    pub fn set_outbox(&mut self, feed: &Feed, destination: &str, pending: Vec<PendingItem>) {
+        let feed_state = self.get_feed_or_create(feed);
+        let current = feed_state.outbox.get(destination);
+        if current.map_or(pending.is_empty(), |current| *current == pending) {
+            return;
+        }
+
+        if pending.is_empty() {
+            feed_state.outbox.remove(destination);
+        } else {
+            feed_state.outbox.insert(destination.to_string(), pending);
+        }
+        feed_state.dirty = true;
+    }
+
+    /// Drops pending items of destinations that were removed from the config
+    pub fn retain_destinations(&mut self, destinations: &[&str]) {
+        for (feed_id, state) in self.feeds.iter_mut() {
+            let size = state.outbox.len();
+            state.outbox.retain(|destination, pending| {
+                let keep = destinations.contains(&destination.as_str());
+                if !keep {
+                    warn!(
+                        "Dropping {} pending items of feed {feed_id} for removed destination {destination}",
+                        pending.len()
+                    );
+                }
+                keep
+            });
+            if state.outbox.len() != size {
+                state.dirty = true;
+            }
+        }
+    }
+
+    /// Known offers of the feed, newest first
+    pub fn seen_offers(&self, feed: &Feed) -> Vec<SeenOffer> {
+        let Some(state) = self.feeds.get(feed.id()) else {
+            return Vec::new();
+        };
+
+        let mut offers = state
+            .seen
+            .values()
+            .filter_map(|seen| {
+                let snapshot = seen.snapshot.as_ref()?;
+                Some(SeenOffer {
+                    feed: feed.name().to_string(),
+                    title: snapshot
+                        .title
+                        .clone()
+                        .unwrap_or_else(|| "No title".to_string()),
+                    description: snapshot.description.clone(),
+                    link: seen.link.clone(),
+                    first_seen: seen.first_seen,
+                })
+            })
+            .collect::<Vec<SeenOffer>>();
+        offers.sort_by_key(|offer| std::cmp::Reverse(offer.first_seen));
+        offers
+    }
+
+    pub fn status(&self, feed: &Feed) -> FeedStatus {
+        let state = self.feeds.get(feed.id());
+        FeedStatus {
+            feed: feed.name().to_string(),
+            last_update: state.and_then(|state| state.last_update),
+            known_offers: state.map_or(0, |state| state.seen.len()),
+            pending_deliveries: state.map_or(0, |state| state.outbox.values().map(Vec::len).sum()),
+        }
+    }
+
+    /// Forgets a feed, returns whether it was known
+    pub fn remove(&mut self, feed_id: &str) -> bool {
+        self.feeds.remove(feed_id).is_some()
+    }
+
+    /// Replaces the seen items of a feed by a date, only items published after it are announced
+    pub fn set_watermark(&mut self, feed_id: &str, date: DateTime<Utc>) -> &FeedState {
+        let state = self.feeds.entry(feed_id.to_string()).or_default();
+        state.seen.clear();
+        state.set_last_update(date);
+        state
+    }
+
+    pub fn is_dirty(&self) -> bool {
+        self.feeds.values().any(|state| state.dirty)
+    }
+
+    /// Writes the dirty feeds to the store
+    #[tracing::instrument(skip(store))]
+    pub async fn save(&mut self, store: &dyn StateStore) -> crate::Result<()> {
         if !self.is_dirty() {
             debug!("Feed state is not dirty, skipping save");
             return Ok(());
         }
 
-        debug!("Saving feed state to file");
+        debug!("Saving feed state");
 
-        // Save to file
-        tokio::fs::write(file, serde_json::to_string_pretty(self)?).await?;
+        // One write for all feeds, the backup of the JSON store is the state before this save
+        let dirty = self
+            .feeds
+            .iter()
+            .filter(|(_, state)| state.dirty)
+            .map(|(feed_id, state)| (feed_id.as_str(), state))
+            .collect::<Vec<(&str, &FeedState)>>();
+        store.update(&dirty).await?;
 
         self.un_dirty();
 
@@ -126,22 +373,245 @@ impl FeedStates {
     }
 }
 
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
+pub struct SeenItem {
+    #[serde(with = "ts_seconds")]
+    first_seen: DateTime<Utc>,
+    #[serde(with = "ts_seconds")]
+    last_seen: DateTime<Utc>,
+    /// See [`ItemSnapshot::fingerprint`], missing for items seen before edits were tracked
+    #[serde(default)]
+    fingerprint: Option<String>,
+    #[serde(default)]
+    snapshot: Option<ItemSnapshot>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    link: Option<String>,
+    /// Locale of the snapshot, texts of different locales can't be compared
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    locale: Option<Locale>,
+    /// Last content in the other locales, compared when the item is only found in another locale
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    other_locales: HashMap<Locale, ItemSnapshot>,
+}
+
+impl SeenItem {
+    fn new(
+        snapshot: ItemSnapshot,
+        link: Option<String>,
+        locale: Option<Locale>,
+        now: DateTime<Utc>,
+    ) -> Self {
+        Self {
+            first_seen: now,
+            last_seen: now,
+            fingerprint: Some(snapshot.fingerprint()),
+            snapshot: Some(snapshot),
+            link,
+            locale,
+            other_locales: HashMap::new(),
+        }
+    }
+}
+
+/// Known offer of a feed, used to answer bot commands
+#[derive(Debug, Clone, PartialEq)]
+pub struct SeenOffer {
+    pub feed: String,
+    pub title: String,
+    pub description: Option<String>,
+    pub link: Option<String>,
+    pub first_seen: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct FeedStatus {
+    pub feed: String,
+    pub last_update: Option<DateTime<Utc>>,
+    pub known_offers: usize,
+    pub pending_deliveries: usize,
+}
+
+#[derive(Debug, PartialEq)]
+enum SeenStatus {
+    New,
+    Unchanged,
+    /// Content differs from the last announced version
+    Changed(ItemSnapshot),
+}
+
 #[derive(Serialize, Deserialize, Debug, PartialEq)]
 pub struct FeedState {
     #[serde(with = "ts_seconds_option")]
     last_update: Option<DateTime<Utc>>,
+    /// Identities of all items found in the feed, see [`FeedItem::identity`] and [`FeedStates::seen_key`]
+    #[serde(default)]
+    seen: HashMap<String, SeenItem>,
+    /// Items not yet delivered, keyed by destination name
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    outbox: HashMap<String, Vec<PendingItem>>,
     #[serde(skip_serializing, default)]
     dirty: bool,
 }
 
 impl FeedState {
     fn new(last_update: Option<DateTime<Utc>>, dirty: bool) -> Self {
-        Self { last_update, dirty }
+        Self {
+            last_update,
+            seen: HashMap::new(),
+            outbox: HashMap::new(),
+            dirty,
+        }
+    }
+
+    /// Compares the item against the known items and updates the stored content on changes
+    fn check_seen(
+        &mut self,
+        identity: &str,
+        snapshot: &ItemSnapshot,
+        now: DateTime<Utc>,
+    ) -> SeenStatus {
+        let seen = match self.seen.get_mut(identity) {
+            Some(seen) => seen,
+            None => return SeenStatus::New,
+        };
+
+        if now - seen.last_seen >= Duration::hours(SEEN_ITEM_REFRESH_HOURS) {
+            seen.last_seen = now;
+            self.dirty = true;
+        }
+
+        let fingerprint = snapshot.fingerprint();
+        if seen.fingerprint.as_ref() == Some(&fingerprint) {
+            return SeenStatus::Unchanged;
+        }
+
+        let previous = seen.snapshot.replace(snapshot.clone());
+        let had_fingerprint = seen.fingerprint.replace(fingerprint).is_some();
+        self.dirty = true;
+
+        match previous {
+            Some(previous) if had_fingerprint => SeenStatus::Changed(previous),
+            // Nothing to compare against yet
+            _ => SeenStatus::Unchanged,
+        }
+    }
+
+    /// Compares a known item in another locale than its snapshot against the last content seen in
+    /// that locale, `None` if the locale is the one of the snapshot.
+    /// The snapshot moves to the item if its locale is preferred
+    fn check_locale(
+        &mut self,
+        feed: &Feed,
+        identity: &str,
+        item: &FeedItem,
+        snapshot: &ItemSnapshot,
+        now: DateTime<Utc>,
+    ) -> Option<SeenStatus> {
+        let seen = self.seen.get_mut(identity)?;
+        let Some(locale) = seen.locale else {
+            // Snapshots from before the locale was stored
+            if item.locale.is_some() {
+                seen.locale = item.locale;
+                self.dirty = true;
+            }
+            return None;
+        };
+        let item_locale = item.locale?;
+        if item_locale == locale {
+            return None;
+        }
+
+        if now - seen.last_seen >= Duration::hours(SEEN_ITEM_REFRESH_HOURS) {
+            seen.last_seen = now;
+            self.dirty = true;
+        }
+
+        if feed.locale_priority(item.locale) < feed.locale_priority(Some(locale)) {
+            let previous = seen.snapshot.replace(snapshot.clone());
+            seen.fingerprint = Some(snapshot.fingerprint());
+            seen.link = item.link.clone();
+            seen.locale = item.locale;
+            seen.other_locales.remove(&item_locale);
+            if let Some(previous) = previous {
+                seen.other_locales.insert(locale, previous);
+            }
+            self.dirty = true;
+            return Some(SeenStatus::Unchanged);
+        }
+
+        let status = match seen.other_locales.get(&item_locale) {
+            Some(previous) if previous.fingerprint() == snapshot.fingerprint() => {
+                return Some(SeenStatus::Unchanged);
+            }
+            Some(previous) => SeenStatus::Changed(previous.clone()),
+            // First time the offer is seen in this locale
+            None => SeenStatus::Unchanged,
+        };
+        seen.other_locales.insert(item_locale, snapshot.clone());
+        self.dirty = true;
+        Some(status)
+    }
+
+    /// Moves a seen item to a new identity, unless the new identity is already known
+    fn rekey_seen(&mut self, from: &str, to: &str) {
+        if from == to || self.seen.contains_key(to) {
+            return;
+        }
+        if let Some(seen) = self.seen.remove(from) {
+            self.seen.insert(to.to_string(), seen);
+            self.dirty = true;
+        }
+    }
+
+    fn mark_seen(
+        &mut self,
+        identity: String,
+        snapshot: ItemSnapshot,
+        link: Option<String>,
+        locale: Option<Locale>,
+        now: DateTime<Utc>,
+    ) {
+        self.seen
+            .insert(identity, SeenItem::new(snapshot, link, locale, now));
+        self.dirty = true;
+    }
+
+    /// Drops expired items and the oldest items above the size limit.
+    /// Items of the current fetch are always kept, `last_seen` is only refreshed once a day
+    /// and they would be announced again otherwise
+    fn prune_seen(&mut self, current: &HashSet<String>, now: DateTime<Utc>) {
+        let expiry = now - Duration::days(SEEN_ITEM_EXPIRY_DAYS);
+        let size = self.seen.len();
+        self.seen
+            .retain(|identity, seen| seen.last_seen >= expiry || current.contains(identity));
+
+        if self.seen.len() > MAX_SEEN_ITEMS {
+            let mut by_age = self
+                .seen
+                .iter()
+                .filter(|(identity, _)| !current.contains(*identity))
+                .map(|(identity, seen)| (seen.last_seen, identity.clone()))
+                .collect::<Vec<(DateTime<Utc>, String)>>();
+            by_age.sort();
+
+            let overflow = self.seen.len() - MAX_SEEN_ITEMS;
+            for (_, identity) in by_age.into_iter().take(overflow) {
+                self.seen.remove(&identity);
+            }
+        }
+
+        if self.seen.len() != size {
+            self.dirty = true;
+        }
     }
 
     pub fn is_before(&self, date: &DateTime<FixedOffset>) -> bool {
         self.last_update
-            .map_or(false, |last_update| *date <= last_update)
+            .is_some_and(|last_update| *date <= last_update)
+    }
+
+    pub fn last_update(&self) -> Option<DateTime<Utc>> {
+        self.last_update
     }
 
     pub fn set_last_update(&mut self, date: DateTime<Utc>) {
@@ -163,6 +633,9 @@ mod tests_feed_states {
     use chrono::Duration;
     use tempfile::{tempdir, TempDir};
 
+    use crate::locale::Locale;
+    use crate::state_store::json::JsonFileStore;
+
     use super::*;
 
     struct TestFile {
@@ -183,23 +656,41 @@ mod tests_feed_states {
     }
 
     fn create_empty_feed_states() -> FeedStates {
-        FeedStates {
-            feeds: HashMap::new(),
-        }
+        FeedStates::default()
+    }
+
+    fn create_feed() -> Feed {
+        Feed::new(
+            DEFAULT_FEED_ID,
+            "Netcup",
+            "https://www.netcup.com/special-offers.xml?locale=de",
+        )
     }
 
     fn create_feed_states(dirty: bool) -> FeedStates {
         let mut map = HashMap::new();
         map.insert(
-            Feed::Netcup,
+            DEFAULT_FEED_ID.to_string(),
             FeedState::new(Some(get_current_utc_time()), dirty),
         );
-        FeedStates { feeds: map }
+        FeedStates::from_feeds(map)
+    }
+
+    fn create_rss_item(date: DateTime<Utc>) -> FeedItem {
+        FeedItem {
+            pub_date: Some(date.fixed_offset()),
+            ..Default::default()
+        }
     }
 
-    fn create_rss_item(date: DateTime<Utc>) -> Item {
-        Item {
-            pub_date: Some(date.to_rfc2822()),
+    fn create_localized_item(date: DateTime<Utc>, locale: Locale, offer: &str) -> FeedItem {
+        FeedItem {
+            pub_date: Some(date.fixed_offset()),
+            link: Some(format!(
+                "https://www.netcup.com/{}/deals/{offer}",
+                locale.code()
+            )),
+            locale: Some(locale),
             ..Default::default()
         }
     }
@@ -214,48 +705,155 @@ mod tests_feed_states {
             .with_timezone(&Utc)
     }
 
-    #[test]
-    fn test_load_no_file() {
+    #[tokio::test]
+    async fn test_load_no_file() {
         let test_file = create_temp_file();
-        let config = FeedStates::load_from_path(&test_file.path).unwrap();
+        let config = JsonFileStore::new(&test_file.path).load().await.unwrap();
         assert!(config.feeds.is_empty());
     }
 
-    #[test]
-    fn test_load_with_file() {
+    #[tokio::test]
+    async fn test_load_with_file() {
         let test_file = create_temp_file();
 
         let mut map = HashMap::new();
         // Don't use UTC::now here since nano seconds are not saved during the serialization
         map.insert(
-            Feed::Netcup,
+            DEFAULT_FEED_ID.to_string(),
             FeedState::new(Some(get_current_utc_time()), false),
         );
-        let expected = FeedStates { feeds: map };
+        let expected = FeedStates::from_feeds(map);
         std::fs::write(
             &test_file.path,
             serde_json::to_string_pretty(&expected).unwrap(),
         )
         .unwrap();
 
-        let config = FeedStates::load_from_path(&test_file.path).unwrap();
+        let config = JsonFileStore::new(&test_file.path).load().await.unwrap();
         assert_eq!(config, expected);
     }
 
+    const V0_LEGACY: &str = include_str!("../tests/fixtures/feed_state/v0_legacy.json");
+    const V0_SEEN: &str = include_str!("../tests/fixtures/feed_state/v0_seen.json");
+    const V0_OUTBOX: &str = include_str!("../tests/fixtures/feed_state/v0_outbox.json");
+    const V1: &str = include_str!("../tests/fixtures/feed_state/v1.json");
+
+    fn migrate_fixture(fixture: &str) -> (FeedStates, bool) {
+        let mut content = serde_json::from_str(fixture).unwrap();
+        let migrated = migrate(&mut content).unwrap();
+        (serde_json::from_value(content).unwrap(), migrated)
+    }
+
+    fn timestamp(seconds: i64) -> DateTime<Utc> {
+        DateTime::from_timestamp(seconds, 0).unwrap()
+    }
+
+    #[test]
+    fn test_migrate_legacy() {
+        let (states, migrated) = migrate_fixture(V0_LEGACY);
+
+        assert!(migrated);
+        assert_eq!(states.version, STATE_VERSION);
+        assert!(!states.feeds.contains_key(LEGACY_NETCUP_FEED_ID));
+        assert_eq!(
+            states.feeds[DEFAULT_FEED_ID].last_update,
+            Some(timestamp(1736848800))
+        );
+    }
+
+    #[test]
+    fn test_migrate_seen() {
+        let (states, migrated) = migrate_fixture(V0_SEEN);
+
+        assert!(migrated);
+        assert_eq!(states.feeds.len(), 2);
+        let seen =
+            &states.feeds[DEFAULT_FEED_ID].seen["guid:https://www.netcup.com/de/deals/rs-1000"];
+        assert_eq!(seen.first_seen, timestamp(1736762400));
+        assert_eq!(seen.fingerprint, None);
+        assert_eq!(seen.link, None);
+    }
+
+    #[test]
+    fn test_migrate_outbox() {
+        let (states, migrated) = migrate_fixture(V0_OUTBOX);
+
+        assert!(migrated);
+        let state = &states.feeds[DEFAULT_FEED_ID];
+        let seen = &state.seen["guid:https://www.netcup.com/de/deals/rs-1000"];
+        assert!(seen.snapshot.is_some());
+        assert_eq!(
+            seen.link.as_deref(),
+            Some("https://www.netcup.com/de/deals/rs-1000")
+        );
+        let pending = &state.outbox["main"][0];
+        assert_eq!(pending.item.title.as_deref(), Some("RS 1000 G11"));
+        assert_eq!(pending.attempts, 2);
+    }
+
+    #[test]
+    fn test_migrate_current() {
+        let (states, migrated) = migrate_fixture(V1);
+
+        assert!(!migrated);
+        // Serializing again gives the same document
+        assert_eq!(
+            serde_json::to_value(&states).unwrap(),
+            serde_json::from_str::<serde_json::Value>(V1).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_migrate_newer_version() {
+        let mut content = serde_json::json!({"version": STATE_VERSION + 1, "feeds": {}});
+
+        assert!(migrate(&mut content).is_err());
+    }
+
+    #[test]
+    fn test_set_watermark() {
+        let (mut states, _) = migrate_fixture(V1);
+        let date = timestamp(1736935200);
+
+        let state = states.set_watermark(DEFAULT_FEED_ID, date);
+
+        assert!(state.seen.is_empty());
+        assert_eq!(state.last_update, Some(date));
+        assert!(state.dirty);
+    }
+
+    #[tokio::test]
+    async fn test_load_migrates_file() {
+        let test_file = create_temp_file();
+        std::fs::write(&test_file.path, V0_LEGACY).unwrap();
+
+        let states = JsonFileStore::new(&test_file.path).load().await.unwrap();
+        assert!(states.feeds.contains_key(DEFAULT_FEED_ID));
+
+        // The migrated state is written, the original file is kept as backup
+        let content: serde_json::Value =
+            serde_json::from_str(&std::fs::read_to_string(&test_file.path).unwrap()).unwrap();
+        assert_eq!(content["version"], STATE_VERSION);
+        assert!(content["feeds"].get(LEGACY_NETCUP_FEED_ID).is_none());
+        let mut backup = test_file.path.clone().into_os_string();
+        backup.push(".bak");
+        assert_eq!(std::fs::read_to_string(backup).unwrap(), V0_LEGACY);
+    }
+
     #[test]
     fn test_un_dirty() {
         let mut feed_states = create_feed_states(true);
 
         feed_states.un_dirty();
 
-        assert!(!feed_states.feeds[&Feed::Netcup].dirty);
+        assert!(!feed_states.feeds[DEFAULT_FEED_ID].dirty);
     }
 
     #[test]
     fn test_get_feed_or_create() {
         let mut feed_states = create_empty_feed_states();
 
-        let feed = Feed::Netcup;
+        let feed = create_feed();
         let new_time = get_current_utc_time();
 
         // Initial state
@@ -277,7 +875,7 @@ mod tests_feed_states {
     fn test_get_new_feed_empty() {
         let mut feed_states = create_empty_feed_states();
 
-        let items = feed_states.get_new_feed(&Feed::Netcup, Vec::new());
+        let items = feed_states.get_new_feed(&create_feed(), Vec::new());
         assert!(items.is_empty());
     }
 
@@ -285,7 +883,7 @@ mod tests_feed_states {
     fn test_get_new_feed_first_run() {
         let mut feed_states = create_empty_feed_states();
 
-        let feed = Feed::Netcup;
+        let feed = create_feed();
         let highest_time = get_current_utc_time() + Duration::hours(1);
         let items = vec![
             create_rss_item(get_current_utc_time()),
@@ -294,7 +892,7 @@ mod tests_feed_states {
         let filtered_items = feed_states.get_new_feed(&feed, items.clone());
 
         assert_eq!(items, filtered_items);
-        assert_eq!(feed_states.feeds[&feed].last_update, Some(highest_time));
+        assert_eq!(feed_states.feeds[feed.id()].last_update, Some(highest_time));
         assert!(feed_states.is_dirty());
     }
 
@@ -302,8 +900,8 @@ mod tests_feed_states {
     fn test_get_new_feed_all_before() {
         let mut feed_states = create_feed_states(false);
 
-        let feed = Feed::Netcup;
-        let expected_time = feed_states.feeds[&feed].last_update;
+        let feed = create_feed();
+        let expected_time = feed_states.feeds[feed.id()].last_update;
 
         let mut items = Vec::new();
         for i in 1..10 {
@@ -311,24 +909,27 @@ mod tests_feed_states {
             items.push(create_rss_item(time));
         }
 
-        let filtered_items = feed_states.get_new_feed(&feed, items);
+        let filtered_items = feed_states.get_new_feed(&feed, items.clone());
 
         assert!(filtered_items.is_empty());
-        assert!(!feed_states.is_dirty());
-        assert_eq!(feed_states.feeds[&feed].last_update, expected_time);
+        assert_eq!(feed_states.feeds[feed.id()].last_update, expected_time);
+
+        // Skipped items are remembered, so they stay skipped after the watermark moved on
+        assert!(feed_states.is_dirty());
+        assert_eq!(feed_states.feeds[feed.id()].seen.len(), items.len());
     }
 
     #[test]
     fn test_get_new_feed_all_after() {
         let mut feed_states = create_feed_states(false);
 
-        let feed = Feed::Netcup;
+        let feed = create_feed();
 
         let mut items = Vec::new();
         let mut expected_time = None;
         for i in 1..10 {
             let time = get_current_utc_time() + Duration::hours(i);
-            if expected_time == None || time > expected_time.unwrap() {
+            if expected_time.is_none() || time > expected_time.unwrap() {
                 expected_time = Some(time);
             }
             items.push(create_rss_item(time));
@@ -339,29 +940,29 @@ mod tests_feed_states {
         assert!(feed_states.is_dirty());
         assert_eq!(filtered_items.len(), items.len());
         assert_eq!(filtered_items, items);
-        assert_eq!(feed_states.feeds[&feed].last_update, expected_time);
+        assert_eq!(feed_states.feeds[feed.id()].last_update, expected_time);
     }
 
     #[test]
     fn test_get_new_feed_same_time() {
         let mut feed_states = create_feed_states(false);
 
-        let feed = Feed::Netcup;
+        let feed = create_feed();
 
-        let time = feed_states.feeds[&feed].last_update.unwrap();
+        let time = feed_states.feeds[feed.id()].last_update.unwrap();
         let items = vec![create_rss_item(time), create_rss_item(time)];
 
         let filtered_items = feed_states.get_new_feed(&feed, items.clone());
 
-        assert!(!feed_states.is_dirty());
         assert!(filtered_items.is_empty());
+        assert_eq!(feed_states.feeds[feed.id()].last_update, Some(time));
     }
 
     #[test]
     fn test_get_new_feed() {
         let mut feed_states = create_feed_states(false);
 
-        let feed = Feed::Netcup;
+        let feed = create_feed();
         let mut before = Vec::new();
         for i in 1..10 {
             let time = get_current_utc_time() - Duration::hours(i);
@@ -384,13 +985,255 @@ mod tests_feed_states {
         assert_eq!(filtered_items, after);
     }
 
+    #[test]
+    fn test_get_new_feed_correlate_locales() {
+        let mut feed_states = create_empty_feed_states();
+
+        let feed = create_feed().with_locales(vec![Locale::En, Locale::De]);
+        let time = get_current_utc_time();
+        let items = vec![
+            create_localized_item(time, Locale::De, "rs-1000"),
+            create_localized_item(time, Locale::De, "rs-2000"),
+            create_localized_item(time, Locale::En, "rs-1000"),
+        ];
+
+        let filtered_items = feed_states.get_new_feed(&feed, items);
+
+        assert_eq!(filtered_items.len(), 2);
+
+        // Preferred locale wins, other locale is attached
+        let rs_1000 = &filtered_items[0];
+        assert_eq!(rs_1000.locale, Some(Locale::En));
+        assert_eq!(rs_1000.alternates.len(), 1);
+        assert_eq!(rs_1000.alternates[0].locale, Locale::De);
+        assert_eq!(
+            rs_1000.alternates[0].url,
+            "https://www.netcup.com/de/deals/rs-1000"
+        );
+
+        // Offer only available in one locale is still send
+        let rs_2000 = &filtered_items[1];
+        assert_eq!(rs_2000.locale, Some(Locale::De));
+        assert!(rs_2000.alternates.is_empty());
+    }
+
+    #[test]
+    fn test_get_new_feed_preferred_locale_missing() {
+        let mut feed_states = create_empty_feed_states();
+
+        let feed = create_feed().with_locales(vec![Locale::En, Locale::De]);
+        let time = get_current_utc_time();
+        let item = |locale, description: &str| FeedItem {
+            description: Some(description.to_string()),
+            ..create_localized_item(time, locale, "rs-1000")
+        };
+        let en = item(Locale::En, "8 GB RAM, 9.99 € / month");
+        let de = item(Locale::De, "8 GB RAM, 9,99 € / Monat");
+
+        let filtered_items = feed_states.get_new_feed(&feed, vec![de.clone(), en.clone()]);
+        assert_eq!(filtered_items.len(), 1);
+
+        // Only the other locale in this check, neither new nor an update
+        let filtered_items = feed_states.get_new_feed(&feed, vec![de.clone()]);
+        assert!(filtered_items.is_empty());
+
+        let filtered_items = feed_states.get_new_feed(&feed, vec![de.clone(), en.clone()]);
+        assert!(filtered_items.is_empty());
+
+        let changed = item(Locale::En, "8 GB RAM, 8.99 € / month");
+        let filtered_items = feed_states.get_new_feed(&feed, vec![de, changed]);
+        assert_eq!(filtered_items.len(), 1);
+        assert!(filtered_items[0].is_update());
+    }
+
+    #[test]
+    fn test_get_new_feed_other_locale_changed() {
+        let mut feed_states = create_empty_feed_states();
+
+        let feed = create_feed().with_locales(vec![Locale::En, Locale::De]);
+        let time = get_current_utc_time();
+        let item = |locale, description: &str| FeedItem {
+            description: Some(description.to_string()),
+            ..create_localized_item(time, locale, "rs-1000")
+        };
+        let en = item(Locale::En, "8 GB RAM, 9.99 € / month");
+
+        feed_states.get_new_feed(&feed, vec![en.clone()]);
+        // First time in the other locale, nothing to compare against
+        let filtered_items =
+            feed_states.get_new_feed(&feed, vec![item(Locale::De, "8 GB RAM, 9,99 € / Monat")]);
+        assert!(filtered_items.is_empty());
+
+        // Changed in the other locale while the preferred one is missing
+        let changed = item(Locale::De, "8 GB RAM, 8,99 € / Monat");
+        let filtered_items = feed_states.get_new_feed(&feed, vec![changed.clone()]);
+        assert_eq!(filtered_items.len(), 1);
+        assert_eq!(filtered_items[0].locale, Some(Locale::De));
+        assert_eq!(
+            filtered_items[0]
+                .previous
+                .as_ref()
+                .unwrap()
+                .description
+                .as_deref(),
+            Some("8 GB RAM, 9,99 € / Monat")
+        );
+
+        let filtered_items = feed_states.get_new_feed(&feed, vec![changed]);
+        assert!(filtered_items.is_empty());
+        let filtered_items = feed_states.get_new_feed(&feed, vec![en]);
+        assert!(filtered_items.is_empty());
+
+        let content = serde_json::to_string(&feed_states).unwrap();
+        let loaded = serde_json::from_str::<FeedStates>(&content).unwrap();
+        assert_eq!(serde_json::to_string(&loaded).unwrap(), content);
+        assert!(content.contains(r#""other_locales":{"de":"#));
+    }
+
+    #[test]
+    fn test_get_new_feed_legacy_locale_identity() {
+        let mut feed_states = create_empty_feed_states();
+
+        let feed = create_feed().with_locales(vec![Locale::En, Locale::De]);
+        let time = get_current_utc_time();
+        let en = create_localized_item(time, Locale::En, "rs-1000");
+        let de = create_localized_item(time, Locale::De, "rs-1000");
+        // Seen before the offers were keyed locale independent
+        feed_states.get_feed_or_create(&feed).mark_seen(
+            en.identity(),
+            en.snapshot(),
+            en.link.clone(),
+            None,
+            time,
+        );
+
+        let filtered_items = feed_states.get_new_feed(&feed, vec![de, en.clone()]);
+
+        assert!(filtered_items.is_empty());
+        let seen = &feed_states.get_feed_or_create(&feed).seen;
+        assert!(!seen.contains_key(&en.identity()));
+        assert_eq!(seen.len(), 1);
+    }
+
+    #[test]
+    fn test_get_new_feed_single_locale_not_correlated() {
+        let mut feed_states = create_empty_feed_states();
+
+        let feed = create_feed().with_locales(vec![Locale::De]);
+        let time = get_current_utc_time();
+        let items = vec![
+            create_localized_item(time, Locale::De, "rs-1000"),
+            create_localized_item(time, Locale::En, "rs-1000"),
+        ];
+
+        let filtered_items = feed_states.get_new_feed(&feed, items.clone());
+
+        assert_eq!(filtered_items, items);
+    }
+
+    #[test]
+    fn test_get_new_feed_seen_identity() {
+        let mut feed_states = create_empty_feed_states();
+
+        let feed = create_feed();
+        let time = get_current_utc_time();
+        let item = create_localized_item(time, Locale::De, "rs-1000");
+
+        let filtered_items = feed_states.get_new_feed(&feed, vec![item.clone()]);
+        assert_eq!(filtered_items, vec![item.clone()]);
+        feed_states.un_dirty();
+
+        // Republished with a new date
+        let republished = FeedItem {
+            pub_date: Some((time + Duration::hours(1)).fixed_offset()),
+            ..item.clone()
+        };
+        let filtered_items = feed_states.get_new_feed(&feed, vec![item, republished]);
+        assert!(filtered_items.is_empty());
+        assert!(!feed_states.is_dirty());
+    }
+
+    #[test]
+    fn test_get_new_feed_updated() {
+        let mut feed_states = create_empty_feed_states();
+
+        let feed = create_feed();
+        let item = FeedItem {
+            description: Some("8,74 € / Monat".to_string()),
+            ..create_localized_item(get_current_utc_time(), Locale::De, "rs-1000")
+        };
+        feed_states.get_new_feed(&feed, vec![item.clone()]);
+
+        let updated = FeedItem {
+            description: Some("7,99 € / Monat".to_string()),
+            ..item.clone()
+        };
+        let filtered_items = feed_states.get_new_feed(&feed, vec![updated.clone()]);
+
+        assert_eq!(filtered_items.len(), 1);
+        assert!(filtered_items[0].is_update());
+        assert_eq!(filtered_items[0].previous, Some(item.snapshot()));
+        assert_eq!(filtered_items[0].description, updated.description);
+
+        // Unchanged afterwards
+        let filtered_items = feed_states.get_new_feed(&feed, vec![updated]);
+        assert!(filtered_items.is_empty());
+    }
+
+    #[test]
+    fn test_get_new_feed_backdated() {
+        let mut feed_states = create_empty_feed_states();
+
+        let feed = create_feed();
+        let time = get_current_utc_time();
+        let first = create_localized_item(time, Locale::De, "rs-1000");
+        feed_states.get_new_feed(&feed, vec![first.clone()]);
+
+        let backdated = create_localized_item(time - Duration::days(1), Locale::De, "rs-2000");
+        let filtered_items = feed_states.get_new_feed(&feed, vec![first, backdated.clone()]);
+
+        assert_eq!(filtered_items, vec![backdated]);
+        // Watermark is not moved backwards
+        assert_eq!(feed_states.feeds[feed.id()].last_update, Some(time));
+    }
+
+    #[test]
+    fn test_get_new_feed_without_date() {
+        let mut feed_states = create_feed_states(false);
+
+        let feed = create_feed();
+        let item = FeedItem {
+            title: Some("RS 1000".to_string()),
+            ..Default::default()
+        };
+
+        let filtered_items = feed_states.get_new_feed(&feed, vec![item.clone()]);
+
+        assert_eq!(filtered_items, vec![item]);
+    }
+
+    #[test]
+    fn test_get_new_feed_duplicate_in_batch() {
+        let mut feed_states = create_empty_feed_states();
+
+        let feed = create_feed();
+        let item = create_localized_item(get_current_utc_time(), Locale::De, "rs-1000");
+
+        let filtered_items = feed_states.get_new_feed(&feed, vec![item.clone(), item.clone()]);
+
+        assert_eq!(filtered_items, vec![item]);
+    }
+
     #[tokio::test]
     async fn test_save_no_dirty_empty() {
         let test_file = create_temp_file();
 
         let mut feed_states = create_empty_feed_states();
 
-        feed_states.save_to_path(&test_file.path).await.unwrap();
+        feed_states
+            .save(&JsonFileStore::new(&test_file.path))
+            .await
+            .unwrap();
 
         assert!(!test_file.path.exists());
     }
@@ -401,7 +1244,10 @@ mod tests_feed_states {
 
         let mut feed_states = create_feed_states(false);
 
-        feed_states.save_to_path(&test_file.path).await.unwrap();
+        feed_states
+            .save(&JsonFileStore::new(&test_file.path))
+            .await
+            .unwrap();
 
         assert!(!test_file.path.exists());
     }
@@ -412,10 +1258,144 @@ mod tests_feed_states {
 
         let mut feed_states = create_feed_states(true);
 
-        feed_states.save_to_path(&test_file.path).await.unwrap();
+        feed_states
+            .save(&JsonFileStore::new(&test_file.path))
+            .await
+            .unwrap();
 
         assert!(test_file.path.exists());
     }
+
+    #[tokio::test]
+    async fn test_save_backup_before_save() {
+        let test_file = create_temp_file();
+        let store = JsonFileStore::new(&test_file.path);
+        let mut feed_states = create_feed_states(true);
+        feed_states.save(&store).await.unwrap();
+        let before = std::fs::read_to_string(&test_file.path).unwrap();
+
+        let time = get_current_utc_time() + Duration::hours(1);
+        for feed_id in [DEFAULT_FEED_ID, "other", "third"] {
+            feed_states
+                .feeds
+                .insert(feed_id.to_string(), FeedState::new(Some(time), true));
+        }
+        feed_states.save(&store).await.unwrap();
+
+        // All feeds are written at once, the backup is the state before the save
+        let backup = test_file.dir.path().join("file.json.bak");
+        assert_eq!(std::fs::read_to_string(backup).unwrap(), before);
+        assert_eq!(store.load().await.unwrap(), feed_states);
+    }
+
+    fn create_pending(title: &str) -> PendingItem {
+        PendingItem::new(
+            FeedItem {
+                title: Some(title.to_string()),
+                ..Default::default()
+            },
+            get_current_utc_time(),
+        )
+    }
+
+    #[tokio::test]
+    async fn test_outbox_persisted() {
+        let test_file = create_temp_file();
+        let feed = create_feed();
+
+        let mut feed_states = create_empty_feed_states();
+        feed_states.enqueue(&feed, "main", vec![create_pending("RS 1000")]);
+        assert!(feed_states.is_dirty());
+        feed_states
+            .save(&JsonFileStore::new(&test_file.path))
+            .await
+            .unwrap();
+
+        let loaded = JsonFileStore::new(&test_file.path).load().await.unwrap();
+        assert_eq!(
+            loaded.outbox(&feed, "main"),
+            vec![create_pending("RS 1000")]
+        );
+        assert!(loaded.outbox(&feed, "other").is_empty());
+    }
+
+    #[test]
+    fn test_set_outbox() {
+        let feed = create_feed();
+        let mut feed_states = create_feed_states(false);
+
+        feed_states.set_outbox(&feed, "main", Vec::new());
+        assert!(!feed_states.is_dirty());
+
+        feed_states.set_outbox(&feed, "main", vec![create_pending("RS 1000")]);
+        assert!(feed_states.is_dirty());
+        feed_states.un_dirty();
+
+        feed_states.set_outbox(&feed, "main", vec![create_pending("RS 1000")]);
+        assert!(!feed_states.is_dirty());
+
+        feed_states.set_outbox(&feed, "main", Vec::new());
+        assert!(feed_states.is_dirty());
+        assert!(feed_states.feeds[DEFAULT_FEED_ID].outbox.is_empty());
+    }
+
+    #[test]
+    fn test_retain_destinations() {
+        let feed = create_feed();
+        let mut feed_states = create_empty_feed_states();
+        feed_states.enqueue(&feed, "main", vec![create_pending("RS 1000")]);
+        feed_states.enqueue(&feed, "removed", vec![create_pending("RS 2000")]);
+        feed_states.un_dirty();
+
+        feed_states.retain_destinations(&["main"]);
+
+        assert!(feed_states.is_dirty());
+        assert_eq!(feed_states.outbox(&feed, "main").len(), 1);
+        assert!(feed_states.outbox(&feed, "removed").is_empty());
+    }
+
+    #[test]
+    fn test_seen_offers_and_status() {
+        let feed = create_feed();
+        let mut feed_states = create_feed_states(false);
+        let now = Utc::now();
+        let state = feed_states.get_feed_or_create(&feed);
+        state.mark_seen(
+            "guid:1".to_string(),
+            ItemSnapshot {
+                title: Some("RS 1000".to_string()),
+                description: Some("8,74 €".to_string()),
+            },
+            Some("https://www.netcup.com/de/deals/1".to_string()),
+            None,
+            now - Duration::hours(1),
+        );
+        state.mark_seen(
+            "guid:2".to_string(),
+            ItemSnapshot {
+                title: Some("RS 2000".to_string()),
+                description: Some("9,99 €".to_string()),
+            },
+            None,
+            None,
+            now,
+        );
+        feed_states.enqueue(&feed, "main", vec![create_pending("RS 1000")]);
+
+        let offers = feed_states.seen_offers(&feed);
+        assert_eq!(offers.len(), 2);
+        assert_eq!(offers[0].description.as_deref(), Some("9,99 €"));
+        assert_eq!(offers[0].link, None);
+        assert_eq!(
+            offers[1].link.as_deref(),
+            Some("https://www.netcup.com/de/deals/1")
+        );
+
+        let status = feed_states.status(&feed);
+        assert_eq!(status.feed, "Netcup");
+        assert_eq!(status.known_offers, 2);
+        assert_eq!(status.pending_deliveries, 1);
+    }
 }
 
 #[cfg(test)]
@@ -449,6 +1429,169 @@ mod tests_feed_state {
         assert!(state.dirty);
     }
 
+    fn create_snapshot(description: &str) -> ItemSnapshot {
+        ItemSnapshot {
+            title: Some("RS 1000".to_string()),
+            description: Some(description.to_string()),
+        }
+    }
+
+    #[test]
+    fn test_check_seen() {
+        let mut state = FeedState::default();
+        let now = Utc::now();
+        let snapshot = create_snapshot("8,74 €");
+
+        assert_eq!(state.check_seen("guid:1", &snapshot, now), SeenStatus::New);
+
+        state.mark_seen("guid:1".to_string(), snapshot.clone(), None, None, now);
+        state.dirty = false;
+
+        // Recently seen items are not rewritten
+        assert_eq!(
+            state.check_seen("guid:1", &snapshot, now + Duration::hours(1)),
+            SeenStatus::Unchanged
+        );
+        assert!(!state.dirty);
+
+        let later = now + Duration::hours(SEEN_ITEM_REFRESH_HOURS);
+        assert_eq!(
+            state.check_seen("guid:1", &snapshot, later),
+            SeenStatus::Unchanged
+        );
+        assert!(state.dirty);
+        assert_eq!(state.seen["guid:1"].last_seen, later);
+        assert_eq!(state.seen["guid:1"].first_seen, now);
+    }
+
+    #[test]
+    fn test_check_seen_changed() {
+        let mut state = FeedState::default();
+        let now = Utc::now();
+        let snapshot = create_snapshot("8,74 €");
+        let changed = create_snapshot("7,99 €");
+
+        state.mark_seen("guid:1".to_string(), snapshot.clone(), None, None, now);
+        state.dirty = false;
+
+        assert_eq!(
+            state.check_seen("guid:1", &changed, now),
+            SeenStatus::Changed(snapshot)
+        );
+        assert!(state.dirty);
+
+        // The change is only reported once
+        assert_eq!(
+            state.check_seen("guid:1", &changed, now),
+            SeenStatus::Unchanged
+        );
+    }
+
+    #[test]
+    fn test_check_seen_without_fingerprint() {
+        let mut state = FeedState::default();
+        let now = Utc::now();
+        state.seen.insert(
+            "guid:1".to_string(),
+            SeenItem {
+                first_seen: now,
+                last_seen: now,
+                fingerprint: None,
+                snapshot: None,
+                link: None,
+                locale: None,
+                other_locales: HashMap::new(),
+            },
+        );
+
+        assert_eq!(
+            state.check_seen("guid:1", &create_snapshot("8,74 €"), now),
+            SeenStatus::Unchanged
+        );
+        assert!(state.seen["guid:1"].fingerprint.is_some());
+        assert!(state.dirty);
+    }
+
+    #[test]
+    fn test_prune_seen_expired() {
+        let mut state = FeedState::default();
+        let now = Utc::now();
+
+        state.mark_seen(
+            "guid:old".to_string(),
+            ItemSnapshot::default(),
+            None,
+            None,
+            now - Duration::days(SEEN_ITEM_EXPIRY_DAYS + 1),
+        );
+        state.mark_seen(
+            "guid:new".to_string(),
+            ItemSnapshot::default(),
+            None,
+            None,
+            now,
+        );
+        state.dirty = false;
+
+        state.prune_seen(&HashSet::new(), now);
+
+        assert!(state.dirty);
+        assert!(!state.seen.contains_key("guid:old"));
+        assert!(state.seen.contains_key("guid:new"));
+    }
+
+    #[test]
+    fn test_prune_seen_limit() {
+        let mut state = FeedState::default();
+        let now = Utc::now();
+
+        for i in 0..MAX_SEEN_ITEMS + 10 {
+            state.mark_seen(
+                format!("guid:{i}"),
+                ItemSnapshot::default(),
+                None,
+                None,
+                now - Duration::minutes(i as i64),
+            );
+        }
+
+        state.prune_seen(&HashSet::new(), now);
+
+        assert_eq!(state.seen.len(), MAX_SEEN_ITEMS);
+        // The oldest items are dropped
+        assert!(state.seen.contains_key("guid:0"));
+        assert!(!state
+            .seen
+            .contains_key(&format!("guid:{}", MAX_SEEN_ITEMS + 9)));
+    }
+
+    #[test]
+    fn test_prune_seen_limit_keeps_current() {
+        let mut state = FeedState::default();
+        let now = Utc::now();
+
+        for i in 0..MAX_SEEN_ITEMS + 10 {
+            state.mark_seen(
+                format!("guid:{i}"),
+                ItemSnapshot::default(),
+                None,
+                None,
+                now - Duration::minutes(i as i64),
+            );
+        }
+        // Still in the feed, but last refreshed before the newer items were added
+        let oldest = format!("guid:{}", MAX_SEEN_ITEMS + 9);
+        let current = HashSet::from([oldest.clone()]);
+
+        state.prune_seen(&current, now);
+
+        assert_eq!(state.seen.len(), MAX_SEEN_ITEMS);
+        assert!(state.seen.contains_key(&oldest));
+        assert!(!state
+            .seen
+            .contains_key(&format!("guid:{}", MAX_SEEN_ITEMS + 8)));
+    }
+
     #[test]
     fn test_is_before_none() {
         let state = FeedState::default();
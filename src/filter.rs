@@ -0,0 +1,390 @@
+use regex::Regex;
+use serde::Deserialize;
+
+use crate::error::Error;
+use crate::feed_item::FeedItem;
+use crate::offer::{strip_html, Offer};
+
+/// Raw filter rules as found in the config, all rules have to match for an item to be kept
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct FilterConfig {
+    /// At least one keyword has to be part of the title or description
+    #[serde(default)]
+    pub include_keywords: Vec<String>,
+    /// None of the keywords is allowed in the title or description
+    #[serde(default)]
+    pub exclude_keywords: Vec<String>,
+    pub title_regex: Option<String>,
+    /// At least one category has to match
+    #[serde(default)]
+    pub categories: Vec<String>,
+    pub min_price: Option<f64>,
+    pub max_price: Option<f64>,
+    pub min_vcores: Option<u32>,
+    pub min_ram_gb: Option<f64>,
+    pub min_storage_gb: Option<f64>,
+}
+
+#[derive(Debug, PartialEq)]
+pub enum FilterDecision {
+    Keep(String),
+    Drop(String),
+}
+
+impl FilterDecision {
+    pub fn is_keep(&self) -> bool {
+        matches!(self, FilterDecision::Keep(_))
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct Filter {
+    include_keywords: Vec<String>,
+    exclude_keywords: Vec<String>,
+    title_regex: Option<Regex>,
+    categories: Vec<String>,
+    min_price: Option<f64>,
+    max_price: Option<f64>,
+    min_vcores: Option<u32>,
+    min_ram_gb: Option<f64>,
+    min_storage_gb: Option<f64>,
+}
+
+impl TryFrom<FilterConfig> for Filter {
+    type Error = Error;
+
+    fn try_from(config: FilterConfig) -> Result<Self, Self::Error> {
+        let title_regex = config
+            .title_regex
+            .map(|regex| {
+                Regex::new(&regex)
+                    .map_err(|e| Error::ConfigVar(format!("Invalid title regex {regex}: {e}")))
+            })
+            .transpose()?;
+
+        if let (Some(min), Some(max)) = (config.min_price, config.max_price) {
+            if min > max {
+                return Err(Error::ConfigVar(format!(
+                    "Filter min price {min} is above max price {max}"
+                )));
+            }
+        }
+
+        let lowercase = |values: Vec<String>| {
+            values
+                .into_iter()
+                .map(|value| value.to_lowercase())
+                .collect::<Vec<String>>()
+        };
+
+        Ok(Self {
+            include_keywords: lowercase(config.include_keywords),
+            exclude_keywords: lowercase(config.exclude_keywords),
+            title_regex,
+            categories: lowercase(config.categories),
+            min_price: config.min_price,
+            max_price: config.max_price,
+            min_vcores: config.min_vcores,
+            min_ram_gb: config.min_ram_gb,
+            min_storage_gb: config.min_storage_gb,
+        })
+    }
+}
+
+impl Filter {
+    pub fn evaluate(&self, item: &FeedItem) -> FilterDecision {
+        self.evaluate_offer(item, &item.offer())
+    }
+
+    pub fn evaluate_offer(&self, item: &FeedItem, offer: &Offer) -> FilterDecision {
+        let title = item.title.as_deref().unwrap_or_default();
+        // Keywords match the text of the description, not its markup
+        let text = format!(
+            "{}\n{}",
+            title,
+            item.description
+                .as_deref()
+                .map(strip_html)
+                .unwrap_or_default()
+        )
+        .to_lowercase();
+
+        if !self.include_keywords.is_empty() {
+            match self
+                .include_keywords
+                .iter()
+                .find(|keyword| text.contains(keyword.as_str()))
+            {
+                Some(keyword) => trace!("Found include keyword \"{keyword}\""),
+                None => return FilterDecision::Drop("no include keyword found".to_string()),
+            }
+        }
+
+        if let Some(keyword) = self
+            .exclude_keywords
+            .iter()
+            .find(|keyword| text.contains(keyword.as_str()))
+        {
+            return FilterDecision::Drop(format!("found exclude keyword \"{keyword}\""));
+        }
+
+        if let Some(regex) = &self.title_regex {
+            if !regex.is_match(title) {
+                return FilterDecision::Drop(format!("title doesn't match {regex}"));
+            }
+        }
+
+        if !self.categories.is_empty()
+            && !item
+                .categories
+                .iter()
+                .any(|category| self.categories.contains(&category.to_lowercase()))
+        {
+            return FilterDecision::Drop(format!("categories {:?} don't match", item.categories));
+        }
+
+        let price = offer.price.map(|price| price.amount);
+        if let Some(reason) = check_min("price", price, self.min_price)
+            .or_else(|| check_max("price", price, self.max_price))
+            .or_else(|| {
+                check_min(
+                    "vCores",
+                    offer.vcores.map(f64::from),
+                    self.min_vcores.map(f64::from),
+                )
+            })
+            .or_else(|| check_min("RAM", offer.ram_gb, self.min_ram_gb))
+            .or_else(|| check_min("storage", offer.storage_gb, self.min_storage_gb))
+        {
+            return FilterDecision::Drop(reason);
+        }
+
+        FilterDecision::Keep("all rules matched".to_string())
+    }
+}
+
+fn check_min(name: &str, value: Option<f64>, min: Option<f64>) -> Option<String> {
+    let min = min?;
+    match value {
+        Some(value) if value >= min => None,
+        Some(value) => Some(format!("{name} {value} is below {min}")),
+        None => Some(format!("{name} is unknown")),
+    }
+}
+
+fn check_max(name: &str, value: Option<f64>, max: Option<f64>) -> Option<String> {
+    let max = max?;
+    match value {
+        Some(value) if value <= max => None,
+        Some(value) => Some(format!("{name} {value} is above {max}")),
+        None => Some(format!("{name} is unknown")),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn create_item() -> FeedItem {
+        FeedItem {
+            title: Some("RS 4000 G11".to_string()),
+            description: Some(
+                "12 dedizierte Kerne, 32 GB DDR5 ECC RAM, 1 TB NVMe, 26,44 € / Monat".to_string(),
+            ),
+            categories: vec!["Root-Server".to_string()],
+            ..Default::default()
+        }
+    }
+
+    fn create_filter(config: FilterConfig) -> Filter {
+        Filter::try_from(config).unwrap()
+    }
+
+    #[test]
+    fn test_empty_filter_keeps() {
+        let filter = Filter::default();
+
+        assert!(filter.evaluate(&create_item()).is_keep());
+    }
+
+    #[test]
+    fn test_include_keywords() {
+        let filter = create_filter(FilterConfig {
+            include_keywords: vec!["VPS".to_string(), "rs 4000".to_string()],
+            ..Default::default()
+        });
+        assert!(filter.evaluate(&create_item()).is_keep());
+
+        let filter = create_filter(FilterConfig {
+            include_keywords: vec!["ARM".to_string()],
+            ..Default::default()
+        });
+        assert_eq!(
+            filter.evaluate(&create_item()),
+            FilterDecision::Drop("no include keyword found".to_string())
+        );
+    }
+
+    #[test]
+    fn test_exclude_keywords() {
+        let filter = create_filter(FilterConfig {
+            exclude_keywords: vec!["ddr5".to_string()],
+            ..Default::default()
+        });
+
+        assert_eq!(
+            filter.evaluate(&create_item()),
+            FilterDecision::Drop("found exclude keyword \"ddr5\"".to_string())
+        );
+    }
+
+    #[test]
+    fn test_keywords_html_description() {
+        let item = FeedItem {
+            description: Some(
+                "<p><strong>RS&nbsp;8000</strong> with <b>DDR5</b> &amp; NVMe</p>".to_string(),
+            ),
+            ..create_item()
+        };
+
+        let filter = create_filter(FilterConfig {
+            exclude_keywords: vec!["strong".to_string(), "nbsp".to_string()],
+            ..Default::default()
+        });
+        assert!(filter.evaluate(&item).is_keep());
+
+        let filter = create_filter(FilterConfig {
+            include_keywords: vec!["RS 8000 with DDR5 & NVMe".to_string()],
+            ..Default::default()
+        });
+        assert!(filter.evaluate(&item).is_keep());
+
+        let item = FeedItem {
+            description: Some("RS <b>8000</b>".to_string()),
+            ..create_item()
+        };
+        let filter = create_filter(FilterConfig {
+            include_keywords: vec!["rs 8000".to_string()],
+            ..Default::default()
+        });
+        assert!(filter.evaluate(&item).is_keep());
+
+        let item = FeedItem {
+            description: Some("RS&#160;8000 f&uuml;r 3&ndash;12 Monate".to_string()),
+            ..create_item()
+        };
+        let filter = create_filter(FilterConfig {
+            include_keywords: vec!["RS 8000 für 3–12".to_string()],
+            ..Default::default()
+        });
+        assert!(filter.evaluate(&item).is_keep());
+    }
+
+    #[test]
+    fn test_title_regex() {
+        let filter = create_filter(FilterConfig {
+            title_regex: Some(r"^RS \d+".to_string()),
+            ..Default::default()
+        });
+        assert!(filter.evaluate(&create_item()).is_keep());
+
+        let filter = create_filter(FilterConfig {
+            title_regex: Some(r"^VPS".to_string()),
+            ..Default::default()
+        });
+        assert!(!filter.evaluate(&create_item()).is_keep());
+    }
+
+    #[test]
+    fn test_invalid_title_regex() {
+        let result = Filter::try_from(FilterConfig {
+            title_regex: Some("(".to_string()),
+            ..Default::default()
+        });
+
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_invalid_price_bounds() {
+        let result = Filter::try_from(FilterConfig {
+            min_price: Some(10.0),
+            max_price: Some(5.0),
+            ..Default::default()
+        });
+
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_categories() {
+        let filter = create_filter(FilterConfig {
+            categories: vec!["root-server".to_string()],
+            ..Default::default()
+        });
+        assert!(filter.evaluate(&create_item()).is_keep());
+
+        let filter = create_filter(FilterConfig {
+            categories: vec!["vServer".to_string()],
+            ..Default::default()
+        });
+        assert!(!filter.evaluate(&create_item()).is_keep());
+    }
+
+    #[test]
+    fn test_price_bounds() {
+        let filter = create_filter(FilterConfig {
+            min_price: Some(10.0),
+            max_price: Some(30.0),
+            ..Default::default()
+        });
+        assert!(filter.evaluate(&create_item()).is_keep());
+
+        let filter = create_filter(FilterConfig {
+            max_price: Some(20.0),
+            ..Default::default()
+        });
+        assert_eq!(
+            filter.evaluate(&create_item()),
+            FilterDecision::Drop("price 26.44 is above 20".to_string())
+        );
+    }
+
+    #[test]
+    fn test_unknown_price() {
+        let filter = create_filter(FilterConfig {
+            max_price: Some(20.0),
+            ..Default::default()
+        });
+        let item = FeedItem {
+            description: None,
+            ..create_item()
+        };
+
+        assert_eq!(
+            filter.evaluate(&item),
+            FilterDecision::Drop("price is unknown".to_string())
+        );
+    }
+
+    #[test]
+    fn test_spec_bounds() {
+        let filter = create_filter(FilterConfig {
+            min_vcores: Some(8),
+            min_ram_gb: Some(16.0),
+            min_storage_gb: Some(512.0),
+            ..Default::default()
+        });
+        assert!(filter.evaluate(&create_item()).is_keep());
+
+        let filter = create_filter(FilterConfig {
+            min_ram_gb: Some(64.0),
+            ..Default::default()
+        });
+        assert_eq!(
+            filter.evaluate(&create_item()),
+            FilterDecision::Drop("RAM 32 is below 64".to_string())
+        );
+    }
+}
@@ -0,0 +1,312 @@
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+
+use chrono::{DateTime, Utc};
+use rusqlite::{params, Connection};
+
+use crate::feed::Feed;
+use crate::feed_item::FeedItem;
+
+/// File name in the data directory
+pub const HISTORY_FILE: &str = "history.db";
+
+/// Schema changes, applied in order. The index of the last applied migration is stored in `user_version`,
+/// existing migrations must never be changed
+const MIGRATIONS: [&str; 2] = [
+    "CREATE TABLE offers (
+        feed TEXT NOT NULL,
+        identity TEXT NOT NULL,
+        title TEXT,
+        link TEXT,
+        description TEXT,
+        price REAL,
+        setup_fee REAL,
+        vcores INTEGER,
+        ram_gb REAL,
+        storage_gb REAL,
+        storage_type TEXT,
+        first_seen INTEGER NOT NULL,
+        last_seen INTEGER NOT NULL,
+        PRIMARY KEY (feed, identity)
+    );
+    CREATE TABLE deliveries (
+        feed TEXT NOT NULL,
+        identity TEXT NOT NULL,
+        destination TEXT NOT NULL,
+        status TEXT NOT NULL,
+        attempts INTEGER NOT NULL DEFAULT 0,
+        updated_at INTEGER NOT NULL,
+        PRIMARY KEY (feed, identity, destination)
+    );",
+    "CREATE INDEX offers_first_seen ON offers (first_seen);",
+];
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum DeliveryStatus {
+    Queued,
+    Delivered,
+    /// Failed, retried later
+    Failed,
+    /// Expired or rejected by the destination
+    Dropped,
+}
+
+impl DeliveryStatus {
+    fn as_str(&self) -> &'static str {
+        match self {
+            Self::Queued => "queued",
+            Self::Delivered => "delivered",
+            Self::Failed => "failed",
+            Self::Dropped => "dropped",
+        }
+    }
+}
+
+/// Record of every seen offer and its delivery status per destination.
+/// The JSON feed state stays the source for the check itself, the history is never read back by the bot
+#[derive(Debug, Clone)]
+pub struct OfferHistory {
+    connection: Arc<Mutex<Connection>>,
+}
+
+impl OfferHistory {
+    #[tracing::instrument]
+    pub fn open(file: &Path) -> crate::Result<Self> {
+        info!("Opening offer history");
+        Self::from_connection(Connection::open(file)?)
+    }
+
+    #[cfg(test)]
+    pub fn open_in_memory() -> crate::Result<Self> {
+        Self::from_connection(Connection::open_in_memory()?)
+    }
+
+    fn from_connection(mut connection: Connection) -> crate::Result<Self> {
+        migrate(&mut connection, "Offer history", &MIGRATIONS)?;
+        Ok(Self {
+            connection: Arc::new(Mutex::new(connection)),
+        })
+    }
+
+    fn connection(&self) -> std::sync::MutexGuard<'_, Connection> {
+        self.connection
+            .lock()
+            .expect("Offer history lock is poisoned")
+    }
+
+    /// Inserts new offers and updates the content and last seen time of known ones
+    pub fn record_seen(
+        &self,
+        feed: &Feed,
+        items: &[FeedItem],
+        now: DateTime<Utc>,
+    ) -> crate::Result<()> {
+        let mut connection = self.connection();
+        let transaction = connection.transaction()?;
+        {
+            let mut statement = transaction.prepare_cached(
+                "INSERT INTO offers (feed, identity, title, link, description, price, setup_fee, vcores,
+                    ram_gb, storage_gb, storage_type, first_seen, last_seen)
+                VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?12)
+                ON CONFLICT (feed, identity) DO UPDATE SET
+                    title = excluded.title,
+                    link = excluded.link,
+                    description = excluded.description,
+                    price = excluded.price,
+                    setup_fee = excluded.setup_fee,
+                    vcores = excluded.vcores,
+                    ram_gb = excluded.ram_gb,
+                    storage_gb = excluded.storage_gb,
+                    storage_type = excluded.storage_type,
+                    last_seen = excluded.last_seen",
+            )?;
+            for item in items {
+                let offer = item.offer();
+                statement.execute(params![
+                    feed.id(),
+                    item.identity(),
+                    item.title,
+                    item.link,
+                    item.description,
+                    offer.price.map(|price| price.amount),
+                    offer.setup_fee,
+                    offer.vcores,
+                    offer.ram_gb,
+                    offer.storage_gb,
+                    offer.storage_type,
+                    now.timestamp(),
+                ])?;
+            }
+        }
+        transaction.commit()?;
+        Ok(())
+    }
+
+    pub fn record_delivery(
+        &self,
+        feed: &Feed,
+        destination: &str,
+        item: &FeedItem,
+        status: DeliveryStatus,
+        attempts: u32,
+        now: DateTime<Utc>,
+    ) -> crate::Result<()> {
+        self.connection().execute(
+            "INSERT INTO deliveries (feed, identity, destination, status, attempts, updated_at)
+            VALUES (?1, ?2, ?3, ?4, ?5, ?6)
+            ON CONFLICT (feed, identity, destination) DO UPDATE SET
+                status = excluded.status,
+                attempts = excluded.attempts,
+                updated_at = excluded.updated_at",
+            params![
+                feed.id(),
+                item.identity(),
+                destination,
+                status.as_str(),
+                attempts,
+                now.timestamp()
+            ],
+        )?;
+        Ok(())
+    }
+
+    #[cfg(test)]
+    pub fn delivery_status(
+        &self,
+        feed: &Feed,
+        destination: &str,
+        item: &FeedItem,
+    ) -> Option<String> {
+        use rusqlite::OptionalExtension;
+
+        self.connection()
+            .query_row(
+                "SELECT status FROM deliveries WHERE feed = ?1 AND identity = ?2 AND destination = ?3",
+                params![feed.id(), item.identity(), destination],
+                |row| row.get(0),
+            )
+            .optional()
+            .unwrap()
+    }
+}
+
+/// Applies the migrations newer than the `user_version` of the database
+pub fn migrate(connection: &mut Connection, name: &str, migrations: &[&str]) -> crate::Result<()> {
+    let version: usize = connection.query_row("PRAGMA user_version", [], |row| row.get(0))?;
+    if version > migrations.len() {
+        return Err(crate::Error::custom(format!(
+            "{name} has schema version {version}, this version of the bot supports up to {}",
+            migrations.len()
+        )));
+    }
+
+    for (index, migration) in migrations.iter().enumerate().skip(version) {
+        debug!("Migrating {name} to version {}", index + 1);
+        let transaction = connection.transaction()?;
+        transaction.execute_batch(migration)?;
+        transaction.pragma_update(None, "user_version", index + 1)?;
+        transaction.commit()?;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::Duration;
+    use tempfile::tempdir;
+
+    use super::*;
+
+    fn create_feed() -> Feed {
+        Feed::new("netcup", "Netcup", "")
+    }
+
+    fn create_item(description: &str) -> FeedItem {
+        FeedItem {
+            guid: Some("1".to_string()),
+            title: Some("RS 1000".to_string()),
+            description: Some(description.to_string()),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_migrate() {
+        let dir = tempdir().unwrap();
+        let file = dir.path().join("history.db");
+
+        OfferHistory::open(&file).unwrap();
+        // Reopening doesn't apply the migrations again
+        let history = OfferHistory::open(&file).unwrap();
+
+        let version: usize = history
+            .connection()
+            .query_row("PRAGMA user_version", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(version, MIGRATIONS.len());
+    }
+
+    #[test]
+    fn test_migrate_newer_schema() {
+        let connection = Connection::open_in_memory().unwrap();
+        connection
+            .pragma_update(None, "user_version", MIGRATIONS.len() + 1)
+            .unwrap();
+
+        assert!(OfferHistory::from_connection(connection).is_err());
+    }
+
+    #[test]
+    fn test_record_seen() {
+        let history = OfferHistory::open_in_memory().unwrap();
+        let feed = create_feed();
+        let now = Utc::now();
+
+        history
+            .record_seen(&feed, &[create_item("8 GB RAM, 9,99 € / Monat")], now)
+            .unwrap();
+        history
+            .record_seen(
+                &feed,
+                &[create_item("16 GB RAM, 9,99 € / Monat")],
+                now + Duration::hours(1),
+            )
+            .unwrap();
+
+        let (ram_gb, price, first_seen, last_seen): (f64, f64, i64, i64) = history
+            .connection()
+            .query_row(
+                "SELECT ram_gb, price, first_seen, last_seen FROM offers",
+                [],
+                |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?)),
+            )
+            .unwrap();
+        assert_eq!(ram_gb, 16.0);
+        assert_eq!(price, 9.99);
+        assert_eq!(first_seen, now.timestamp());
+        assert_eq!(last_seen, (now + Duration::hours(1)).timestamp());
+    }
+
+    #[test]
+    fn test_record_delivery() {
+        let history = OfferHistory::open_in_memory().unwrap();
+        let feed = create_feed();
+        let item = create_item("");
+        let now = Utc::now();
+
+        assert_eq!(history.delivery_status(&feed, "main", &item), None);
+
+        history
+            .record_delivery(&feed, "main", &item, DeliveryStatus::Queued, 0, now)
+            .unwrap();
+        history
+            .record_delivery(&feed, "main", &item, DeliveryStatus::Delivered, 0, now)
+            .unwrap();
+
+        assert_eq!(
+            history.delivery_status(&feed, "main", &item).as_deref(),
+            Some("delivered")
+        );
+    }
+}
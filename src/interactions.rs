@@ -0,0 +1,833 @@
+use axum::body::Bytes;
+use axum::extract::State;
+use axum::http::{HeaderMap, StatusCode};
+use axum::response::{IntoResponse, Response};
+use axum::routing::post;
+use axum::{Json, Router};
+use chrono::{DateTime, Utc};
+use ed25519_dalek::{Signature, VerifyingKey};
+use reqwest_middleware::ClientWithMiddleware;
+use secrecy::ExposeSecret;
+use serde::{Deserialize, Serialize};
+use tokio::sync::watch;
+
+use crate::config::InteractionsConfig;
+use crate::error::Error;
+use crate::feed_state::{FeedStatus, SeenOffer};
+use crate::notifier::direct_message::DISCORD_API_URL;
+use crate::notifier::discord::Embed;
+use crate::notifier::markdown::{escape_markdown, truncate_markdown};
+use crate::notifier::mention::AllowedMentions;
+use crate::offer::Offer;
+use crate::subscription::{Subscribed, Subscriptions, MAX_KEYWORDS_PER_USER, MAX_KEYWORD_LENGTH};
+
+const HEADER_SIGNATURE: &str = "x-signature-ed25519";
+const HEADER_TIMESTAMP: &str = "x-signature-timestamp";
+/// Older or newer signed requests are rejected, a captured request can't be replayed later
+const MAX_TIMESTAMP_SKEW_SECONDS: u64 = 5 * 60;
+
+// https://discord.com/developers/docs/interactions/receiving-and-responding#interaction-object-interaction-type
+const INTERACTION_PING: u8 = 1;
+const INTERACTION_APPLICATION_COMMAND: u8 = 2;
+// https://discord.com/developers/docs/interactions/receiving-and-responding#interaction-response-object-interaction-callback-type
+const RESPONSE_PONG: u8 = 1;
+const RESPONSE_CHANNEL_MESSAGE: u8 = 4;
+const FLAG_EPHEMERAL: u64 = 1 << 6;
+
+const DEFAULT_OFFER_COUNT: usize = 5;
+const MAX_OFFER_COUNT: usize = 10;
+const MAX_TITLE_LENGTH: usize = 256;
+const MAX_DESCRIPTION_LENGTH: usize = 4096;
+const MAX_TERM_LENGTH: usize = 100;
+
+/// State of the last finished check, published by the feed checker
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct BotSnapshot {
+    pub checked_at: Option<DateTime<Utc>>,
+    pub feeds: Vec<FeedStatus>,
+    /// Offers of all feeds, newest first
+    pub offers: Vec<SeenOffer>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Interaction {
+    #[serde(rename = "type")]
+    kind: u8,
+    data: Option<CommandData>,
+    /// Set for commands in a guild
+    member: Option<Member>,
+    /// Set for commands in a DM
+    user: Option<User>,
+}
+
+impl Interaction {
+    fn user_id(&self) -> Option<&str> {
+        self.member
+            .as_ref()
+            .map(|member| &member.user)
+            .or(self.user.as_ref())
+            .map(|user| user.id.as_str())
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct Member {
+    user: User,
+}
+
+#[derive(Debug, Deserialize)]
+struct User {
+    id: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct CommandData {
+    name: String,
+    #[serde(default)]
+    options: Vec<CommandOption>,
+}
+
+#[derive(Debug, Deserialize)]
+struct CommandOption {
+    name: String,
+    value: Option<serde_json::Value>,
+    /// Options of a subcommand
+    #[serde(default)]
+    options: Vec<CommandOption>,
+}
+
+fn option<'a>(options: &'a [CommandOption], name: &str) -> Option<&'a serde_json::Value> {
+    options
+        .iter()
+        .find(|option| option.name == name)
+        .and_then(|option| option.value.as_ref())
+}
+
+#[derive(Debug, Serialize, PartialEq)]
+struct InteractionResponse {
+    #[serde(rename = "type")]
+    kind: u8,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    data: Option<ResponseData>,
+}
+
+#[derive(Debug, Serialize, PartialEq)]
+struct ResponseData {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    content: Option<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    embeds: Vec<Embed>,
+    flags: u64,
+    allowed_mentions: AllowedMentions,
+}
+
+impl InteractionResponse {
+    fn pong() -> Self {
+        Self {
+            kind: RESPONSE_PONG,
+            data: None,
+        }
+    }
+
+    /// Replies are only visible to the user that ran the command
+    fn message(content: Option<String>, embeds: Vec<Embed>) -> Self {
+        Self {
+            kind: RESPONSE_CHANNEL_MESSAGE,
+            data: Some(ResponseData {
+                content,
+                embeds,
+                flags: FLAG_EPHEMERAL,
+                allowed_mentions: AllowedMentions::default(),
+            }),
+        }
+    }
+
+    fn text(content: impl Into<String>) -> Self {
+        Self::message(Some(content.into()), Vec::new())
+    }
+}
+
+fn embed(title: String, description: String) -> Embed {
+    Embed {
+        title: truncate_markdown(&title, MAX_TITLE_LENGTH, None),
+        description,
+        url: None,
+        color: None,
+        fields: Vec::new(),
+        footer: None,
+    }
+}
+
+/// Discord renders the timestamp in the time zone of the user
+fn relative_time(date: DateTime<Utc>) -> String {
+    format!("<t:{}:R>", date.timestamp())
+}
+
+fn offer_line(offer: &SeenOffer) -> String {
+    let title = escape_markdown(&offer.title);
+    let mut line = match &offer.link {
+        Some(link) => format!("• [{title}]({link})"),
+        None => format!("• {title}"),
+    };
+    if let Some(price) = offer
+        .description
+        .as_deref()
+        .map(Offer::parse)
+        .and_then(|offer| offer.price)
+    {
+        line.push_str(&format!(" - {price}"));
+    }
+    line.push_str(&format!(
+        " · {} · {}",
+        escape_markdown(&offer.feed),
+        relative_time(offer.first_seen)
+    ));
+    line
+}
+
+fn offer_list(title: String, offers: &[&SeenOffer]) -> InteractionResponse {
+    let description = offers
+        .iter()
+        .map(|offer| offer_line(offer))
+        .collect::<Vec<String>>()
+        .join("\n");
+    InteractionResponse::message(
+        None,
+        vec![embed(
+            title,
+            truncate_markdown(&description, MAX_DESCRIPTION_LENGTH, None),
+        )],
+    )
+}
+
+fn latest(snapshot: &BotSnapshot, options: &[CommandOption]) -> InteractionResponse {
+    let count = option(options, "count")
+        .and_then(|count| count.as_u64())
+        .map_or(DEFAULT_OFFER_COUNT, |count| count as usize)
+        .clamp(1, MAX_OFFER_COUNT);
+    let offers = snapshot
+        .offers
+        .iter()
+        .take(count)
+        .collect::<Vec<&SeenOffer>>();
+    if offers.is_empty() {
+        return InteractionResponse::text("No offers found yet");
+    }
+
+    offer_list("Latest offers".to_string(), &offers)
+}
+
+fn search(snapshot: &BotSnapshot, options: &[CommandOption]) -> InteractionResponse {
+    let Some(term) = option(options, "term")
+        .and_then(|term| term.as_str())
+        .map(str::trim)
+        .filter(|term| !term.is_empty())
+    else {
+        return InteractionResponse::text("A search term is required");
+    };
+
+    let needle = term.to_lowercase();
+    let offers = snapshot
+        .offers
+        .iter()
+        .filter(|offer| {
+            offer.title.to_lowercase().contains(&needle)
+                || offer
+                    .description
+                    .as_deref()
+                    .is_some_and(|description| description.to_lowercase().contains(&needle))
+        })
+        .take(MAX_OFFER_COUNT)
+        .collect::<Vec<&SeenOffer>>();
+    if offers.is_empty() {
+        return InteractionResponse::text(format!(
+            "No offers found for \"{}\"",
+            escape_markdown(term)
+        ));
+    }
+
+    offer_list(format!("Offers matching \"{term}\""), &offers)
+}
+
+fn keyword(options: &[CommandOption]) -> Option<&str> {
+    option(options, "keyword")
+        .and_then(|keyword| keyword.as_str())
+        .map(str::trim)
+        .filter(|keyword| !keyword.is_empty())
+}
+
+fn subscribe(
+    subscriptions: &Subscriptions,
+    user: Option<&str>,
+    options: &[CommandOption],
+) -> InteractionResponse {
+    let Some(user) = user else {
+        return InteractionResponse::text("Subscriptions need a user");
+    };
+    let Some(keyword) = keyword(options) else {
+        return InteractionResponse::text("A keyword is required");
+    };
+    if keyword.chars().count() > MAX_KEYWORD_LENGTH {
+        return InteractionResponse::text(format!(
+            "Keywords can have at most {MAX_KEYWORD_LENGTH} characters"
+        ));
+    }
+
+    match subscriptions.subscribe(user, keyword) {
+        Subscribed::Added(keywords) => {
+            info!("User {user} subscribed to \"{keyword}\"");
+            InteractionResponse::text(format!(
+                "Subscribed to \"{}\", matching offers are sent as direct message. Your keywords: {}",
+                escape_markdown(keyword),
+                escape_markdown(&keywords.join(", "))
+            ))
+        }
+        Subscribed::Duplicate => InteractionResponse::text(format!(
+            "Already subscribed to \"{}\"",
+            escape_markdown(keyword)
+        )),
+        Subscribed::LimitReached => InteractionResponse::text(format!(
+            "You can subscribe to at most {MAX_KEYWORDS_PER_USER} keywords"
+        )),
+    }
+}
+
+fn unsubscribe(
+    subscriptions: &Subscriptions,
+    user: Option<&str>,
+    options: &[CommandOption],
+) -> InteractionResponse {
+    let Some(user) = user else {
+        return InteractionResponse::text("Subscriptions need a user");
+    };
+    let Some(keyword) = keyword(options) else {
+        return InteractionResponse::text("A keyword is required");
+    };
+
+    match subscriptions.unsubscribe(user, keyword) {
+        Some(keywords) => {
+            info!("User {user} unsubscribed from \"{keyword}\"");
+            let remaining = if keywords.is_empty() {
+                "You have no keywords left".to_string()
+            } else {
+                format!("Your keywords: {}", escape_markdown(&keywords.join(", ")))
+            };
+            InteractionResponse::text(format!(
+                "Unsubscribed from \"{}\". {remaining}",
+                escape_markdown(keyword)
+            ))
+        }
+        None => InteractionResponse::text(format!(
+            "Not subscribed to \"{}\"",
+            escape_markdown(keyword)
+        )),
+    }
+}
+
+fn list_subscriptions(subscriptions: &Subscriptions, user: Option<&str>) -> InteractionResponse {
+    let Some(user) = user else {
+        return InteractionResponse::text("Subscriptions need a user");
+    };
+
+    let keywords = subscriptions.keywords(user);
+    if keywords.is_empty() {
+        return InteractionResponse::text("You have no keywords, add one with /subscribe");
+    }
+    InteractionResponse::text(format!(
+        "Your keywords: {}",
+        escape_markdown(&keywords.join(", "))
+    ))
+}
+
+fn status(snapshot: &BotSnapshot) -> InteractionResponse {
+    let mut lines = vec![match snapshot.checked_at {
+        Some(checked_at) => format!("Last check {}", relative_time(checked_at)),
+        None => "No check finished yet".to_string(),
+    }];
+    for feed in &snapshot.feeds {
+        let last_update = feed.last_update.map_or("never".to_string(), relative_time);
+        lines.push(format!(
+            "**{}**: {} known offers, {} pending deliveries, last offer {last_update}",
+            escape_markdown(&feed.feed),
+            feed.known_offers,
+            feed.pending_deliveries
+        ));
+    }
+
+    InteractionResponse::message(None, vec![embed("Status".to_string(), lines.join("\n"))])
+}
+
+fn handle(
+    interaction: &Interaction,
+    snapshot: &BotSnapshot,
+    subscriptions: &Subscriptions,
+) -> InteractionResponse {
+    if interaction.kind == INTERACTION_PING {
+        return InteractionResponse::pong();
+    }
+
+    let data = match (&interaction.data, interaction.kind) {
+        (Some(data), INTERACTION_APPLICATION_COMMAND) => data,
+        _ => return InteractionResponse::text("Unsupported interaction"),
+    };
+    debug!("Handling command /{}", data.name);
+
+    match data.name.as_str() {
+        "offers" => match data.options.first() {
+            Some(subcommand) if subcommand.name == "latest" => {
+                latest(snapshot, &subcommand.options)
+            }
+            Some(subcommand) if subcommand.name == "search" => {
+                search(snapshot, &subcommand.options)
+            }
+            _ => InteractionResponse::text("Unknown subcommand"),
+        },
+        "subscribe" => subscribe(subscriptions, interaction.user_id(), &data.options),
+        "unsubscribe" => unsubscribe(subscriptions, interaction.user_id(), &data.options),
+        "subscriptions" => list_subscriptions(subscriptions, interaction.user_id()),
+        "status" => status(snapshot),
+        _ => InteractionResponse::text("Unknown command"),
+    }
+}
+
+/// Checks the signature Discord adds to every interaction request and the age of its timestamp
+fn verify(key: &VerifyingKey, headers: &HeaderMap, body: &[u8], now: DateTime<Utc>) -> bool {
+    let header = |name| headers.get(name).and_then(|value| value.to_str().ok());
+    let (Some(signature), Some(timestamp)) = (header(HEADER_SIGNATURE), header(HEADER_TIMESTAMP))
+    else {
+        return false;
+    };
+    let Ok(seconds) = timestamp.parse::<i64>() else {
+        return false;
+    };
+    // The header isn't authenticated yet, any number must be handled without overflow
+    if now.timestamp().abs_diff(seconds) > MAX_TIMESTAMP_SKEW_SECONDS {
+        return false;
+    }
+    let Some(signature) = hex::decode(signature)
+        .ok()
+        .and_then(|signature| Signature::from_slice(&signature).ok())
+    else {
+        return false;
+    };
+
+    let mut message = timestamp.as_bytes().to_vec();
+    message.extend_from_slice(body);
+    key.verify_strict(&message, &signature).is_ok()
+}
+
+#[derive(Debug, Clone)]
+struct AppState {
+    public_key: VerifyingKey,
+    snapshot: watch::Receiver<BotSnapshot>,
+    subscriptions: Subscriptions,
+}
+
+async fn interactions(State(state): State<AppState>, headers: HeaderMap, body: Bytes) -> Response {
+    if !verify(&state.public_key, &headers, &body, Utc::now()) {
+        debug!("Rejecting interaction with invalid signature");
+        return (StatusCode::UNAUTHORIZED, "Invalid request signature").into_response();
+    }
+
+    let interaction = match serde_json::from_slice::<Interaction>(&body) {
+        Ok(interaction) => interaction,
+        Err(e) => {
+            warn!("Invalid interaction payload: {e}");
+            return (StatusCode::BAD_REQUEST, "Invalid interaction").into_response();
+        }
+    };
+
+    let snapshot = state.snapshot.borrow().clone();
+    let response = handle(&interaction, &snapshot, &state.subscriptions);
+    if let Err(e) = state.subscriptions.save().await {
+        error!("Error saving subscriptions: {e}");
+    }
+    Json(response).into_response()
+}
+
+fn router(
+    public_key: VerifyingKey,
+    snapshot: watch::Receiver<BotSnapshot>,
+    subscriptions: Subscriptions,
+) -> Router {
+    Router::new()
+        .route("/interactions", post(interactions))
+        .with_state(AppState {
+            public_key,
+            snapshot,
+            subscriptions,
+        })
+}
+
+/// Slash commands of the bot, see https://discord.com/developers/docs/interactions/application-commands
+fn commands() -> serde_json::Value {
+    // 1 = subcommand, 3 = string, 4 = integer
+    serde_json::json!([
+        {
+            "name": "offers",
+            "description": "Show known offers",
+            "options": [
+                {
+                    "type": 1,
+                    "name": "latest",
+                    "description": "Newest offers of all feeds",
+                    "options": [{
+                        "type": 4,
+                        "name": "count",
+                        "description": "Number of offers",
+                        "min_value": 1,
+                        "max_value": MAX_OFFER_COUNT
+                    }]
+                },
+                {
+                    "type": 1,
+                    "name": "search",
+                    "description": "Search offers by title and description",
+                    "options": [{
+                        "type": 3,
+                        "name": "term",
+                        "description": "Text to search for",
+                        "required": true,
+                        "max_length": MAX_TERM_LENGTH
+                    }]
+                }
+            ]
+        },
+        {
+            "name": "subscribe",
+            "description": "Subscribe to offers matching a keyword",
+            "options": [{
+                "type": 3,
+                "name": "keyword",
+                "description": "Keyword in the offer title or description",
+                "required": true,
+                "max_length": MAX_KEYWORD_LENGTH
+            }]
+        },
+        {
+            "name": "unsubscribe",
+            "description": "Stop receiving offers matching a keyword",
+            "options": [{
+                "type": 3,
+                "name": "keyword",
+                "description": "Keyword you subscribed to",
+                "required": true,
+                "max_length": MAX_KEYWORD_LENGTH
+            }]
+        },
+        {
+            "name": "subscriptions",
+            "description": "List your keywords"
+        },
+        {
+            "name": "status",
+            "description": "Show the state of the feeds"
+        }
+    ])
+}
+
+/// Replaces the global commands of the application with the commands of the bot
+async fn register_commands(
+    client: &ClientWithMiddleware,
+    application_id: &str,
+    bot_token: &str,
+) -> crate::Result<()> {
+    let response = client
+        .put(format!(
+            "{DISCORD_API_URL}/applications/{application_id}/commands"
+        ))
+        .header(reqwest::header::AUTHORIZATION, format!("Bot {bot_token}"))
+        .json(&commands())
+        .send()
+        .await?;
+    if !response.status().is_success() {
+        let status = response.status();
+        let body = response.text().await.unwrap_or_default();
+        return Err(Error::Notifier(format!(
+            "Discord responded with {status} while registering commands: {body}"
+        )));
+    }
+
+    Ok(())
+}
+
+/// Serves the interactions endpoint in the background, commands are answered from the snapshot
+pub async fn spawn(
+    config: &InteractionsConfig,
+    client: ClientWithMiddleware,
+    snapshot: watch::Receiver<BotSnapshot>,
+    subscriptions: Subscriptions,
+) -> crate::Result<()> {
+    if let (Some(application_id), Some(bot_token)) = (&config.application_id, &config.bot_token) {
+        match register_commands(&client, application_id, bot_token.expose_secret()).await {
+            Ok(()) => info!("Registered slash commands"),
+            Err(e) => warn!("Failed to register slash commands: {e}"),
+        }
+    }
+
+    let listener = tokio::net::TcpListener::bind(config.socket).await?;
+    info!("Listening for interactions on {}", config.socket);
+    let app = router(config.public_key, snapshot, subscriptions);
+    tokio::spawn(async move {
+        if let Err(e) = axum::serve(listener, app).await {
+            error!("Interactions server stopped: {e}");
+        }
+    });
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{Duration, TimeZone};
+    use ed25519_dalek::{Signer, SigningKey};
+
+    use super::*;
+
+    const PING: &str = include_str!("../tests/fixtures/interactions/ping.json");
+    const OFFERS_LATEST: &str = include_str!("../tests/fixtures/interactions/offers_latest.json");
+    const OFFERS_SEARCH: &str = include_str!("../tests/fixtures/interactions/offers_search.json");
+    const SUBSCRIBE: &str = include_str!("../tests/fixtures/interactions/subscribe.json");
+    const UNSUBSCRIBE: &str = include_str!("../tests/fixtures/interactions/unsubscribe.json");
+    const SUBSCRIPTIONS: &str = include_str!("../tests/fixtures/interactions/subscriptions.json");
+    const STATUS: &str = include_str!("../tests/fixtures/interactions/status.json");
+
+    fn create_key() -> SigningKey {
+        SigningKey::from_bytes(&[7; 32])
+    }
+
+    fn create_snapshot() -> BotSnapshot {
+        let now = Utc.with_ymd_and_hms(2025, 1, 14, 10, 0, 0).unwrap();
+        let offer = |title: &str, description: &str, age: i64| SeenOffer {
+            feed: "Netcup".to_string(),
+            title: title.to_string(),
+            description: Some(description.to_string()),
+            link: Some(format!(
+                "https://example.com/{}",
+                title.to_lowercase().replace(' ', "-")
+            )),
+            first_seen: now - Duration::hours(age),
+        };
+
+        BotSnapshot {
+            checked_at: Some(now),
+            feeds: vec![FeedStatus {
+                feed: "Netcup".to_string(),
+                last_update: Some(now - Duration::hours(1)),
+                known_offers: 2,
+                pending_deliveries: 1,
+            }],
+            offers: vec![
+                offer("RS 1000", "8 GB RAM, 9,99 € / Monat", 1),
+                offer("VPS 500 ARM", "4 GB RAM, 3,99 € / Monat", 2),
+            ],
+        }
+    }
+
+    /// Serves the router on a random local port
+    async fn start_server(subscriptions: Subscriptions) -> String {
+        let (_, snapshot) = watch::channel(create_snapshot());
+        let app = router(create_key().verifying_key(), snapshot, subscriptions);
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+        format!("http://{address}/interactions")
+    }
+
+    async fn post(url: &str, body: &str, key: &SigningKey) -> reqwest::Response {
+        post_at(url, body, key, Utc::now()).await
+    }
+
+    async fn post_at(
+        url: &str,
+        body: &str,
+        key: &SigningKey,
+        time: DateTime<Utc>,
+    ) -> reqwest::Response {
+        let timestamp = time.timestamp().to_string();
+        let signature = key.sign(format!("{timestamp}{body}").as_bytes());
+        reqwest::Client::new()
+            .post(url)
+            .header(HEADER_SIGNATURE, hex::encode(signature.to_bytes()))
+            .header(HEADER_TIMESTAMP, &timestamp)
+            .body(body.to_string())
+            .send()
+            .await
+            .unwrap()
+    }
+
+    async fn post_json(url: &str, body: &str) -> serde_json::Value {
+        let response = post(url, body, &create_key()).await;
+        assert_eq!(response.status(), 200);
+        response.json().await.unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_ping() {
+        let url = start_server(Subscriptions::default()).await;
+
+        assert_eq!(post_json(&url, PING).await, serde_json::json!({"type": 1}));
+    }
+
+    #[tokio::test]
+    async fn test_invalid_signature() {
+        let url = start_server(Subscriptions::default()).await;
+
+        let response = post(&url, PING, &SigningKey::from_bytes(&[8; 32])).await;
+        assert_eq!(response.status(), 401);
+
+        let response = reqwest::Client::new()
+            .post(&url)
+            .body(PING)
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status(), 401);
+    }
+
+    #[tokio::test]
+    async fn test_stale_timestamp() {
+        let url = start_server(Subscriptions::default()).await;
+        let key = create_key();
+
+        let response = post_at(&url, PING, &key, Utc::now() - Duration::minutes(10)).await;
+        assert_eq!(response.status(), 401);
+        let response = post_at(&url, PING, &key, Utc::now() + Duration::minutes(10)).await;
+        assert_eq!(response.status(), 401);
+
+        let response = post_at(&url, PING, &key, Utc::now() - Duration::minutes(1)).await;
+        assert_eq!(response.status(), 200);
+
+        // Checked before the signature, must not overflow
+        let response = reqwest::Client::new()
+            .post(&url)
+            .header(HEADER_SIGNATURE, "00")
+            .header(HEADER_TIMESTAMP, i64::MIN.to_string())
+            .body(PING)
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status(), 401);
+    }
+
+    #[tokio::test]
+    async fn test_offers_latest() {
+        let url = start_server(Subscriptions::default()).await;
+
+        let response = post_json(&url, OFFERS_LATEST).await;
+
+        assert_eq!(response["type"], 4);
+        assert_eq!(response["data"]["flags"], 64);
+        assert_eq!(
+            response["data"]["embeds"][0]["description"],
+            "• [RS 1000](https://example.com/rs-1000) - 9.99 € / month · Netcup · <t:1736845200:R>"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_offers_search() {
+        let url = start_server(Subscriptions::default()).await;
+
+        let response = post_json(&url, OFFERS_SEARCH).await;
+
+        let description = response["data"]["embeds"][0]["description"]
+            .as_str()
+            .unwrap();
+        assert!(description.starts_with("• [VPS 500 ARM]"));
+        assert_eq!(description.lines().count(), 1);
+    }
+
+    #[test]
+    fn test_search_long_term() {
+        let term = "RAM ".repeat(100);
+        let mut snapshot = create_snapshot();
+        snapshot.offers[0].description = Some(term.clone());
+        let options: Vec<CommandOption> =
+            serde_json::from_value(serde_json::json!([{"name": "term", "type": 3, "value": term}]))
+                .unwrap();
+
+        let response = serde_json::to_value(search(&snapshot, &options)).unwrap();
+
+        let title = response["data"]["embeds"][0]["title"].as_str().unwrap();
+        assert!(title.starts_with("Offers matching \"RAM RAM"));
+        assert!(title.chars().count() <= MAX_TITLE_LENGTH);
+    }
+
+    #[tokio::test]
+    async fn test_subscribe() {
+        let dir = tempfile::tempdir().unwrap();
+        let subscriptions =
+            Subscriptions::load_from_path(&dir.path().join("subscriptions.json")).unwrap();
+        let url = start_server(subscriptions.clone()).await;
+
+        let response = post_json(&url, SUBSCRIBE).await;
+        assert_eq!(
+            response["data"]["content"],
+            "Subscribed to \"RS 8000\", matching offers are sent as direct message. Your keywords: RS 8000"
+        );
+        let response = post_json(&url, SUBSCRIBE).await;
+        assert_eq!(
+            response["data"]["content"],
+            "Already subscribed to \"RS 8000\""
+        );
+
+        assert_eq!(subscriptions.keywords("80351110224678912"), vec!["RS 8000"]);
+    }
+
+    #[tokio::test]
+    async fn test_unsubscribe() {
+        let dir = tempfile::tempdir().unwrap();
+        let file = dir.path().join("subscriptions.json");
+        let subscriptions = Subscriptions::load_from_path(&file).unwrap();
+        let url = start_server(subscriptions.clone()).await;
+        subscriptions.subscribe("80351110224678912", "RS 8000");
+        subscriptions.subscribe("80351110224678912", "ARM");
+
+        let response = post_json(&url, SUBSCRIPTIONS).await;
+        assert_eq!(response["data"]["content"], "Your keywords: RS 8000, ARM");
+
+        let response = post_json(&url, UNSUBSCRIBE).await;
+        assert_eq!(
+            response["data"]["content"],
+            "Unsubscribed from \"rs 8000\". Your keywords: ARM"
+        );
+        let response = post_json(&url, UNSUBSCRIBE).await;
+        assert_eq!(response["data"]["content"], "Not subscribed to \"rs 8000\"");
+
+        let loaded = Subscriptions::load_from_path(&file).unwrap();
+        assert_eq!(loaded.keywords("80351110224678912"), vec!["ARM"]);
+    }
+
+    #[tokio::test]
+    async fn test_status() {
+        let url = start_server(Subscriptions::default()).await;
+
+        let response = post_json(&url, STATUS).await;
+
+        assert_eq!(
+            response["data"]["embeds"][0]["description"],
+            "Last check <t:1736848800:R>\n**Netcup**: 2 known offers, 1 pending deliveries, last offer <t:1736845200:R>"
+        );
+    }
+
+    #[test]
+    fn test_commands_match_handlers() {
+        let names = commands()
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|command| command["name"].as_str().unwrap().to_string())
+            .collect::<Vec<String>>();
+
+        assert_eq!(
+            names,
+            vec![
+                "offers",
+                "subscribe",
+                "unsubscribe",
+                "subscriptions",
+                "status"
+            ]
+        );
+    }
+}
@@ -1,97 +1,471 @@
 #[macro_use]
 extern crate tracing;
 
+use std::collections::HashMap;
 use std::fmt::Debug;
+use std::future::Future;
+use std::time::Duration;
 
 use reqwest_middleware::{ClientBuilder, ClientWithMiddleware};
 use reqwest_tracing::{SpanBackendWithUrl, TracingMiddleware};
 use secrecy::ExposeSecret;
-use strum::IntoEnumIterator;
+use tokio::sync::watch;
 
 use crate::config::Config;
-use crate::discord_webhook::DiscordWebhook;
-use crate::error::Error;
+use crate::destination::Destination;
 use crate::feed::Feed;
+use crate::feed_item::FeedItem;
 use crate::feed_state::FeedStates;
+use crate::history::{OfferHistory, HISTORY_FILE};
+use crate::interactions::BotSnapshot;
+use crate::notifier::direct_message::DirectMessenger;
+use crate::state_store::StateStore;
+use crate::subscription::Subscriptions;
 
+pub mod admin;
 pub mod config;
-mod discord_webhook;
+mod destination;
+mod diff;
 mod error;
 mod feed;
+mod feed_item;
+mod feed_parser;
 mod feed_state;
+mod filter;
+mod history;
+mod interactions;
+mod locale;
 mod metrics;
+mod notifier;
+mod offer;
+mod outbox;
+mod schedule;
+mod state_store;
+mod subscription;
+
+pub use error::Error;
 
 pub type Result<T> = anyhow::Result<T, Error>;
 
+/// Part of the shutdown timeout reserved to save the state, the running check gets the rest
+const SHUTDOWN_SAVE_SHARE: u32 = 4;
+
+/// Offers of a feed a check would send to a destination
+#[derive(Debug)]
+pub struct DryRunDelivery {
+    pub feed: String,
+    pub destination: String,
+    pub offers: Vec<DryRunOffer>,
+}
+
+#[derive(Debug)]
+pub struct DryRunOffer {
+    pub title: String,
+    pub link: Option<String>,
+    /// Edited version of an already announced offer
+    pub update: bool,
+}
+
 #[derive(Debug)]
 pub struct FeedChecker {
     client: ClientWithMiddleware,
+    feeds: Vec<Feed>,
     states: FeedStates,
-    hook: DiscordWebhook,
+    store: Box<dyn StateStore>,
+    destinations: Vec<Destination>,
+    history: Option<OfferHistory>,
+    /// Only set while the interactions endpoint is running
+    snapshot: Option<watch::Sender<BotSnapshot>>,
+    subscriptions: Subscriptions,
+    /// Sends offers matching the subscriptions, requires a bot token
+    direct_messenger: Option<DirectMessenger>,
 }
 
 impl FeedChecker {
-    pub fn new(client: ClientWithMiddleware, states: FeedStates, webhook: DiscordWebhook) -> Self {
+    pub fn new(
+        client: ClientWithMiddleware,
+        feeds: Vec<Feed>,
+        mut states: FeedStates,
+        store: Box<dyn StateStore>,
+        destinations: Vec<Destination>,
+    ) -> Self {
+        let names = destinations
+            .iter()
+            .map(|destination| destination.name())
+            .collect::<Vec<&str>>();
+        states.retain_destinations(&names);
+
         Self {
             client,
+            feeds,
             states,
-            hook: webhook,
+            store,
+            destinations,
+            history: None,
+            snapshot: None,
+            subscriptions: Subscriptions::default(),
+            direct_messenger: None,
         }
     }
 
-    pub fn from_config(config: &Config) -> Self {
+    pub async fn from_config(config: &Config) -> Result<Self> {
+        config.check_writable()?;
+        let store = state_store::from_config(&config.state)?;
+        // The bot keeps working without the history
+        let history = OfferHistory::open(&config.data_dir.join(HISTORY_FILE))
+            .inspect_err(|e| error!("Error opening offer history: {e}"))
+            .ok();
+        Self::build(config, store, history).await
+    }
+
+    /// Checker that doesn't write to the data directory, for commands like the dry run.
+    /// The state is loaded without migrating or recovering the stored files and there is no history
+    pub async fn from_config_read_only(config: &Config) -> Result<Self> {
+        let store = state_store::open_read_only(&config.state)?;
+        Self::build(config, store, None).await
+    }
+
+    async fn build(
+        config: &Config,
+        store: Box<dyn StateStore>,
+        history: Option<OfferHistory>,
+    ) -> Result<Self> {
         let client = ClientBuilder::new(reqwest::Client::new())
             .with(TracingMiddleware::<SpanBackendWithUrl>::new())
             .build();
-        let states = FeedStates::load().unwrap();
-        let hook = DiscordWebhook::new(config.discord_webhook_url.expose_secret());
+        // Notifier urls can contain tokens, they are not added to the spans
+        let notifier_client = ClientBuilder::new(reqwest::Client::new())
+            .with(TracingMiddleware::default())
+            .build();
+        let feeds = config.feeds.iter().map(Feed::from).collect();
+        let states = store.load().await?;
+        let destinations = config
+            .destinations
+            .iter()
+            .map(|destination| {
+                Destination::from_config(destination, notifier_client.clone())
+                    .with_history(history.clone())
+            })
+            .collect();
 
-        FeedChecker::new(client, states, hook)
+        Ok(FeedChecker::new(client, feeds, states, store, destinations).with_history(history))
+    }
+
+    pub fn with_history(mut self, history: Option<OfferHistory>) -> Self {
+        self.history = history;
+        self
     }
 
     #[tracing::instrument]
     pub async fn check_feeds(&mut self) {
         trace!("Run feed check");
 
-        for feed in Feed::iter() {
-            self.check_feed(feed).await;
+        for feed in self.feeds.clone() {
+            self.check_feed(&feed).await;
         }
 
-        if let Err(e) = self.states.save().await {
+        if let Err(e) = self.states.save(self.store.as_ref()).await {
             error!("Error saving feed states: {}", e);
         }
+
+        if let Some(sender) = &self.snapshot {
+            sender.send_replace(self.create_snapshot(Some(chrono::Utc::now())));
+        }
+    }
+
+    /// Checks the feeds in the interval until `shutdown` resolves, then saves the state.
+    /// A running check is cancelled if it doesn't finish before the time reserved for the save
+    pub async fn run(
+        &mut self,
+        check_interval: Duration,
+        shutdown_timeout: Duration,
+        shutdown: impl Future<Output = ()>,
+    ) -> Result<()> {
+        tokio::pin!(shutdown);
+        let save_timeout = shutdown_timeout / SHUTDOWN_SAVE_SHARE;
+        let mut interval = tokio::time::interval(check_interval);
+        loop {
+            tokio::select! {
+                _ = &mut shutdown => break,
+                _ = interval.tick() => {}
+            }
+
+            let check = self.check_feeds();
+            tokio::pin!(check);
+            tokio::select! {
+                _ = &mut check => continue,
+                _ = &mut shutdown => {}
+            }
+
+            // Messages may already be sent, the state has to be saved to prevent duplicates
+            info!("Waiting for the running feed check to finish");
+            if tokio::time::timeout(shutdown_timeout - save_timeout, check)
+                .await
+                .is_err()
+            {
+                warn!("Feed check didn't finish in time, saving the current state");
+            }
+            break;
+        }
+
+        match tokio::time::timeout(save_timeout, self.save_state()).await {
+            Ok(result) => result,
+            Err(_) => Err(Error::custom(format!(
+                "Feed state not saved within {}s of the shutdown timeout",
+                save_timeout.as_secs_f32()
+            ))),
+        }
+    }
+
+    /// Offers the next check would send per destination, nothing is sent or saved
+    pub async fn dry_run(&mut self) -> Vec<DryRunDelivery> {
+        let mut deliveries = Vec::new();
+        for feed in self.feeds.clone() {
+            let items = match feed.fetch(&self.client).await {
+                Ok(items) => self.states.get_new_feed(&feed, items),
+                Err(e) => {
+                    error!("Error fetching feed for {}: {}", feed.name(), e);
+                    continue;
+                }
+            };
+
+            for destination in self.destinations.iter() {
+                if !destination.subscribes(&feed) {
+                    continue;
+                }
+                let offers = destination
+                    .filter(&items)
+                    .into_iter()
+                    .map(|item| DryRunOffer {
+                        title: item.title.clone().unwrap_or_else(|| "No title".to_string()),
+                        link: item.link.clone(),
+                        update: item.is_update(),
+                    })
+                    .collect();
+                deliveries.push(DryRunDelivery {
+                    feed: feed.name().to_string(),
+                    destination: destination.name().to_string(),
+                    offers,
+                });
+            }
+        }
+        deliveries
+    }
+
+    /// Writes the in-memory state, used on shutdown if the running check was cancelled
+    pub async fn save_state(&mut self) -> Result<()> {
+        self.states.save(self.store.as_ref()).await?;
+        self.subscriptions.save().await
+    }
+
+    /// Loads the keyword subscriptions and delivers matching offers by direct message,
+    /// without the endpoint for the slash commands
+    pub fn load_subscriptions(&mut self, config: &Config) -> Result<()> {
+        let Some(interactions) = &config.interactions else {
+            return Ok(());
+        };
+
+        self.subscriptions = Subscriptions::load(&config.data_dir)?;
+        match &interactions.bot_token {
+            Some(bot_token) => {
+                self.direct_messenger = Some(DirectMessenger::new(
+                    Self::interactions_client(),
+                    bot_token.expose_secret(),
+                ))
+            }
+            None => warn!("No bot token configured, subscriptions are not delivered"),
+        }
+        Ok(())
+    }
+
+    /// Starts the endpoint for Discord slash commands if configured
+    pub async fn start_interactions(&mut self, config: &Config) -> Result<()> {
+        let Some(interactions) = &config.interactions else {
+            return Ok(());
+        };
+
+        self.load_subscriptions(config)?;
+        let (sender, receiver) = watch::channel(self.create_snapshot(None));
+        interactions::spawn(
+            interactions,
+            Self::interactions_client(),
+            receiver,
+            self.subscriptions.clone(),
+        )
+        .await?;
+        self.snapshot = Some(sender);
+
+        Ok(())
+    }
+
+    /// Client for the Discord API
+    fn interactions_client() -> ClientWithMiddleware {
+        ClientBuilder::new(reqwest::Client::new())
+            .with(TracingMiddleware::default())
+            .build()
+    }
+
+    fn create_snapshot(&self, checked_at: Option<chrono::DateTime<chrono::Utc>>) -> BotSnapshot {
+        let mut offers = self
+            .feeds
+            .iter()
+            .flat_map(|feed| self.states.seen_offers(feed))
+            .collect::<Vec<_>>();
+        offers.sort_by_key(|offer| std::cmp::Reverse(offer.first_seen));
+
+        BotSnapshot {
+            checked_at,
+            feeds: self
+                .feeds
+                .iter()
+                .map(|feed| self.states.status(feed))
+                .collect(),
+            offers,
+        }
     }
 
     #[tracing::instrument]
-    pub async fn check_feed(&mut self, feed: Feed) {
+    pub async fn check_feed(&mut self, feed: &Feed) {
         debug!("Checking feed {}", feed.name());
 
         match feed.fetch(&self.client).await {
             Ok(feed_result) => {
                 // Filter out already sent items
-                trace!("Found {} items for feed", feed_result.items.len());
-                let items = self.states.get_new_feed(&feed, feed_result.items);
+                trace!("Found {} items for feed", feed_result.len());
+                if let Some(history) = &self.history {
+                    if let Err(e) = history.record_seen(feed, &feed_result, chrono::Utc::now()) {
+                        error!("Error recording offer history: {e}");
+                    }
+                }
+                let items = self.states.get_new_feed(feed, feed_result);
                 if items.is_empty() {
                     debug!("No new items found");
-                    return;
-                }
-
-                debug!("Found {} new items", items.len());
-
-                // Increase metrics
-                let counter = metrics::FEED_COUNTER.with_label_values(&[feed.name()]);
-                counter.inc_by(items.len() as u64);
+                } else {
+                    debug!("Found {} new items", items.len());
+                    Self::record_metrics(feed, &items);
 
-                // Send feed to discord
-                for item in items {
-                    if let Err(e) = self.hook.send_discord_message(&feed, item).await {
-                        error!("Error sending message for feed {}: {}", feed.name(), e);
+                    // Items are only removed from the outbox after they were delivered
+                    let now = chrono::Utc::now();
+                    for destination in &self.destinations {
+                        let pending = destination.enqueue(feed, &items, now);
+                        self.states.enqueue(feed, destination.name(), pending);
                     }
+
+                    self.notify_subscribers(feed, &items).await;
                 }
             }
             Err(e) => {
                 error!("Error fetching feed for {}: {}", feed.name(), e);
             }
         }
+
+        // Retries of earlier failures don't depend on a successful fetch
+        self.deliver_pending(feed).await;
+    }
+
+    fn record_metrics(feed: &Feed, items: &[FeedItem]) {
+        let updates = items.iter().filter(|item| item.is_update()).count();
+        let counter = metrics::FEED_COUNTER.with_label_values(&[feed.name()]);
+        counter.inc_by((items.len() - updates) as u64);
+        let counter = metrics::FEED_UPDATE_COUNTER.with_label_values(&[feed.name()]);
+        counter.inc_by(updates as u64);
+        let histogram = metrics::OFFER_PRICE_HISTOGRAM.with_label_values(&[feed.name()]);
+        for item in items.iter().filter(|item| !item.is_update()) {
+            if let Some(price) = item.offer().price {
+                histogram.observe(price.amount);
+            }
+        }
+    }
+
+    /// Sends new offers to the users subscribed to a matching keyword.
+    /// Short rate limits are waited for, other failed messages are not retried
+    async fn notify_subscribers(&self, feed: &Feed, items: &[FeedItem]) {
+        let Some(direct_messenger) = &self.direct_messenger else {
+            return;
+        };
+
+        let mut matches = HashMap::<String, Vec<&FeedItem>>::new();
+        for item in items.iter().filter(|item| !item.is_update()) {
+            for user in self.subscriptions.matching(item) {
+                matches.entry(user).or_default().push(item);
+            }
+        }
+
+        for (user, items) in matches {
+            if let Err(e) = direct_messenger.send(&user, feed, &items).await {
+                error!("Error sending offers to subscriber {user}: {e}");
+            }
+        }
+    }
+
+    /// Delivers to all destinations in parallel, a slow or failing destination doesn't affect the others
+    async fn deliver_pending(&mut self, feed: &Feed) {
+        let deliveries = self.destinations.iter().map(|destination| {
+            let pending = self.states.outbox(feed, destination.name());
+            destination.deliver(feed, pending)
+        });
+        let remaining = futures::future::join_all(deliveries).await;
+
+        for (destination, pending) in self.destinations.iter().zip(remaining) {
+            self.states.set_outbox(feed, destination.name(), pending);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use wiremock::matchers::path;
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    use crate::state_store::json::JsonFileStore;
+
+    use super::*;
+
+    const NETCUP_DE: &[u8] = include_bytes!("../tests/fixtures/netcup_de.xml");
+
+    #[tokio::test]
+    async fn test_run_saves_state_after_hanging_check() {
+        let server = MockServer::start().await;
+        Mock::given(path("/fast.xml"))
+            .respond_with(ResponseTemplate::new(200).set_body_raw(NETCUP_DE, "application/rss+xml"))
+            .mount(&server)
+            .await;
+        Mock::given(path("/hanging.xml"))
+            .respond_with(ResponseTemplate::new(200).set_delay(Duration::from_secs(60)))
+            .mount(&server)
+            .await;
+
+        let dir = tempfile::tempdir().unwrap();
+        let file = dir.path().join("feed_state.json");
+        let feeds = vec![
+            Feed::new("fast", "Fast", &format!("{}/fast.xml", server.uri())),
+            Feed::new(
+                "hanging",
+                "Hanging",
+                &format!("{}/hanging.xml", server.uri()),
+            ),
+        ];
+        let client = ClientBuilder::new(reqwest::Client::new()).build();
+        let mut checker = FeedChecker::new(
+            client,
+            feeds,
+            FeedStates::default(),
+            Box::new(JsonFileStore::new(&file)),
+            Vec::new(),
+        );
+
+        let started = std::time::Instant::now();
+        checker
+            .run(
+                Duration::from_secs(300),
+                Duration::from_secs(2),
+                tokio::time::sleep(Duration::from_millis(500)),
+            )
+            .await
+            .unwrap();
+
+        assert!(started.elapsed() < Duration::from_secs(3));
+        let states = JsonFileStore::new(&file).load().await.unwrap();
+        assert!(states.feeds()["fast"].last_update().is_some());
     }
 }
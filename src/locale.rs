@@ -0,0 +1,54 @@
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, PartialEq, Clone, Copy, Serialize, Deserialize, Eq, Hash)]
+#[serde(rename_all = "lowercase")]
+pub enum Locale {
+    De,
+    En,
+    At,
+}
+
+impl Locale {
+    pub const ALL: [Locale; 3] = [Locale::De, Locale::En, Locale::At];
+
+    pub fn code(&self) -> &'static str {
+        match self {
+            Locale::De => "de",
+            Locale::En => "en",
+            Locale::At => "at",
+        }
+    }
+
+    pub fn from_code(code: &str) -> Option<Self> {
+        Self::ALL
+            .into_iter()
+            .find(|locale| locale.code().eq_ignore_ascii_case(code))
+    }
+}
+
+impl fmt::Display for Locale {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.code().to_uppercase())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_from_code() {
+        assert_eq!(Locale::from_code("de"), Some(Locale::De));
+        assert_eq!(Locale::from_code("EN"), Some(Locale::En));
+        assert_eq!(Locale::from_code("at"), Some(Locale::At));
+        assert_eq!(Locale::from_code("fr"), None);
+    }
+
+    #[test]
+    fn test_deserialize() {
+        let locales: Vec<Locale> = serde_json::from_str(r#"["de", "en", "at"]"#).unwrap();
+        assert_eq!(locales, Locale::ALL.to_vec());
+    }
+}
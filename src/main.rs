@@ -1,16 +1,18 @@
 #[macro_use]
 extern crate tracing;
 
+use chrono::{DateTime, Utc};
+use clap::{Parser, Subcommand};
+use netcup_offer_bot::admin;
 use netcup_offer_bot::config::Config;
 use netcup_offer_bot::FeedChecker;
 use netcup_offer_bot::Result;
 use sentry::ClientInitGuard;
 use std::env;
+use std::future::Future;
 use std::net::SocketAddr;
 use std::str::FromStr;
-use tokio::time;
-use tokio_stream::wrappers::IntervalStream;
-use tokio_stream::StreamExt;
+use tokio::signal::unix::{signal, SignalKind};
 use tracing_subscriber::layer::SubscriberExt;
 use tracing_subscriber::util::SubscriberInitExt;
 use tracing_subscriber::{filter, Layer};
@@ -20,37 +22,159 @@ const ENV_LOG_LEVEL: &str = "LOG_LEVEL";
 
 const DEFAULT_LOG_LEVEL: &str = "info";
 
+#[derive(Parser)]
+#[command(version, about = "Announces new offers of netcup and other feeds")]
+struct Cli {
+    #[command(subcommand)]
+    command: Option<Command>,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Checks the feeds in the configured interval until SIGINT or SIGTERM [default]
+    Run,
+    /// Checks the feeds once and exits, for cron jobs and systemd timers
+    CheckOnce,
+    /// Prints the offers the next check would send, without sending them or saving the state
+    DryRun,
+    /// Inspects or changes the stored feed state
+    #[command(subcommand)]
+    State(StateCommand),
+    /// Loads the configuration and exits
+    ValidateConfig,
+}
+
+#[derive(Subcommand)]
+enum StateCommand {
+    /// Prints the feed state as JSON
+    Show,
+    /// Forgets the known offers, they are announced again on the next check
+    Reset {
+        /// Only reset this feed
+        #[arg(long)]
+        feed: Option<String>,
+    },
+    /// Only announces offers of the feed published after the date
+    SetWatermark {
+        feed: String,
+        /// RFC 3339 date, e.g. 2025-01-14T10:00:00Z
+        date: DateTime<Utc>,
+    },
+}
+
 #[tokio::main]
 async fn main() -> Result<()> {
+    let cli = Cli::parse();
     setup_tracing()?;
 
-    let dns = match env::var(ENV_SENTRY_DSN) {
-        Ok(dns) => Some(dns),
-        Err(_) => None,
-    };
+    let dns = env::var(ENV_SENTRY_DSN).ok();
     // Prevents the process from exiting until all events are sent
-    let _sentry = setup_sentry(dns);
+    let sentry = setup_sentry(dns);
 
     let config = Config::get_configurations()?;
 
+    let result = match cli.command.unwrap_or(Command::Run) {
+        Command::Run => run(&config).await,
+        Command::CheckOnce => check_once(&config).await,
+        Command::DryRun => dry_run(&config).await,
+        Command::State(command) => state(&config, command).await,
+        Command::ValidateConfig => {
+            println!(
+                "Configuration is valid: {} feeds, {} destinations",
+                config.feeds.len(),
+                config.destinations.len()
+            );
+            Ok(())
+        }
+    };
+    if let Err(e) = &result {
+        error!("{e}");
+    }
+
+    // Sends the pending events, the guard would be dropped only after the error is printed
+    drop(sentry);
+    result
+}
+
+async fn run(config: &Config) -> Result<()> {
     setup_metrics(&config.metric_socket)?;
 
     info!("Starting feed bot");
-    let mut checker = FeedChecker::from_config(&config);
-    let mut stream = IntervalStream::new(time::interval(config.check_interval));
-    while let Some(_ts) = stream.next().await {
-        checker.check_feeds().await;
+    let mut checker = FeedChecker::from_config(config).await?;
+    checker.start_interactions(config).await?;
+
+    checker
+        .run(
+            config.check_interval,
+            config.shutdown_timeout,
+            shutdown_signal()?,
+        )
+        .await?;
+    info!("Feed bot stopped");
+    Ok(())
+}
+
+async fn check_once(config: &Config) -> Result<()> {
+    let mut checker = FeedChecker::from_config(config).await?;
+    checker.load_subscriptions(config)?;
+    checker.check_feeds().await;
+    Ok(())
+}
+
+async fn dry_run(config: &Config) -> Result<()> {
+    let mut checker = FeedChecker::from_config_read_only(config).await?;
+    for delivery in checker.dry_run().await {
+        println!(
+            "{} -> {}: {} offers",
+            delivery.feed,
+            delivery.destination,
+            delivery.offers.len()
+        );
+        for offer in delivery.offers {
+            let kind = if offer.update { "updated" } else { "new" };
+            let link = offer.link.unwrap_or_default();
+            println!("  [{kind}] {} {link}", offer.title);
+        }
     }
+    Ok(())
+}
 
+async fn state(config: &Config, command: StateCommand) -> Result<()> {
+    match command {
+        StateCommand::Show => println!("{}", admin::show_state(config).await?),
+        StateCommand::Reset { feed } => admin::reset_state(config, feed.as_deref()).await?,
+        StateCommand::SetWatermark { feed, date } => {
+            admin::set_watermark(config, &feed, date).await?
+        }
+    }
     Ok(())
 }
 
+/// Resolves on SIGINT or SIGTERM, which is sent by `docker stop`
+fn shutdown_signal() -> Result<impl Future<Output = ()>> {
+    // Registered right away, signals before the first poll are not lost
+    let mut terminate = signal(SignalKind::terminate())?;
+    let mut interrupt = signal(SignalKind::interrupt())?;
+
+    Ok(async move {
+        tokio::select! {
+            _ = terminate.recv() => info!("Received SIGTERM, shutting down"),
+            _ = interrupt.recv() => info!("Received SIGINT, shutting down"),
+        }
+    })
+}
+
 fn setup_tracing() -> Result<()> {
     let level = env::var(ENV_LOG_LEVEL).unwrap_or_else(|_| DEFAULT_LOG_LEVEL.to_string());
     let level = tracing::Level::from_str(&level)?;
 
     tracing_subscriber::registry()
-        .with(tracing_subscriber::fmt::layer().with_filter(filter::LevelFilter::from_level(level)))
+        // Keeps stdout for the output of the commands
+        .with(
+            tracing_subscriber::fmt::layer()
+                .with_writer(std::io::stderr)
+                .with_filter(filter::LevelFilter::from_level(level)),
+        )
         .with(sentry::integrations::tracing::layer().with_filter(filter::LevelFilter::DEBUG))
         .init();
 
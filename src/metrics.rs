@@ -1,8 +1,36 @@
 use lazy_static::lazy_static;
-use prometheus::{register_int_counter_vec, IntCounterVec};
+use prometheus::{
+    register_histogram_vec, register_int_counter_vec, register_int_gauge_vec, HistogramVec,
+    IntCounterVec, IntGaugeVec,
+};
 
 lazy_static! {
     pub static ref FEED_COUNTER: IntCounterVec =
         register_int_counter_vec!("feed_counter", "Number of send feeds", &["feed"])
             .expect("Failed to register feed counter metric");
+    pub static ref FEED_UPDATE_COUNTER: IntCounterVec = register_int_counter_vec!(
+        "feed_update_counter",
+        "Number of send updates of already announced items",
+        &["feed"]
+    )
+    .expect("Failed to register feed update counter metric");
+    pub static ref DESTINATION_COUNTER: IntCounterVec = register_int_counter_vec!(
+        "destination_counter",
+        "Number of send messages per destination",
+        &["destination", "feed"]
+    )
+    .expect("Failed to register destination counter metric");
+    pub static ref PENDING_DELIVERIES: IntGaugeVec = register_int_gauge_vec!(
+        "pending_deliveries",
+        "Number of items waiting to be delivered per destination",
+        &["destination", "feed"]
+    )
+    .expect("Failed to register pending deliveries metric");
+    pub static ref OFFER_PRICE_HISTOGRAM: HistogramVec = register_histogram_vec!(
+        "offer_price_euro",
+        "Price of send offers in EUR",
+        &["feed"],
+        vec![1.0, 2.5, 5.0, 10.0, 20.0, 50.0, 100.0, 250.0, 500.0]
+    )
+    .expect("Failed to register offer price metric");
 }
@@ -0,0 +1,291 @@
+use reqwest::StatusCode;
+use reqwest_middleware::ClientWithMiddleware;
+use secrecy::{ExposeSecret, SecretBox};
+use serde::{Deserialize, Serialize};
+
+use crate::error::{DiscordError, Error};
+use crate::feed::Feed;
+use crate::feed_item::FeedItem;
+use crate::notifier::discord::{
+    pack_embeds, retry_after, Embed, MAX_RATE_LIMIT_RETRIES, MAX_RATE_LIMIT_WAIT,
+};
+use crate::notifier::mention::AllowedMentions;
+use crate::notifier::Message;
+use crate::Result;
+
+pub const DISCORD_API_URL: &str = "https://discord.com/api/v10";
+
+#[derive(Debug, Serialize)]
+struct CreateChannel<'a> {
+    recipient_id: &'a str,
+}
+
+#[derive(Debug, Deserialize)]
+struct Channel {
+    id: String,
+}
+
+#[derive(Debug, Serialize)]
+struct ChannelMessage {
+    content: String,
+    embeds: Vec<Embed>,
+    allowed_mentions: AllowedMentions,
+}
+
+/// Sends offers as direct message from the bot user, used for keyword subscriptions
+#[derive(Debug)]
+pub struct DirectMessenger {
+    client: ClientWithMiddleware,
+    bot_token: SecretBox<String>,
+    api_url: String,
+}
+
+impl DirectMessenger {
+    pub fn new(client: ClientWithMiddleware, bot_token: &str) -> Self {
+        Self {
+            client,
+            bot_token: SecretBox::new(Box::new(bot_token.to_string())),
+            api_url: DISCORD_API_URL.to_string(),
+        }
+    }
+
+    #[cfg(test)]
+    fn with_api_url(mut self, api_url: &str) -> Self {
+        self.api_url = api_url.to_string();
+        self
+    }
+
+    /// Waits for short rate limits, messages aren't queued so a 429 would lose the offers
+    async fn post<T: Serialize>(&self, path: &str, body: &T) -> Result<reqwest::Response> {
+        let mut retries = 0;
+        loop {
+            let response = self
+                .client
+                .post(format!("{}{path}", self.api_url))
+                .header(
+                    reqwest::header::AUTHORIZATION,
+                    format!("Bot {}", self.bot_token.expose_secret()),
+                )
+                .json(body)
+                .send()
+                .await?;
+
+            let status = response.status();
+            if status.is_success() {
+                return Ok(response);
+            }
+
+            if status == StatusCode::TOO_MANY_REQUESTS {
+                let retry_after = retry_after(response).await;
+                if retries >= MAX_RATE_LIMIT_RETRIES || retry_after > MAX_RATE_LIMIT_WAIT {
+                    return Err(DiscordError::RateLimited(retry_after).into());
+                }
+
+                retries += 1;
+                warn!("Discord rate limit hit for direct messages, retrying in {retry_after:?}");
+                tokio::time::sleep(retry_after).await;
+                continue;
+            }
+
+            let body = response.text().await.unwrap_or_default();
+            return Err(super::discord::DiscordWebhook::status_error(status, body).into());
+        }
+    }
+
+    /// Sends the items to the user, up to 10 offers per message within the embed length limit
+    #[tracing::instrument(skip(items))]
+    pub async fn send(&self, user: &str, feed: &Feed, items: &[&FeedItem]) -> Result<()> {
+        // Returns the existing channel if the bot already wrote to the user
+        let channel = self
+            .post("/users/@me/channels", &CreateChannel { recipient_id: user })
+            .await?
+            .json::<Channel>()
+            .await?;
+
+        let embeds = items
+            .iter()
+            .map(|item| Embed::new(&Message::new(item)))
+            .collect::<Vec<Embed>>();
+        for embed in &embeds {
+            embed.validate().map_err(Error::from)?;
+        }
+
+        for embeds in pack_embeds(embeds) {
+            info!(
+                "Sending {} offers of feed {} to subscriber {user}",
+                embeds.len(),
+                feed.name()
+            );
+            self.post(
+                &format!("/channels/{}/messages", channel.id),
+                &ChannelMessage {
+                    content: format!("New offers matching your subscriptions on {}", feed.name()),
+                    embeds,
+                    allowed_mentions: AllowedMentions::default(),
+                },
+            )
+            .await?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use wiremock::matchers::{body_partial_json, header, method, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    use crate::notifier::tests::{create_client, create_feed, create_item};
+
+    use super::*;
+
+    #[tokio::test]
+    async fn test_send() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/users/@me/channels"))
+            .and(header("authorization", "Bot token"))
+            .and(body_partial_json(serde_json::json!({"recipient_id": "1"})))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({"id": "42"})))
+            .expect(1)
+            .mount(&server)
+            .await;
+        Mock::given(method("POST"))
+            .and(path("/channels/42/messages"))
+            .and(body_partial_json(serde_json::json!({
+                "embeds": [{"title": "RS 1000 <G11>"}],
+                "allowed_mentions": {"parse": []}
+            })))
+            .respond_with(ResponseTemplate::new(200))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let item = create_item();
+        DirectMessenger::new(create_client(), "token")
+            .with_api_url(&server.uri())
+            .send("1", &create_feed(), &[&item])
+            .await
+            .unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_send_retry_after_rate_limit() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/users/@me/channels"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({"id": "42"})))
+            .mount(&server)
+            .await;
+        Mock::given(method("POST"))
+            .and(path("/channels/42/messages"))
+            .respond_with(
+                ResponseTemplate::new(429)
+                    .set_body_json(serde_json::json!({"retry_after": 0.05, "global": false})),
+            )
+            .up_to_n_times(1)
+            .expect(1)
+            .mount(&server)
+            .await;
+        Mock::given(method("POST"))
+            .and(path("/channels/42/messages"))
+            .respond_with(ResponseTemplate::new(200))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let item = create_item();
+        DirectMessenger::new(create_client(), "token")
+            .with_api_url(&server.uri())
+            .send("1", &create_feed(), &[&item])
+            .await
+            .unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_send_long_descriptions() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/users/@me/channels"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({"id": "42"})))
+            .mount(&server)
+            .await;
+        Mock::given(method("POST"))
+            .and(path("/channels/42/messages"))
+            .respond_with(ResponseTemplate::new(200))
+            .mount(&server)
+            .await;
+
+        let items = (0..10)
+            .map(|index| FeedItem {
+                title: Some(format!("RS {index}")),
+                description: Some("8 GB RAM, 256 GB SSD. ".repeat(100)),
+                ..create_item()
+            })
+            .collect::<Vec<FeedItem>>();
+        let items = items.iter().collect::<Vec<&FeedItem>>();
+        DirectMessenger::new(create_client(), "token")
+            .with_api_url(&server.uri())
+            .send("1", &create_feed(), &items)
+            .await
+            .unwrap();
+
+        let messages = server
+            .received_requests()
+            .await
+            .unwrap()
+            .into_iter()
+            .filter(|request| request.url.path() == "/channels/42/messages")
+            .map(|request| request.body_json::<serde_json::Value>().unwrap())
+            .collect::<Vec<serde_json::Value>>();
+        assert!(messages.len() > 1);
+        let mut embeds = 0;
+        for message in messages {
+            let message_embeds = message["embeds"].as_array().unwrap();
+            embeds += message_embeds.len();
+            let text_length =
+                |value: &serde_json::Value| value.as_str().map_or(0, |text| text.chars().count());
+            let length = message_embeds
+                .iter()
+                .map(|embed| {
+                    text_length(&embed["title"])
+                        + text_length(&embed["description"])
+                        + text_length(&embed["footer"]["text"])
+                        + embed["fields"].as_array().map_or(0, |fields| {
+                            fields
+                                .iter()
+                                .map(|field| {
+                                    text_length(&field["name"]) + text_length(&field["value"])
+                                })
+                                .sum()
+                        })
+                })
+                .sum::<usize>();
+            assert!(length <= 6000, "{length}");
+        }
+        assert_eq!(embeds, 10);
+    }
+
+    #[tokio::test]
+    async fn test_send_blocked() {
+        let server = MockServer::start().await;
+        // Users that don't share a server with the bot or disabled DMs
+        Mock::given(method("POST"))
+            .respond_with(ResponseTemplate::new(403).set_body_json(serde_json::json!({
+                "message": "Cannot send messages to this user",
+                "code": 50007
+            })))
+            .mount(&server)
+            .await;
+
+        let item = create_item();
+        let error = DirectMessenger::new(create_client(), "token")
+            .with_api_url(&server.uri())
+            .send("1", &create_feed(), &[&item])
+            .await
+            .unwrap_err();
+
+        assert!(error.to_string().contains("Discord"));
+    }
+}
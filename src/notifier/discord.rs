@@ -0,0 +1,1525 @@
+use std::time::Duration;
+
+use async_trait::async_trait;
+use reqwest::header::HeaderMap;
+use reqwest::StatusCode;
+use reqwest_middleware::ClientWithMiddleware;
+use serde::{Deserialize, Serialize};
+use tokio::sync::Mutex;
+use tokio::time::Instant;
+
+use crate::error::{DiscordError, Error};
+use crate::feed::Feed;
+use crate::feed_item::FeedItem;
+use crate::notifier::markdown::{escape_markdown, html_to_markdown, truncate_markdown};
+use crate::notifier::mention::{AllowedMentions, MentionRule, Mentions};
+use crate::notifier::template::MessageTemplate;
+use crate::notifier::{notify_each, Delivery, Message, Notifier};
+use crate::Result;
+
+// https://discord.com/developers/docs/resources/message#embed-object-embed-limits
+const MAX_TITLE_LENGTH: usize = 256;
+const MAX_DESCRIPTION_LENGTH: usize = 4096;
+const MAX_FIELDS: usize = 25;
+const MAX_FIELD_NAME_LENGTH: usize = 256;
+const MAX_FIELD_VALUE_LENGTH: usize = 1024;
+const MAX_FOOTER_LENGTH: usize = 2048;
+/// Shared by all embeds of a message
+const MAX_EMBED_LENGTH: usize = 6000;
+const MAX_EMBEDS: usize = 10;
+// https://discord.com/developers/docs/resources/webhook#execute-webhook
+const MAX_CONTENT_LENGTH: usize = 2000;
+
+// More messages than this are replaced by a digest of all items
+const MAX_BATCH_MESSAGES: usize = 3;
+// Leaves room for two digest embeds per message
+const MAX_DIGEST_DESCRIPTION_LENGTH: usize = 2800;
+const MAX_DIGEST_TITLE_LENGTH: usize = 100;
+
+// Retries after a 429 before the item is left to the outbox
+pub(super) const MAX_RATE_LIMIT_RETRIES: u32 = 3;
+// Longer waits block the check loop, those items are retried by the outbox instead
+pub(super) const MAX_RATE_LIMIT_WAIT: Duration = Duration::from_secs(30);
+
+const HEADER_REMAINING: &str = "x-ratelimit-remaining";
+const HEADER_RESET_AFTER: &str = "x-ratelimit-reset-after";
+const HEADER_RETRY_AFTER: &str = "retry-after";
+
+#[derive(Debug, Serialize, PartialEq)]
+pub struct WebhookMessage {
+    pub username: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub avatar_url: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub content: Option<String>,
+    pub embeds: Vec<Embed>,
+    pub allowed_mentions: AllowedMentions,
+}
+
+#[derive(Debug, Serialize, PartialEq)]
+pub struct Embed {
+    pub title: String,
+    pub description: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub url: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub color: Option<u32>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub fields: Vec<EmbedField>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub footer: Option<EmbedFooter>,
+}
+
+#[derive(Debug, Serialize, PartialEq)]
+pub struct EmbedFooter {
+    pub text: String,
+}
+
+#[derive(Debug, Serialize, PartialEq)]
+pub struct EmbedField {
+    pub name: String,
+    pub value: String,
+    pub inline: bool,
+}
+
+/// Diff as code block, cut at a line boundary to fit into the description
+fn diff_block(diff: &str) -> String {
+    // A fence in the text would end the code block early
+    let diff = diff.replace("```", "`\u{200b}``");
+    truncate_code_block(&format!("```diff\n{diff}\n```"), MAX_DESCRIPTION_LENGTH)
+}
+
+/// Cuts text starting with a code block at a line boundary and closes the block again
+fn truncate_code_block(text: &str, max: usize) -> String {
+    const CLOSE: &str = "\n```";
+    const CUT: &str = "\n…";
+
+    if text.chars().count() <= max {
+        return text.to_string();
+    }
+
+    let budget = max.saturating_sub(CLOSE.len() + CUT.chars().count());
+    let mut truncated = text.chars().take(budget).collect::<String>();
+    // The opening line with the language is kept
+    let opening = truncated.find('\n').unwrap_or(truncated.len());
+    if let Some(position) = truncated.rfind('\n').filter(|position| *position > opening) {
+        truncated.truncate(position);
+    }
+    truncated.push_str(CUT);
+    if truncated.matches("```").count() % 2 == 1 {
+        truncated.push_str(CLOSE);
+    }
+    truncated
+}
+
+impl Embed {
+    pub fn new(message: &Message) -> Self {
+        let description = match &message.html_description {
+            _ if message.is_diff => diff_block(&message.description),
+            Some(html) => html_to_markdown(html),
+            None => escape_markdown(&message.description),
+        };
+
+        let mut fields = message
+            .fields
+            .iter()
+            .map(|field| EmbedField {
+                name: field.name.to_string(),
+                value: field.value.clone(),
+                inline: field.inline,
+            })
+            .collect::<Vec<EmbedField>>();
+
+        // Links that don't fit are left out instead of being cut
+        let mut alternates = String::new();
+        for (locale, url) in &message.alternates {
+            let link = format!("[{locale}]({url})");
+            let separator = if alternates.is_empty() { "" } else { " | " };
+            if alternates.chars().count() + separator.len() + link.chars().count()
+                > MAX_FIELD_VALUE_LENGTH
+            {
+                break;
+            }
+            alternates.push_str(separator);
+            alternates.push_str(&link);
+        }
+        if !alternates.is_empty() {
+            fields.push(EmbedField {
+                name: "Other languages".to_string(),
+                value: alternates,
+                inline: false,
+            });
+        }
+
+        let mut embed = Self {
+            title: message.title.clone(),
+            description,
+            url: message.link.clone(),
+            color: None,
+            fields,
+            footer: None,
+        };
+        embed.fit();
+        embed
+    }
+
+    /// Shortens all parts to the Discord limits, the description and then the last fields
+    /// give way if the whole embed is still too long
+    pub fn fit(&mut self) {
+        let url = self.url.clone();
+        let shorten = |description: &str, max: usize| {
+            if description.starts_with("```") {
+                truncate_code_block(description, max)
+            } else {
+                truncate_markdown(description, max, url.as_deref())
+            }
+        };
+
+        self.title = truncate(&self.title, MAX_TITLE_LENGTH);
+        self.description = shorten(&self.description, MAX_DESCRIPTION_LENGTH);
+        self.fields.truncate(MAX_FIELDS);
+        for field in &mut self.fields {
+            field.name = truncate(&field.name, MAX_FIELD_NAME_LENGTH);
+            field.value = truncate(&field.value, MAX_FIELD_VALUE_LENGTH);
+        }
+        if let Some(footer) = &mut self.footer {
+            footer.text = truncate(&footer.text, MAX_FOOTER_LENGTH);
+        }
+
+        let excess = self.length().saturating_sub(MAX_EMBED_LENGTH);
+        if excess > 0 {
+            let description = self.description.chars().count();
+            self.description =
+                shorten(&self.description, description.saturating_sub(excess).max(1));
+        }
+        while self.length() > MAX_EMBED_LENGTH && self.fields.pop().is_some() {}
+    }
+
+    /// Number of characters counted against the embed limit
+    pub fn length(&self) -> usize {
+        self.title.chars().count()
+            + self.description.chars().count()
+            + self
+                .fields
+                .iter()
+                .map(|field| field.name.chars().count() + field.value.chars().count())
+                .sum::<usize>()
+            + self
+                .footer
+                .as_ref()
+                .map_or(0, |footer| footer.text.chars().count())
+    }
+
+    pub(super) fn validate(&self) -> std::result::Result<(), DiscordError> {
+        let too_large = |what: &str, length: usize, max: usize| {
+            Err(DiscordError::PayloadTooLarge(format!(
+                "{what} has {length} characters, the limit is {max}"
+            )))
+        };
+
+        let title = self.title.chars().count();
+        if title > MAX_TITLE_LENGTH {
+            return too_large("Title", title, MAX_TITLE_LENGTH);
+        }
+        let description = self.description.chars().count();
+        if description > MAX_DESCRIPTION_LENGTH {
+            return too_large("Description", description, MAX_DESCRIPTION_LENGTH);
+        }
+        if self.fields.len() > MAX_FIELDS {
+            return too_large("Field list", self.fields.len(), MAX_FIELDS);
+        }
+        for field in &self.fields {
+            let name = field.name.chars().count();
+            if name > MAX_FIELD_NAME_LENGTH {
+                return too_large("Field name", name, MAX_FIELD_NAME_LENGTH);
+            }
+            let value = field.value.chars().count();
+            if value > MAX_FIELD_VALUE_LENGTH {
+                return too_large(
+                    &format!("Field {}", field.name),
+                    value,
+                    MAX_FIELD_VALUE_LENGTH,
+                );
+            }
+        }
+        if let Some(footer) = &self.footer {
+            let footer = footer.text.chars().count();
+            if footer > MAX_FOOTER_LENGTH {
+                return too_large("Footer", footer, MAX_FOOTER_LENGTH);
+            }
+        }
+        let length = self.length();
+        if length > MAX_EMBED_LENGTH {
+            return too_large("Embed", length, MAX_EMBED_LENGTH);
+        }
+
+        Ok(())
+    }
+}
+
+fn validate_message(message: &WebhookMessage) -> std::result::Result<(), DiscordError> {
+    if let Some(content) = &message.content {
+        let length = content.chars().count();
+        if length > MAX_CONTENT_LENGTH {
+            return Err(DiscordError::PayloadTooLarge(format!(
+                "Content has {length} characters, the limit is {MAX_CONTENT_LENGTH}"
+            )));
+        }
+    }
+    message.embeds.iter().try_for_each(Embed::validate)
+}
+
+/// Message parts outside of the embed, the feed name is used if no username is set
+#[derive(Debug, Default, Clone, PartialEq)]
+struct Header {
+    username: Option<String>,
+    avatar_url: Option<String>,
+    content: Option<String>,
+    mentions: Mentions,
+}
+
+/// Embed with the indices of the items it shows
+#[derive(Debug)]
+struct Entry {
+    items: Vec<usize>,
+    embed: Embed,
+    header: Header,
+}
+
+/// Embeds that are sent together in one message, with the header of the first one.
+/// The mentions of all embeds are combined
+#[derive(Debug, Default)]
+struct Batch {
+    items: Vec<usize>,
+    embeds: Vec<Embed>,
+    length: usize,
+    header: Header,
+    contents: Vec<String>,
+    mentions: Mentions,
+}
+
+/// Mentions followed by the distinct contents of the items, one per line.
+/// Contents can't ping anyone the mentions don't
+fn batch_content(mentions: &Mentions, contents: &[String]) -> Option<String> {
+    let content = mentions
+        .to_content()
+        .into_iter()
+        .chain(contents.iter().map(|content| mentions.neutralize(content)))
+        .collect::<Vec<String>>();
+    (!content.is_empty()).then(|| content.join("\n"))
+}
+
+impl Batch {
+    /// Whether the content stays within the limit with the header of another entry
+    fn fits_content(&self, header: &Header) -> bool {
+        let mut mentions = self.mentions.clone();
+        mentions.merge(&header.mentions);
+        let mut contents = self.contents.clone();
+        if let Some(content) = &header.content {
+            if !contents.contains(content) {
+                contents.push(content.clone());
+            }
+        }
+
+        batch_content(&mentions, &contents)
+            .is_none_or(|content| content.chars().count() <= MAX_CONTENT_LENGTH)
+    }
+
+    fn into_message(self, feed: &Feed) -> (Vec<usize>, WebhookMessage) {
+        // Batches are split before the content gets too long, only the content of a single item can exceed it.
+        // The mentions come first and are kept
+        let content = batch_content(&self.mentions, &self.contents)
+            .map(|content| truncate(&content, MAX_CONTENT_LENGTH));
+        let message = WebhookMessage {
+            username: self
+                .header
+                .username
+                .unwrap_or_else(|| format!("Feed - {}", feed.name())),
+            avatar_url: self.header.avatar_url,
+            content,
+            embeds: self.embeds,
+            allowed_mentions: self.mentions.allowed_mentions(),
+        };
+        (self.items, message)
+    }
+}
+
+/// Packs the embeds into as few messages as possible, keeping their order
+fn pack(entries: Vec<Entry>) -> Vec<Batch> {
+    let mut batches: Vec<Batch> = Vec::new();
+    for entry in entries {
+        let length = entry.embed.length();
+        let batch = match batches.last_mut() {
+            Some(batch)
+                if batch.embeds.len() < MAX_EMBEDS
+                    && batch.length + length <= MAX_EMBED_LENGTH
+                    && batch.fits_content(&entry.header) =>
+            {
+                batch
+            }
+            _ => {
+                batches.push(Batch {
+                    header: entry.header.clone(),
+                    ..Default::default()
+                });
+                batches.last_mut().expect("Batch was just added")
+            }
+        };
+
+        batch.mentions.merge(&entry.header.mentions);
+        if let Some(content) = entry.header.content {
+            if !batch.contents.contains(&content) {
+                batch.contents.push(content);
+            }
+        }
+        batch.items.extend(entry.items);
+        batch.embeds.push(entry.embed);
+        batch.length += length;
+    }
+    batches
+}
+
+/// Groups embeds without a header into messages within the embed limits, keeping their order
+pub(super) fn pack_embeds(embeds: Vec<Embed>) -> Vec<Vec<Embed>> {
+    let entries = embeds
+        .into_iter()
+        .enumerate()
+        .map(|(index, embed)| Entry {
+            items: vec![index],
+            embed,
+            header: Header::default(),
+        })
+        .collect();
+    pack(entries)
+        .into_iter()
+        .map(|batch| batch.embeds)
+        .collect()
+}
+
+fn truncate(text: &str, max: usize) -> String {
+    if text.chars().count() <= max {
+        return text.to_string();
+    }
+    let mut truncated = text.chars().take(max - 1).collect::<String>();
+    truncated.push('…');
+    truncated
+}
+
+fn digest_line(item: &FeedItem) -> String {
+    let message = Message::new(item);
+    let title = truncate(&message.title, MAX_DIGEST_TITLE_LENGTH);
+    let mut line = match &message.link {
+        Some(link) => format!("• [{title}]({link})"),
+        None => format!("• {title}"),
+    };
+    if let Some(price) = item.offer().price.filter(|_| !item.is_update()) {
+        line.push_str(&format!(" - {price}"));
+    }
+    // Links can be arbitrarily long, the line has to fit into one embed
+    truncate(&line, MAX_DIGEST_DESCRIPTION_LENGTH)
+}
+
+/// Summary embeds with one line per item
+fn digest(items: &[(usize, &FeedItem)]) -> Vec<Entry> {
+    let mut entries = Vec::new();
+    let mut indices = Vec::new();
+    let mut lines: Vec<String> = Vec::new();
+    let mut length = 0;
+
+    let mut flush = |indices: &mut Vec<usize>, lines: &mut Vec<String>| {
+        if lines.is_empty() {
+            return;
+        }
+        entries.push(Entry {
+            embed: Embed {
+                title: format!("{} offers", lines.len()),
+                description: lines.join("\n"),
+                url: None,
+                color: None,
+                fields: Vec::new(),
+                footer: None,
+            },
+            items: std::mem::take(indices),
+            header: Header::default(),
+        });
+        lines.clear();
+    };
+
+    for (index, item) in items {
+        let line = digest_line(item);
+        let line_length = line.chars().count() + 1;
+        if length + line_length > MAX_DIGEST_DESCRIPTION_LENGTH {
+            flush(&mut indices, &mut lines);
+            length = 0;
+        }
+        length += line_length;
+        indices.push(*index);
+        lines.push(line);
+    }
+    flush(&mut indices, &mut lines);
+
+    entries
+}
+
+/// Rate limit bucket of the webhook, updated from the response headers
+#[derive(Debug, Default)]
+struct RateLimit {
+    remaining: Option<u64>,
+    /// Time of the response and the reset after it
+    reset: Option<(Instant, Duration)>,
+}
+
+impl RateLimit {
+    fn update(&mut self, headers: &HeaderMap, now: Instant) {
+        if let Some(remaining) = header_value(headers, HEADER_REMAINING) {
+            self.remaining = Some(remaining as u64);
+        }
+        if let Some(reset_after) = header_value(headers, HEADER_RESET_AFTER) {
+            self.reset = Some((now, wait_duration(reset_after)));
+        }
+    }
+
+    /// Time to wait until the bucket has capacity again
+    fn delay(&self, now: Instant) -> Option<Duration> {
+        match (self.remaining, self.reset) {
+            (Some(0), Some((updated, reset_after))) => reset_after
+                .checked_sub(now.saturating_duration_since(updated))
+                .filter(|delay| !delay.is_zero()),
+            _ => None,
+        }
+    }
+}
+
+/// Wait given by Discord in seconds, values too large for a `Duration` wait forever
+fn wait_duration(seconds: f64) -> Duration {
+    Duration::try_from_secs_f64(seconds).unwrap_or(Duration::MAX)
+}
+
+#[derive(Debug, Deserialize)]
+struct RateLimitResponse {
+    retry_after: Option<f64>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ErrorResponse {
+    code: Option<u64>,
+    message: Option<String>,
+}
+
+fn header_value(headers: &HeaderMap, name: &str) -> Option<f64> {
+    headers
+        .get(name)?
+        .to_str()
+        .ok()?
+        .parse::<f64>()
+        .ok()
+        .filter(|value| value.is_finite() && *value >= 0.0)
+}
+
+/// Wait requested by a 429 response, the body is more precise than the header
+pub(super) async fn retry_after(response: reqwest::Response) -> Duration {
+    let header = header_value(response.headers(), HEADER_RETRY_AFTER);
+    let body = response.json::<RateLimitResponse>().await.ok();
+    wait_duration(
+        body.and_then(|body| body.retry_after)
+            .filter(|value| value.is_finite() && *value >= 0.0)
+            .or(header)
+            .unwrap_or(1.0),
+    )
+}
+
+/// Discord webhook client that paces messages according to the rate limit headers.
+/// Messages to the same webhook are sent one after the other
+pub struct DiscordWebhook {
+    client: ClientWithMiddleware,
+    url: String,
+    batch: bool,
+    template: Option<MessageTemplate>,
+    mentions: Vec<MentionRule>,
+    rate_limit: Mutex<RateLimit>,
+}
+
+impl std::fmt::Debug for DiscordWebhook {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("DiscordWebhook").finish()
+    }
+}
+
+impl DiscordWebhook {
+    pub fn new(client: ClientWithMiddleware, url: &str) -> Self {
+        DiscordWebhook {
+            client,
+            url: url.to_string(),
+            batch: false,
+            template: None,
+            mentions: Vec::new(),
+            rate_limit: Mutex::new(RateLimit::default()),
+        }
+    }
+
+    /// Combines multiple items into one message, see [`Notifier::notify_all`]
+    pub fn with_batching(mut self, batch: bool) -> Self {
+        self.batch = batch;
+        self
+    }
+
+    /// Renders single items with the template instead of the default layout
+    pub fn with_template(mut self, template: Option<MessageTemplate>) -> Self {
+        self.template = template;
+        self
+    }
+
+    /// Pings roles or users for items matching the rules
+    pub fn with_mentions(mut self, mentions: Vec<MentionRule>) -> Self {
+        self.mentions = mentions;
+        self
+    }
+
+    fn entry(&self, feed: &Feed, index: usize, item: &FeedItem) -> Result<Entry> {
+        let mut embed = Embed::new(&Message::new(item));
+        let mentions = MentionRule::matching(&self.mentions, item);
+        let Some(template) = &self.template else {
+            return Ok(Entry {
+                items: vec![index],
+                embed,
+                header: Header {
+                    mentions,
+                    ..Default::default()
+                },
+            });
+        };
+
+        let rendered = template.render(feed, item)?;
+        if let Some(title) = rendered.title {
+            embed.title = title;
+        }
+        if let Some(description) = rendered.description {
+            embed.description = description;
+        }
+        if let Some(fields) = rendered.fields {
+            embed.fields = fields
+                .into_iter()
+                .map(|field| EmbedField {
+                    name: field.name,
+                    value: field.value,
+                    inline: field.inline,
+                })
+                .collect();
+        }
+        embed.color = rendered.color;
+        embed.footer = rendered.footer.map(|text| EmbedFooter { text });
+        embed.fit();
+
+        Ok(Entry {
+            items: vec![index],
+            embed,
+            header: Header {
+                username: rendered.username,
+                avatar_url: rendered.avatar_url,
+                content: rendered.content,
+                mentions,
+            },
+        })
+    }
+
+    /// Digest messages, the first one pings for all items matching a mention rule
+    fn digest_batches(&self, items: &[(usize, &FeedItem)]) -> Vec<Batch> {
+        let mut batches = pack(digest(items));
+        if let Some(first) = batches.first_mut() {
+            for (_, item) in items {
+                first
+                    .mentions
+                    .merge(&MentionRule::matching(&self.mentions, item));
+            }
+        }
+        batches
+    }
+
+    pub async fn send(&self, message: &WebhookMessage) -> Result<()> {
+        if let Err(e) = validate_message(message) {
+            // Embeds are fitted to the limits, this is a bug or a template producing too much content
+            error!(
+                "Discord message with embeds {:?} exceeds the limits and is not sent: {e}",
+                message
+                    .embeds
+                    .iter()
+                    .map(|embed| embed.title.as_str())
+                    .collect::<Vec<&str>>()
+            );
+            return Err(e.into());
+        }
+
+        // Held for the whole send, this queues concurrent messages for the same webhook
+        let mut rate_limit = self.rate_limit.lock().await;
+        let mut retries = 0;
+        loop {
+            if let Some(delay) = rate_limit.delay(Instant::now()) {
+                if delay > MAX_RATE_LIMIT_WAIT {
+                    // The next attempt asks Discord again instead of trusting a bogus reset
+                    *rate_limit = RateLimit::default();
+                    return Err(DiscordError::RateLimited(delay).into());
+                }
+                debug!("Waiting {delay:?} for the Discord rate limit");
+                tokio::time::sleep(delay).await;
+            }
+
+            let response = self
+                .client
+                .post(&self.url)
+                .json(message)
+                .send()
+                .await
+                .map_err(|e| match e {
+                    reqwest_middleware::Error::Reqwest(e) => Error::Reqwest(e.without_url()),
+                    e => Error::ReqwestMiddleware(e),
+                })?;
+            rate_limit.update(response.headers(), Instant::now());
+
+            let status = response.status();
+            if status.is_success() {
+                return Ok(());
+            }
+
+            if status == StatusCode::TOO_MANY_REQUESTS {
+                let retry_after = retry_after(response).await;
+                if retries >= MAX_RATE_LIMIT_RETRIES || retry_after > MAX_RATE_LIMIT_WAIT {
+                    return Err(DiscordError::RateLimited(retry_after).into());
+                }
+
+                retries += 1;
+                warn!("Discord rate limit hit, retrying in {retry_after:?}");
+                tokio::time::sleep(retry_after).await;
+                continue;
+            }
+
+            let body = response.text().await.unwrap_or_default();
+            return Err(Self::status_error(status, body).into());
+        }
+    }
+
+    async fn send_batches(&self, feed: &Feed, batches: Vec<Batch>) -> Vec<Delivery> {
+        let mut deliveries = Vec::with_capacity(batches.len());
+        for batch in batches {
+            debug!(
+                "Sending {} embeds for feed {}",
+                batch.embeds.len(),
+                feed.name()
+            );
+            let (items, message) = batch.into_message(feed);
+            let result = self.send(&message).await;
+            deliveries.push(Delivery { items, result });
+        }
+        deliveries
+    }
+
+    pub(super) fn status_error(status: StatusCode, body: String) -> DiscordError {
+        let response = serde_json::from_str::<ErrorResponse>(&body).ok();
+        let message = response
+            .as_ref()
+            .and_then(|response| response.message.clone())
+            .unwrap_or(body);
+
+        match status {
+            StatusCode::UNAUTHORIZED | StatusCode::FORBIDDEN | StatusCode::NOT_FOUND => {
+                DiscordError::InvalidWebhook(message)
+            }
+            StatusCode::PAYLOAD_TOO_LARGE => DiscordError::PayloadTooLarge(message),
+            // 50035 is returned for invalid form bodies like too long embeds
+            StatusCode::BAD_REQUEST
+                if response.is_some_and(|response| response.code == Some(50035)) =>
+            {
+                DiscordError::PayloadTooLarge(message)
+            }
+            StatusCode::BAD_REQUEST => DiscordError::BadRequest(message),
+            status => DiscordError::Unexpected {
+                status: status.as_u16(),
+                message,
+            },
+        }
+    }
+}
+
+#[async_trait]
+impl Notifier for DiscordWebhook {
+    #[tracing::instrument(skip(message))]
+    async fn send_message(&self, feed: &Feed, message: &Message) -> Result<()> {
+        info!(
+            "Sending message for feed {} with title \"{}\"",
+            feed.name(),
+            message.title
+        );
+
+        self.send(&WebhookMessage {
+            username: format!("Feed - {}", feed.name()),
+            avatar_url: None,
+            content: None,
+            embeds: vec![Embed::new(message)],
+            allowed_mentions: AllowedMentions::default(),
+        })
+        .await
+    }
+
+    #[tracing::instrument(skip(item))]
+    async fn notify(&self, feed: &Feed, item: &FeedItem) -> Result<()> {
+        info!(
+            "Sending message for feed {} with title \"{}\"",
+            feed.name(),
+            item.title.as_deref().unwrap_or("No title")
+        );
+
+        let entry = self.entry(feed, 0, item)?;
+        let (_, message) = pack(vec![entry])
+            .pop()
+            .expect("One entry is packed into one batch")
+            .into_message(feed);
+        self.send(&message).await
+    }
+
+    /// Digest embeds with one linked line per item, packed into as few messages as possible
+    async fn notify_digest(&self, feed: &Feed, items: &[&FeedItem]) -> Vec<Delivery> {
+        let items = items
+            .iter()
+            .enumerate()
+            .map(|(index, item)| (index, *item))
+            .collect::<Vec<(usize, &FeedItem)>>();
+        let batches = self.digest_batches(&items);
+        self.send_batches(feed, batches).await
+    }
+
+    /// With batching up to 10 embeds are sent per message. If the items need more than
+    /// three messages, a digest with one line per item is sent instead
+    async fn notify_all(&self, feed: &Feed, items: &[&FeedItem]) -> Vec<Delivery> {
+        if !self.batch || items.len() < 2 {
+            return notify_each(self, feed, items).await;
+        }
+
+        let mut deliveries = Vec::new();
+        let mut entries = Vec::new();
+        for (index, item) in items.iter().enumerate() {
+            let entry = self
+                .entry(feed, index, item)
+                .and_then(|entry| entry.embed.validate().map(|_| entry).map_err(Error::from));
+            match entry {
+                Ok(entry) => entries.push(entry),
+                Err(e) => deliveries.push(Delivery {
+                    items: vec![index],
+                    result: Err(e),
+                }),
+            }
+        }
+
+        let mut batches = pack(entries);
+        if batches.len() > MAX_BATCH_MESSAGES {
+            let items = batches
+                .iter()
+                .flat_map(|batch| batch.items.iter())
+                .map(|index| (*index, items[*index]))
+                .collect::<Vec<(usize, &FeedItem)>>();
+            info!(
+                "Sending digest of {} items for feed {}",
+                items.len(),
+                feed.name()
+            );
+            batches = self.digest_batches(&items);
+        }
+
+        deliveries.extend(self.send_batches(feed, batches).await);
+        deliveries
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use wiremock::matchers::{body_partial_json, method, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    use crate::feed_item::{AlternateLink, ItemSnapshot};
+    use crate::filter::FilterConfig;
+    use crate::locale::Locale;
+    use crate::notifier::mention::MentionConfig;
+    use crate::notifier::template::TemplateConfig;
+    use crate::notifier::tests::{create_client, create_feed, create_item};
+
+    use super::*;
+
+    fn create_webhook(server: &MockServer) -> DiscordWebhook {
+        DiscordWebhook::new(
+            create_client(),
+            &format!("{}/api/webhooks/1/token", server.uri()),
+        )
+    }
+
+    async fn mount_error(server: &MockServer, status: u16, body: serde_json::Value) {
+        Mock::given(method("POST"))
+            .respond_with(ResponseTemplate::new(status).set_body_json(body))
+            .mount(server)
+            .await;
+    }
+
+    async fn notify_error(server: &MockServer) -> Error {
+        create_webhook(server)
+            .notify(&create_feed(), &create_item())
+            .await
+            .unwrap_err()
+    }
+
+    #[tokio::test]
+    async fn test_notify() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/api/webhooks/1/token"))
+            .and(body_partial_json(serde_json::json!({
+                "username": "Feed - Netcup",
+                "embeds": [{"title": "RS 1000 <G11>", "url": "https://example.com/rs-1000"}]
+            })))
+            .respond_with(ResponseTemplate::new(204))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        create_webhook(&server)
+            .notify(&create_feed(), &create_item())
+            .await
+            .unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_retry_after_rate_limit() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .respond_with(
+                ResponseTemplate::new(429)
+                    .set_body_json(serde_json::json!({"retry_after": 0.05, "global": false})),
+            )
+            .up_to_n_times(1)
+            .expect(1)
+            .mount(&server)
+            .await;
+        Mock::given(method("POST"))
+            .respond_with(ResponseTemplate::new(204))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        create_webhook(&server)
+            .notify(&create_feed(), &create_item())
+            .await
+            .unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_rate_limited_too_long() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .respond_with(ResponseTemplate::new(429).insert_header("Retry-After", "3600"))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let error = notify_error(&server).await;
+
+        assert!(matches!(
+            error,
+            Error::Discord(DiscordError::RateLimited(duration)) if duration == Duration::from_secs(3600)
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_rate_limited_huge_retry_after() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .respond_with(
+                ResponseTemplate::new(429).set_body_json(serde_json::json!({"retry_after": 1e20})),
+            )
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        assert!(matches!(
+            notify_error(&server).await,
+            Error::Discord(DiscordError::RateLimited(Duration::MAX))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_rate_limit_reset_too_long() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .respond_with(
+                ResponseTemplate::new(204)
+                    .insert_header(HEADER_REMAINING, "0")
+                    .insert_header(HEADER_RESET_AFTER, "1e20"),
+            )
+            .expect(2)
+            .mount(&server)
+            .await;
+
+        let webhook = create_webhook(&server);
+        webhook
+            .notify(&create_feed(), &create_item())
+            .await
+            .unwrap();
+        let error = webhook
+            .notify(&create_feed(), &create_item())
+            .await
+            .unwrap_err();
+        assert!(matches!(
+            error,
+            Error::Discord(DiscordError::RateLimited(delay)) if delay > MAX_RATE_LIMIT_WAIT
+        ));
+
+        // The bucket is forgotten, the outbox retry sends again
+        webhook
+            .notify(&create_feed(), &create_item())
+            .await
+            .unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_paces_with_rate_limit_headers() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .respond_with(
+                ResponseTemplate::new(204)
+                    .insert_header(HEADER_REMAINING, "0")
+                    .insert_header(HEADER_RESET_AFTER, "0.2"),
+            )
+            .expect(2)
+            .mount(&server)
+            .await;
+
+        let webhook = create_webhook(&server);
+        let start = std::time::Instant::now();
+        webhook
+            .notify(&create_feed(), &create_item())
+            .await
+            .unwrap();
+        webhook
+            .notify(&create_feed(), &create_item())
+            .await
+            .unwrap();
+
+        assert!(start.elapsed() >= Duration::from_millis(200));
+    }
+
+    #[tokio::test]
+    async fn test_invalid_webhook() {
+        let server = MockServer::start().await;
+        mount_error(
+            &server,
+            404,
+            serde_json::json!({"message": "Unknown Webhook", "code": 10015}),
+        )
+        .await;
+
+        assert!(matches!(
+            notify_error(&server).await,
+            Error::Discord(DiscordError::InvalidWebhook(message)) if message == "Unknown Webhook"
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_payload_too_large() {
+        let server = MockServer::start().await;
+        mount_error(
+            &server,
+            400,
+            serde_json::json!({"message": "Invalid Form Body", "code": 50035}),
+        )
+        .await;
+
+        assert!(matches!(
+            notify_error(&server).await,
+            Error::Discord(DiscordError::PayloadTooLarge(_))
+        ));
+    }
+
+    #[test]
+    fn test_embed_description_markdown() {
+        let message = Message::new(&FeedItem {
+            description: Some(format!(
+                "<p><b>RS 1000</b></p><ul><li>{}</li></ul>",
+                "8 GB RAM ".repeat(1000)
+            )),
+            ..create_item()
+        });
+
+        let embed = Embed::new(&message);
+
+        assert!(embed.description.starts_with("**RS 1000**\n\n- 8 GB RAM"));
+        assert!(embed
+            .description
+            .ends_with("… [Read more](https://example.com/rs-1000)"));
+        assert!(embed.description.chars().count() <= MAX_DESCRIPTION_LENGTH);
+        assert!(embed.validate().is_ok());
+    }
+
+    #[test]
+    fn test_embed_long_diff() {
+        let description = |ram: &str| {
+            format!(
+                "<p>```</p><ul>{}</ul>",
+                format!("<li>{ram} GB RAM</li>").repeat(500)
+            )
+        };
+        let message = Message::new(&FeedItem {
+            previous: Some(ItemSnapshot {
+                title: Some("RS 1000".to_string()),
+                description: Some(description("8")),
+            }),
+            description: Some(description("16")),
+            ..create_item()
+        });
+
+        let embed = Embed::new(&message);
+
+        assert!(embed.description.starts_with("```diff\n"));
+        assert!(embed.description.ends_with("\n…\n```"));
+        assert_eq!(embed.description.matches("```").count(), 2);
+        assert!(!embed.description.contains("<li>"));
+        assert!(embed.validate().is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_validate_before_send() {
+        let server = MockServer::start().await;
+        let message = WebhookMessage {
+            username: "Feed - Netcup".to_string(),
+            avatar_url: None,
+            content: Some("a".repeat(MAX_CONTENT_LENGTH + 1)),
+            embeds: vec![Embed::new(&Message::new(&create_item()))],
+            allowed_mentions: AllowedMentions::default(),
+        };
+
+        let error = create_webhook(&server).send(&message).await.unwrap_err();
+
+        assert!(matches!(
+            error,
+            Error::Discord(DiscordError::PayloadTooLarge(_))
+        ));
+        assert!(server.received_requests().await.unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_embed_fit_limits() {
+        let item = FeedItem {
+            title: Some("a".repeat(MAX_TITLE_LENGTH + 1)),
+            previous: Some(ItemSnapshot::default()),
+            categories: vec!["Root-Server".repeat(100)],
+            alternates: (0..100)
+                .map(|index| AlternateLink {
+                    locale: Locale::En,
+                    url: format!("https://example.com/en/rs-1000-{index}"),
+                })
+                .collect(),
+            ..create_item()
+        };
+
+        let mut embed = Embed::new(&Message::new(&item));
+        assert_eq!(embed.title.chars().count(), MAX_TITLE_LENGTH);
+        assert!(embed.title.starts_with("Updated offer: aaa"));
+        let alternates = embed.fields.last().unwrap();
+        assert_eq!(alternates.name, "Other languages");
+        assert!(alternates.value.ends_with(')'));
+        assert!(embed.validate().is_ok());
+
+        // Template output
+        embed.fields = (0..30)
+            .map(|_| EmbedField {
+                name: "n".repeat(300),
+                value: "v".repeat(2000),
+                inline: true,
+            })
+            .collect();
+        embed.footer = Some(EmbedFooter {
+            text: "f".repeat(3000),
+        });
+        embed.fit();
+        assert!(embed.validate().is_ok());
+        assert!(!embed.fields.is_empty());
+    }
+
+    #[test]
+    fn test_embed_fit_code_block() {
+        let mut embed = Embed::new(&Message::new(&create_item()));
+        embed.description = format!("```\n{}```", "RS 1000 G11\n".repeat(500));
+
+        embed.fit();
+
+        assert!(embed.validate().is_ok());
+        assert!(embed.description.starts_with("```\nRS 1000 G11\n"));
+        assert!(embed.description.ends_with("RS 1000 G11\n…\n```"));
+    }
+
+    #[test]
+    fn test_rate_limit_delay() {
+        let now = Instant::now();
+        let mut rate_limit = RateLimit::default();
+        assert_eq!(rate_limit.delay(now), None);
+
+        let mut headers = HeaderMap::new();
+        headers.insert(HEADER_REMAINING, "0".parse().unwrap());
+        headers.insert(HEADER_RESET_AFTER, "1.5".parse().unwrap());
+        rate_limit.update(&headers, now);
+        assert_eq!(rate_limit.delay(now), Some(Duration::from_millis(1500)));
+
+        assert_eq!(
+            rate_limit.delay(now + Duration::from_secs(1)),
+            Some(Duration::from_millis(500))
+        );
+        assert_eq!(rate_limit.delay(now + Duration::from_secs(2)), None);
+
+        headers.insert(HEADER_RESET_AFTER, "1e20".parse().unwrap());
+        rate_limit.update(&headers, now);
+        assert_eq!(rate_limit.delay(now), Some(Duration::MAX));
+
+        headers.insert(HEADER_REMAINING, "4".parse().unwrap());
+        rate_limit.update(&headers, now);
+        assert_eq!(rate_limit.delay(now), None);
+    }
+
+    fn create_items(count: usize) -> Vec<FeedItem> {
+        (0..count)
+            .map(|index| FeedItem {
+                title: Some(format!("RS {index}")),
+                link: Some(format!("https://example.com/rs-{index}")),
+                description: Some("4 vCore, 8 GB RAM, 9,99 € / Monat".to_string()),
+                ..Default::default()
+            })
+            .collect()
+    }
+
+    fn create_entry(index: usize, description_length: usize) -> Entry {
+        Entry {
+            items: vec![index],
+            embed: Embed {
+                title: String::new(),
+                description: "a".repeat(description_length),
+                url: None,
+                color: None,
+                fields: Vec::new(),
+                footer: None,
+            },
+            header: Header::default(),
+        }
+    }
+
+    #[test]
+    fn test_pack_embed_limit() {
+        let entries = (0..25).map(|index| create_entry(index, 10)).collect();
+
+        let batches = pack(entries);
+
+        let sizes = batches
+            .iter()
+            .map(|batch| batch.embeds.len())
+            .collect::<Vec<usize>>();
+        assert_eq!(sizes, vec![10, 10, 5]);
+        assert_eq!(batches[2].items, vec![20, 21, 22, 23, 24]);
+    }
+
+    #[test]
+    fn test_pack_length_budget() {
+        let entries = (0..3).map(|index| create_entry(index, 2500)).collect();
+
+        let batches = pack(entries);
+
+        let sizes = batches
+            .iter()
+            .map(|batch| batch.embeds.len())
+            .collect::<Vec<usize>>();
+        assert_eq!(sizes, vec![2, 1]);
+    }
+
+    #[test]
+    fn test_digest() {
+        let items = create_items(100);
+        let items = items
+            .iter()
+            .enumerate()
+            .collect::<Vec<(usize, &FeedItem)>>();
+
+        let entries = digest(&items);
+
+        assert_eq!(
+            entries.iter().map(|entry| entry.items.len()).sum::<usize>(),
+            100
+        );
+        for entry in &entries {
+            assert!(entry.embed.validate().is_ok());
+            assert_eq!(entry.embed.title, format!("{} offers", entry.items.len()));
+        }
+        assert!(entries[0]
+            .embed
+            .description
+            .starts_with("• [RS 0](https://example.com/rs-0) - 9.99 € / month\n"));
+    }
+
+    #[tokio::test]
+    async fn test_notify_all_batches() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .respond_with(ResponseTemplate::new(204))
+            .expect(2)
+            .mount(&server)
+            .await;
+
+        let webhook = create_webhook(&server).with_batching(true);
+        let items = create_items(15);
+        let items = items.iter().collect::<Vec<&FeedItem>>();
+
+        let deliveries = webhook.notify_all(&create_feed(), &items).await;
+
+        assert_eq!(deliveries.len(), 2);
+        assert_eq!(deliveries[0].items, (0..10).collect::<Vec<usize>>());
+        assert!(deliveries.iter().all(|delivery| delivery.result.is_ok()));
+        let requests = server.received_requests().await.unwrap();
+        let body: serde_json::Value = requests[0].body_json().unwrap();
+        assert_eq!(body["embeds"].as_array().unwrap().len(), 10);
+    }
+
+    #[tokio::test]
+    async fn test_notify_all_digest() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .respond_with(ResponseTemplate::new(204))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let webhook = create_webhook(&server).with_batching(true);
+        let items = create_items(40);
+        let items = items.iter().collect::<Vec<&FeedItem>>();
+
+        let deliveries = webhook.notify_all(&create_feed(), &items).await;
+
+        assert_eq!(deliveries.len(), 1);
+        assert_eq!(deliveries[0].items.len(), 40);
+        let requests = server.received_requests().await.unwrap();
+        let body: serde_json::Value = requests[0].body_json().unwrap();
+        assert_eq!(body["embeds"][0]["title"], "40 offers");
+    }
+
+    fn create_template() -> MessageTemplate {
+        MessageTemplate::try_from(TemplateConfig {
+            username: Some("{{ feed.name }} bot".to_string()),
+            content: Some("New offer in {{ feed.name }}".to_string()),
+            title: Some("{{ item.title }} - {{ offer.price }}".to_string()),
+            color: Some("#00ff00".to_string()),
+            footer: Some("{{ item.categories | join(', ') }}".to_string()),
+            ..Default::default()
+        })
+        .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_notify_template() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(body_partial_json(serde_json::json!({
+                "username": "Netcup bot",
+                "content": "New offer in Netcup",
+                "embeds": [{
+                    "title": "RS 1000 <G11> - 9.99 € / month",
+                    "color": 0x00ff00,
+                    "footer": {"text": "Root-Server"}
+                }]
+            })))
+            .respond_with(ResponseTemplate::new(204))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        create_webhook(&server)
+            .with_template(Some(create_template()))
+            .notify(&create_feed(), &create_item())
+            .await
+            .unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_notify_all_template_content() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .respond_with(ResponseTemplate::new(204))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let webhook = create_webhook(&server)
+            .with_batching(true)
+            .with_template(Some(create_template()));
+        let items = create_items(3);
+        let items = items.iter().collect::<Vec<&FeedItem>>();
+
+        let deliveries = webhook.notify_all(&create_feed(), &items).await;
+
+        assert_eq!(deliveries.len(), 1);
+        let requests = server.received_requests().await.unwrap();
+        let body: serde_json::Value = requests[0].body_json().unwrap();
+        // Identical contents are only sent once per message
+        assert_eq!(body["content"], "New offer in Netcup");
+        assert_eq!(body["embeds"].as_array().unwrap().len(), 3);
+    }
+
+    #[tokio::test]
+    async fn test_notify_all_long_template_content() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .respond_with(ResponseTemplate::new(204))
+            .expect(3)
+            .mount(&server)
+            .await;
+
+        let template = MessageTemplate::try_from(TemplateConfig {
+            content: Some("{{ item.title }} {{ '.' * 450 }}".to_string()),
+            ..Default::default()
+        })
+        .unwrap();
+        let webhook = create_webhook(&server)
+            .with_batching(true)
+            .with_template(Some(template));
+        let items = create_items(10);
+        let items = items.iter().collect::<Vec<&FeedItem>>();
+
+        let deliveries = webhook.notify_all(&create_feed(), &items).await;
+
+        // Every content differs, a message holds at most 4 of them
+        assert_eq!(deliveries.len(), 3);
+        assert!(deliveries.iter().all(|delivery| delivery.result.is_ok()));
+        assert_eq!(
+            deliveries
+                .iter()
+                .map(|delivery| delivery.items.len())
+                .sum::<usize>(),
+            10
+        );
+        for request in server.received_requests().await.unwrap() {
+            let body: serde_json::Value = request.body_json().unwrap();
+            assert!(body["content"].as_str().unwrap().chars().count() <= MAX_CONTENT_LENGTH);
+        }
+    }
+
+    fn create_mentions() -> Vec<MentionRule> {
+        vec![MentionRule::try_from(MentionConfig {
+            roles: vec!["1".to_string()],
+            when: FilterConfig {
+                categories: vec!["Root-Server".to_string()],
+                ..Default::default()
+            },
+            ..Default::default()
+        })
+        .unwrap()]
+    }
+
+    #[tokio::test]
+    async fn test_notify_mentions() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(body_partial_json(serde_json::json!({
+                "content": "<@&1>\nNew offer in Netcup",
+                "allowed_mentions": {"parse": [], "roles": ["1"]}
+            })))
+            .respond_with(ResponseTemplate::new(204))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        create_webhook(&server)
+            .with_template(Some(create_template()))
+            .with_mentions(create_mentions())
+            .notify(&create_feed(), &create_item())
+            .await
+            .unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_notify_here_mention_template_everyone() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(body_partial_json(serde_json::json!({
+                "content": "@here\n@\u{200b}everyone RS 1000 <G11> @here",
+                "allowed_mentions": {"parse": ["everyone"]}
+            })))
+            .respond_with(ResponseTemplate::new(204))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let template = MessageTemplate::try_from(TemplateConfig {
+            content: Some("@everyone {{ item.title }} @here".to_string()),
+            ..Default::default()
+        })
+        .unwrap();
+        let mentions = vec![MentionRule::try_from(MentionConfig {
+            here: true,
+            ..Default::default()
+        })
+        .unwrap()];
+
+        create_webhook(&server)
+            .with_template(Some(template))
+            .with_mentions(mentions)
+            .notify(&create_feed(), &create_item())
+            .await
+            .unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_notify_without_matching_mentions() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .respond_with(ResponseTemplate::new(204))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let item = FeedItem {
+            categories: vec!["vServer".to_string()],
+            ..create_item()
+        };
+        create_webhook(&server)
+            .with_mentions(create_mentions())
+            .notify(&create_feed(), &item)
+            .await
+            .unwrap();
+
+        let requests = server.received_requests().await.unwrap();
+        let body: serde_json::Value = requests[0].body_json().unwrap();
+        assert_eq!(body.get("content"), None);
+        assert_eq!(body["allowed_mentions"], serde_json::json!({"parse": []}));
+    }
+
+    #[test]
+    fn test_digest_batches_mentions() {
+        let webhook = DiscordWebhook::new(create_client(), "https://discord.com/api/webhooks/")
+            .with_mentions(create_mentions());
+        let item = create_item();
+        let items = (0..100).map(|index| (index, &item)).collect::<Vec<_>>();
+
+        let batches = webhook.digest_batches(&items);
+
+        assert!(batches.len() > 1);
+        assert_eq!(batches[0].mentions.to_content().as_deref(), Some("<@&1>"));
+        assert!(batches[1].mentions.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_notify_all_without_batching() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .respond_with(ResponseTemplate::new(204))
+            .expect(3)
+            .mount(&server)
+            .await;
+
+        let items = create_items(3);
+        let items = items.iter().collect::<Vec<&FeedItem>>();
+
+        let deliveries = create_webhook(&server)
+            .notify_all(&create_feed(), &items)
+            .await;
+
+        assert_eq!(deliveries.len(), 3);
+    }
+}
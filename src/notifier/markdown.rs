@@ -0,0 +1,503 @@
+use lazy_static::lazy_static;
+use regex::Regex;
+
+lazy_static! {
+    static ref TOKEN: Regex =
+        Regex::new(r"(?s)<!--.*?-->|<(/?)([a-zA-Z][a-zA-Z0-9]*)([^>]*)>").unwrap();
+    static ref HREF: Regex =
+        Regex::new(r#"(?i)href\s*=\s*(?:"([^"]*)"|'([^']*)'|([^\s>]+))"#).unwrap();
+    static ref ENTITY: Regex = Regex::new(r"&(#[0-9]+|#[xX][0-9a-fA-F]+|[a-zA-Z]+);").unwrap();
+    static ref BLANK_LINES: Regex = Regex::new(r"\n{3,}").unwrap();
+}
+
+const READ_MORE: &str = "Read more";
+
+fn named_entity(name: &str) -> Option<&'static str> {
+    Some(match name {
+        "amp" => "&",
+        "lt" => "<",
+        "gt" => ">",
+        "quot" => "\"",
+        "apos" => "'",
+        "nbsp" => " ",
+        "euro" => "€",
+        "auml" => "ä",
+        "ouml" => "ö",
+        "uuml" => "ü",
+        "Auml" => "Ä",
+        "Ouml" => "Ö",
+        "Uuml" => "Ü",
+        "szlig" => "ß",
+        "ndash" => "–",
+        "mdash" => "—",
+        "hellip" => "…",
+        "bull" => "•",
+        "copy" => "©",
+        "reg" => "®",
+        "trade" => "™",
+        "times" => "×",
+        _ => return None,
+    })
+}
+
+/// Decodes named and numeric character references, unknown ones are kept as is
+pub fn decode_entities(text: &str) -> String {
+    ENTITY
+        .replace_all(text, |captures: &regex::Captures| {
+            let entity = &captures[1];
+            let decoded = match entity.strip_prefix('#') {
+                Some(number) => {
+                    let code = match number.strip_prefix(['x', 'X']) {
+                        Some(hex) => u32::from_str_radix(hex, 16).ok(),
+                        None => number.parse::<u32>().ok(),
+                    };
+                    code.and_then(char::from_u32).map(String::from)
+                }
+                None => named_entity(entity).map(str::to_string),
+            };
+            decoded.unwrap_or_else(|| captures[0].to_string())
+        })
+        .into_owned()
+}
+
+pub fn escape_markdown(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        if matches!(c, '\\' | '*' | '_' | '~' | '`' | '|' | '[' | ']') {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
+
+#[derive(Debug)]
+enum List {
+    Unordered,
+    Ordered(u32),
+}
+
+/// Builds the markdown, whitespace between words is only written once the next word follows
+#[derive(Debug, Default)]
+struct Writer {
+    out: String,
+    space: bool,
+    /// Whitespace directly after an opening marker or bullet is dropped
+    opened: bool,
+    lists: Vec<List>,
+    links: Vec<(usize, Option<String>)>,
+    skip: usize,
+    /// Plain text without formatting markers, escapes and link targets
+    plain: bool,
+}
+
+impl Writer {
+    fn at_line_start(&self) -> bool {
+        self.out.is_empty() || self.out.ends_with('\n')
+    }
+
+    fn flush_space(&mut self) {
+        if self.space && !self.at_line_start() && !self.opened {
+            self.out.push(' ');
+        }
+        self.space = false;
+    }
+
+    fn text(&mut self, text: &str) {
+        if self.skip > 0 {
+            return;
+        }
+        let text = decode_entities(text);
+        if text.starts_with(char::is_whitespace) {
+            self.space = true;
+        }
+        for word in text.split_whitespace() {
+            self.flush_space();
+            if self.plain {
+                self.out.push_str(word);
+            } else {
+                self.out.push_str(&escape_markdown(word));
+            }
+            self.opened = false;
+            self.space = true;
+        }
+        if !text.ends_with(char::is_whitespace) && !text.trim().is_empty() {
+            self.space = false;
+        }
+    }
+
+    /// Opening markers are attached to the following word
+    fn open(&mut self, marker: &str) {
+        self.flush_space();
+        if !self.plain {
+            self.out.push_str(marker);
+        }
+        self.opened = true;
+    }
+
+    /// Closing markers are attached to the previous word
+    fn close(&mut self, marker: &str) {
+        if !self.plain {
+            self.out.push_str(marker);
+        }
+        self.opened = false;
+    }
+
+    fn trim_end(&mut self) {
+        let length = self.out.trim_end_matches([' ', '\t']).len();
+        self.out.truncate(length);
+    }
+
+    fn line_break(&mut self) {
+        self.trim_end();
+        self.out.push('\n');
+        self.space = false;
+    }
+
+    /// Ends the current block with at least `count` newlines
+    fn block(&mut self, count: usize) {
+        self.trim_end();
+        self.space = false;
+        if self.out.is_empty() {
+            return;
+        }
+        let existing = self.out.len() - self.out.trim_end_matches('\n').len();
+        for _ in existing..count {
+            self.out.push('\n');
+        }
+    }
+
+    fn list_item(&mut self) {
+        self.block(1);
+        let indent = "  ".repeat(self.lists.len().saturating_sub(1));
+        let bullet = match self.lists.last_mut() {
+            Some(List::Ordered(number)) => {
+                *number += 1;
+                format!("{number}. ")
+            }
+            _ => "- ".to_string(),
+        };
+        self.out.push_str(&indent);
+        self.out.push_str(&bullet);
+        self.opened = true;
+    }
+
+    fn open_link(&mut self, attributes: &str) {
+        self.flush_space();
+        let href = HREF
+            .captures(attributes)
+            .and_then(|captures| captures.iter().skip(1).flatten().next())
+            .map(|href| decode_entities(href.as_str().trim()))
+            .filter(|href| href.starts_with("https://") || href.starts_with("http://"));
+        self.links.push((self.out.len(), href));
+        self.opened = true;
+    }
+
+    fn close_link(&mut self) {
+        let Some((start, href)) = self.links.pop() else {
+            return;
+        };
+        let Some(href) = href.filter(|_| !self.plain) else {
+            return;
+        };
+        let text = self.out.split_off(start);
+        let href = href.replace(' ', "%20").replace(')', "%29");
+        if text.trim().is_empty() || text == escape_markdown(&href) {
+            self.out.push_str(&href);
+        } else {
+            self.out.push_str(&format!("[{text}]({href})"));
+        }
+    }
+
+    fn tag(&mut self, closing: bool, name: &str, attributes: &str) {
+        let name = name.to_ascii_lowercase();
+        if matches!(name.as_str(), "script" | "style") {
+            self.skip = if closing {
+                self.skip.saturating_sub(1)
+            } else {
+                self.skip + 1
+            };
+            return;
+        }
+        if self.skip > 0 {
+            return;
+        }
+
+        match (name.as_str(), closing) {
+            ("b" | "strong", false) => self.open("**"),
+            ("b" | "strong", true) => self.close("**"),
+            ("i" | "em", false) => self.open("*"),
+            ("i" | "em", true) => self.close("*"),
+            ("u", false) => self.open("__"),
+            ("u", true) => self.close("__"),
+            ("s" | "strike" | "del", false) => self.open("~~"),
+            ("s" | "strike" | "del", true) => self.close("~~"),
+            ("code", false) => self.open("`"),
+            ("code", true) => self.close("`"),
+            ("a", false) => self.open_link(attributes),
+            ("a", true) => self.close_link(),
+            ("br", _) => self.line_break(),
+            ("ul", false) => {
+                self.block(1);
+                self.lists.push(List::Unordered);
+            }
+            ("ol", false) => {
+                self.block(1);
+                self.lists.push(List::Ordered(0));
+            }
+            ("ul" | "ol", true) => {
+                self.lists.pop();
+                self.block(if self.lists.is_empty() { 2 } else { 1 });
+            }
+            ("li", false) => self.list_item(),
+            ("h1" | "h2" | "h3" | "h4" | "h5" | "h6", false) => {
+                self.block(2);
+                self.open("**");
+            }
+            ("h1" | "h2" | "h3" | "h4" | "h5" | "h6", true) => {
+                self.close("**");
+                self.block(2);
+            }
+            ("p" | "hr" | "table", _) => self.block(2),
+            ("div" | "tr", _) => self.block(1),
+            ("td" | "th", true) => self.space = true,
+            _ => {}
+        }
+    }
+
+    fn finish(mut self) -> String {
+        while !self.links.is_empty() {
+            self.close_link();
+        }
+        let text = self
+            .out
+            .lines()
+            .map(str::trim_end)
+            .collect::<Vec<&str>>()
+            .join("\n");
+        BLANK_LINES.replace_all(text.trim(), "\n\n").into_owned()
+    }
+}
+
+/// Converts the HTML of a feed description to Discord markdown.
+/// Unsupported tags are dropped and only their text is kept
+pub fn html_to_markdown(html: &str) -> String {
+    convert(html, Writer::default())
+}
+
+/// Text of the HTML with the line structure of [`html_to_markdown`], used where markdown isn't rendered
+pub fn html_to_text(html: &str) -> String {
+    convert(
+        html,
+        Writer {
+            plain: true,
+            ..Default::default()
+        },
+    )
+}
+
+fn convert(html: &str, mut writer: Writer) -> String {
+    let mut last = 0;
+    for captures in TOKEN.captures_iter(html) {
+        let token = captures.get(0).expect("Whole match is always set");
+        writer.text(&html[last..token.start()]);
+        last = token.end();
+
+        if let Some(name) = captures.get(2) {
+            writer.tag(
+                !captures[1].is_empty(),
+                name.as_str(),
+                captures.get(3).map_or("", |attributes| attributes.as_str()),
+            );
+        }
+    }
+    writer.text(&html[last..]);
+    writer.finish()
+}
+
+/// Formatting markers that are still open at the end of the text
+fn open_markers(text: &str) -> Vec<&'static str> {
+    let mut open: Vec<&'static str> = Vec::new();
+    let mut rest = text;
+    while let Some(c) = rest.chars().next() {
+        if c == '\\' {
+            rest = &rest[c.len_utf8()..];
+            if let Some(escaped) = rest.chars().next() {
+                rest = &rest[escaped.len_utf8()..];
+            }
+            continue;
+        }
+
+        let marker = ["**", "__", "~~", "*", "`"]
+            .into_iter()
+            .find(|marker| rest.starts_with(marker));
+        match marker {
+            // Nothing is formatted inside inline code
+            Some(marker) if open.last() == Some(&"`") && marker != "`" => {
+                rest = &rest[marker.len()..];
+            }
+            Some(marker) => {
+                if open.last() == Some(&marker) {
+                    open.pop();
+                } else {
+                    open.push(marker);
+                }
+                rest = &rest[marker.len()..];
+            }
+            None => rest = &rest[c.len_utf8()..],
+        }
+    }
+    open
+}
+
+/// Start of an unescaped link that isn't complete yet
+fn incomplete_link(text: &str) -> Option<usize> {
+    let mut start = None;
+    let mut escaped = false;
+    for (index, c) in text.char_indices() {
+        match c {
+            _ if escaped => escaped = false,
+            '\\' => escaped = true,
+            '[' => start = Some(index),
+            ')' => start = None,
+            _ => {}
+        }
+    }
+    start
+}
+
+/// Shortens markdown to at most `max` characters on a word boundary.
+/// Open formatting is closed and a link to the full text is added if there is one
+pub fn truncate_markdown(text: &str, max: usize, link: Option<&str>) -> String {
+    if text.chars().count() <= max {
+        return text.to_string();
+    }
+
+    let suffix = link
+        .map(|link| format!("… [{READ_MORE}]({link})"))
+        .filter(|suffix| suffix.chars().count() * 2 <= max)
+        .unwrap_or_else(|| "…".to_string());
+    let suffix_length = suffix.chars().count();
+
+    // Shortened until the closing markers of the open formatting fit as well
+    let mut budget = max.saturating_sub(suffix_length);
+    loop {
+        let mut truncated = cut_markdown(text, budget);
+        let markers = open_markers(&truncated);
+        let length = truncated.chars().count()
+            + markers.iter().map(|marker| marker.len()).sum::<usize>()
+            + suffix_length;
+        if length <= max || budget == 0 {
+            for marker in markers.into_iter().rev() {
+                truncated.push_str(marker);
+            }
+            truncated.push_str(&suffix);
+            return truncated;
+        }
+        budget = budget.saturating_sub(length - max);
+    }
+}
+
+/// First `budget` characters on a word boundary, without incomplete links
+fn cut_markdown(text: &str, budget: usize) -> String {
+    let mut truncated = text.chars().take(budget).collect::<String>();
+    if let Some(position) = truncated.rfind(char::is_whitespace) {
+        if position >= truncated.len() / 2 {
+            truncated.truncate(position);
+        }
+    }
+    if let Some(start) = incomplete_link(&truncated) {
+        truncated.truncate(start);
+    }
+    let length = truncated.trim_end().len();
+    truncated.truncate(length);
+    // A trailing backslash would escape the first closing marker
+    if truncated.ends_with('\\') && !truncated.ends_with("\\\\") {
+        truncated.pop();
+    }
+    truncated
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_emphasis_and_links() {
+        let html = r#"<p>Der <strong>RS 1000</strong> mit <em>4 vCores</em>, <a href="https://www.netcup.com/de/?a=1&amp;b=2">mehr Infos</a></p>"#;
+
+        assert_eq!(
+            html_to_markdown(html),
+            "Der **RS 1000** mit *4 vCores*, [mehr Infos](https://www.netcup.com/de/?a=1&b=2)"
+        );
+    }
+
+    #[test]
+    fn test_lists_and_line_breaks() {
+        let html = "<p>Specs:</p><ul><li>8 GB RAM</li><li>256 GB SSD<ol><li>NVMe</li></ol></li></ul>Preis:<br/>9,99&nbsp;&euro;";
+
+        assert_eq!(
+            html_to_markdown(html),
+            "Specs:\n\n- 8 GB RAM\n- 256 GB SSD\n  1. NVMe\n\nPreis:\n9,99 €"
+        );
+    }
+
+    #[test]
+    fn test_escapes_and_entities() {
+        let html = "<b> RS_1000 * 2 </b>&#8364; &#x2013; &unknown;<script>alert(1)</script>";
+
+        assert_eq!(html_to_markdown(html), "**RS\\_1000 \\* 2** € – &unknown;");
+    }
+
+    #[test]
+    fn test_html_to_text() {
+        let html = r#"<p>Der <strong>RS_1000</strong>, <a href="https://www.netcup.com/">mehr Infos</a></p><ul><li>8 GB RAM</li></ul>"#;
+
+        assert_eq!(html_to_text(html), "Der RS_1000, mehr Infos\n\n- 8 GB RAM");
+    }
+
+    #[test]
+    fn test_relative_link_keeps_text() {
+        assert_eq!(
+            html_to_markdown(r#"<a href="/offers">Offers</a>"#),
+            "Offers"
+        );
+    }
+
+    #[test]
+    fn test_truncate_short() {
+        assert_eq!(truncate_markdown("short", 10, Some("https://a")), "short");
+    }
+
+    #[test]
+    fn test_truncate_word_boundary() {
+        let text = format!("**{}**", "word ".repeat(100).trim());
+
+        let truncated = truncate_markdown(&text, 100, Some("https://example.com/rs"));
+
+        assert!(truncated.chars().count() <= 100);
+        assert!(truncated.starts_with("**word word"));
+        assert!(truncated.ends_with("word**… [Read more](https://example.com/rs)"));
+    }
+
+    #[test]
+    fn test_truncate_inside_link() {
+        let text = format!("{} [link text](https://example.com) end", "a".repeat(40));
+
+        let truncated = truncate_markdown(&text, 60, None);
+
+        assert_eq!(truncated, format!("{}…", "a".repeat(40)));
+    }
+
+    #[test]
+    fn test_truncate_many_open_markers() {
+        // Alternating markers are all still open
+        let text = format!("{}{}", "**a __b ".repeat(10), "c ".repeat(50));
+
+        for max in 20..120 {
+            let truncated = truncate_markdown(&text, max, None);
+            assert!(truncated.chars().count() <= max, "{max}: {truncated}");
+            assert!(open_markers(&truncated).is_empty(), "{max}: {truncated}");
+        }
+    }
+}
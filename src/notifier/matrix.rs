@@ -0,0 +1,192 @@
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use async_trait::async_trait;
+use reqwest_middleware::ClientWithMiddleware;
+
+use crate::error::Error;
+use crate::feed::Feed;
+use crate::notifier::{send, Message, Notifier};
+use crate::Result;
+
+// Events are limited to 64 KiB and carry the text twice, as plain text and html
+const MAX_MESSAGE_LENGTH: usize = 16_000;
+
+/// Matrix client-server API, sends notices to a single room the user already joined
+pub struct MatrixRoom {
+    client: ClientWithMiddleware,
+    homeserver: String,
+    access_token: String,
+    room_id: String,
+    transaction: AtomicU64,
+}
+
+impl std::fmt::Debug for MatrixRoom {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("MatrixRoom")
+            .field("homeserver", &self.homeserver)
+            .field("room_id", &self.room_id)
+            .finish()
+    }
+}
+
+impl MatrixRoom {
+    pub fn new(
+        client: ClientWithMiddleware,
+        homeserver: &str,
+        access_token: &str,
+        room_id: &str,
+    ) -> Self {
+        Self {
+            client,
+            homeserver: homeserver.to_string(),
+            access_token: access_token.to_string(),
+            room_id: room_id.to_string(),
+            transaction: AtomicU64::new(0),
+        }
+    }
+
+    /// Transaction ids have to be unique per access token, the start time keeps them unique between restarts
+    fn next_transaction_id(&self) -> String {
+        format!(
+            "{}-{}",
+            chrono::Utc::now().timestamp_millis(),
+            self.transaction.fetch_add(1, Ordering::Relaxed)
+        )
+    }
+
+    fn send_url(&self, transaction_id: &str) -> Result<reqwest::Url> {
+        let mut url = reqwest::Url::parse(&self.homeserver)
+            .map_err(|e| Error::Notifier(format!("Invalid Matrix homeserver: {e}")))?;
+        url.path_segments_mut()
+            .map_err(|_| Error::Notifier("Invalid Matrix homeserver".to_string()))?
+            .pop_if_empty()
+            .extend([
+                "_matrix",
+                "client",
+                "v3",
+                "rooms",
+                &self.room_id,
+                "send",
+                "m.room.message",
+                transaction_id,
+            ]);
+        Ok(url)
+    }
+}
+
+#[async_trait]
+impl Notifier for MatrixRoom {
+    #[tracing::instrument(skip(message))]
+    async fn send_message(&self, feed: &Feed, message: &Message) -> Result<()> {
+        info!(
+            "Sending Matrix message for feed {} with title \"{}\"",
+            feed.name(),
+            message.title
+        );
+
+        let url = self.send_url(&self.next_transaction_id())?;
+        let body = serde_json::json!({
+            "msgtype": "m.notice",
+            "body": message.to_plain_text(),
+            "format": "org.matrix.custom.html",
+            "formatted_body": message.to_html(MAX_MESSAGE_LENGTH).replace('\n', "<br>"),
+        });
+        send(
+            "Matrix",
+            self.client
+                .put(url)
+                .bearer_auth(&self.access_token)
+                .json(&body),
+        )
+        .await?;
+
+        Ok(())
+    }
+
+    fn max_message_length(&self) -> usize {
+        MAX_MESSAGE_LENGTH
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use wiremock::matchers::{body_partial_json, header, method, path_regex};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    use crate::notifier::tests::{create_client, create_feed, create_item};
+
+    use super::*;
+
+    #[test]
+    fn test_send_url() {
+        let room = MatrixRoom::new(
+            create_client(),
+            "https://matrix.example.com/",
+            "token",
+            "!room:example.com",
+        );
+
+        assert_eq!(
+            room.send_url("1").unwrap().as_str(),
+            "https://matrix.example.com/_matrix/client/v3/rooms/!room:example.com/send/m.room.message/1"
+        );
+    }
+
+    #[test]
+    fn test_transaction_ids_unique() {
+        let room = MatrixRoom::new(create_client(), "https://matrix.example.com", "token", "!r");
+
+        assert_ne!(room.next_transaction_id(), room.next_transaction_id());
+    }
+
+    #[tokio::test]
+    async fn test_notify() {
+        let server = MockServer::start().await;
+        Mock::given(method("PUT"))
+            .and(path_regex(
+                r"^/_matrix/client/v3/rooms/!room:example.com/send/m.room.message/.+$",
+            ))
+            .and(header("authorization", "Bearer secret"))
+            .and(body_partial_json(serde_json::json!({
+                "msgtype": "m.notice",
+                "format": "org.matrix.custom.html"
+            })))
+            .respond_with(
+                ResponseTemplate::new(200).set_body_json(serde_json::json!({"event_id": "$1"})),
+            )
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let notifier = MatrixRoom::new(
+            create_client(),
+            &server.uri(),
+            "secret",
+            "!room:example.com",
+        );
+
+        notifier
+            .notify(&create_feed(), &create_item())
+            .await
+            .unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_notify_error() {
+        let server = MockServer::start().await;
+        Mock::given(method("PUT"))
+            .respond_with(ResponseTemplate::new(403).set_body_json(serde_json::json!({
+                "errcode": "M_FORBIDDEN"
+            })))
+            .mount(&server)
+            .await;
+
+        let notifier = MatrixRoom::new(create_client(), &server.uri(), "secret", "!room");
+
+        let error = notifier
+            .notify(&create_feed(), &create_item())
+            .await
+            .unwrap_err();
+        assert!(error.to_string().contains("M_FORBIDDEN"));
+    }
+}
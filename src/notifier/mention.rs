@@ -0,0 +1,256 @@
+use serde::{Deserialize, Serialize};
+
+use crate::error::Error;
+use crate::feed_item::FeedItem;
+use crate::filter::{Filter, FilterConfig};
+
+/// Raw mention rule as found in the config
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct MentionConfig {
+    /// Role ids to ping
+    #[serde(default)]
+    pub roles: Vec<String>,
+    /// User ids to ping
+    #[serde(default)]
+    pub users: Vec<String>,
+    /// Ping `@here`
+    #[serde(default)]
+    pub here: bool,
+    /// Ping `@everyone`
+    #[serde(default)]
+    pub everyone: bool,
+    /// Filter rules the offer has to match, every offer if not set
+    #[serde(default)]
+    pub when: FilterConfig,
+}
+
+/// Pings added to the message of offers matching the filter
+#[derive(Debug, Clone)]
+pub struct MentionRule {
+    filter: Filter,
+    mentions: Mentions,
+}
+
+fn validate_ids(kind: &str, ids: &[String]) -> crate::Result<()> {
+    // Discord ids are numeric snowflakes, names can't be mentioned
+    match ids
+        .iter()
+        .find(|id| id.is_empty() || !id.chars().all(|c| c.is_ascii_digit()))
+    {
+        Some(id) => Err(Error::ConfigVar(format!(
+            "Invalid {kind} id for mention: {id}"
+        ))),
+        None => Ok(()),
+    }
+}
+
+impl TryFrom<MentionConfig> for MentionRule {
+    type Error = Error;
+
+    fn try_from(config: MentionConfig) -> Result<Self, Self::Error> {
+        validate_ids("role", &config.roles)?;
+        validate_ids("user", &config.users)?;
+        if config.roles.is_empty() && config.users.is_empty() && !config.here && !config.everyone {
+            return Err(Error::ConfigVar(
+                "Mention rule without roles, users, here or everyone".to_string(),
+            ));
+        }
+
+        Ok(Self {
+            filter: Filter::try_from(config.when)?,
+            mentions: Mentions {
+                roles: config.roles,
+                users: config.users,
+                here: config.here,
+                everyone: config.everyone,
+            },
+        })
+    }
+}
+
+impl MentionRule {
+    /// Mentions of all rules matching the item
+    pub fn matching(rules: &[MentionRule], item: &FeedItem) -> Mentions {
+        let mut mentions = Mentions::default();
+        for rule in rules {
+            if rule.filter.evaluate(item).is_keep() {
+                mentions.merge(&rule.mentions);
+            }
+        }
+        mentions
+    }
+}
+
+/// Roles and users pinged by a message
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Mentions {
+    roles: Vec<String>,
+    users: Vec<String>,
+    here: bool,
+    everyone: bool,
+}
+
+/// Only the mentions of the rules are allowed, text like `@everyone` in templates doesn't ping
+#[derive(Debug, Default, Serialize, PartialEq)]
+pub struct AllowedMentions {
+    pub parse: Vec<&'static str>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub roles: Vec<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub users: Vec<String>,
+}
+
+impl Mentions {
+    pub fn is_empty(&self) -> bool {
+        *self == Mentions::default()
+    }
+
+    pub fn merge(&mut self, other: &Mentions) {
+        for role in &other.roles {
+            if !self.roles.contains(role) {
+                self.roles.push(role.clone());
+            }
+        }
+        for user in &other.users {
+            if !self.users.contains(user) {
+                self.users.push(user.clone());
+            }
+        }
+        self.here |= other.here;
+        self.everyone |= other.everyone;
+    }
+
+    /// Message content that triggers the pings
+    pub fn to_content(&self) -> Option<String> {
+        let mut parts = Vec::new();
+        if self.everyone {
+            parts.push("@everyone".to_string());
+        }
+        if self.here {
+            parts.push("@here".to_string());
+        }
+        parts.extend(self.roles.iter().map(|role| format!("<@&{role}>")));
+        parts.extend(self.users.iter().map(|user| format!("<@{user}>")));
+        (!parts.is_empty()).then(|| parts.join(" "))
+    }
+
+    /// Breaks `@everyone` and `@here` in other text with a zero width space unless these mentions ping them.
+    /// Discord allows both with the same type, so they'd ping from a template as soon as one is set
+    pub fn neutralize(&self, text: &str) -> String {
+        let mut text = text.to_string();
+        if !self.everyone {
+            text = text.replace("@everyone", "@\u{200b}everyone");
+        }
+        if !self.here {
+            text = text.replace("@here", "@\u{200b}here");
+        }
+        text
+    }
+
+    pub fn allowed_mentions(&self) -> AllowedMentions {
+        AllowedMentions {
+            // @here is allowed with the everyone type as well
+            parse: if self.here || self.everyone {
+                vec!["everyone"]
+            } else {
+                Vec::new()
+            },
+            roles: self.roles.clone(),
+            users: self.users.clone(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::notifier::tests::create_item;
+
+    use super::*;
+
+    fn create_rule(config: MentionConfig) -> MentionRule {
+        MentionRule::try_from(config).unwrap()
+    }
+
+    #[test]
+    fn test_matching() {
+        let rules = vec![
+            create_rule(MentionConfig {
+                roles: vec!["1".to_string()],
+                when: FilterConfig {
+                    max_price: Some(10.0),
+                    ..Default::default()
+                },
+                ..Default::default()
+            }),
+            create_rule(MentionConfig {
+                roles: vec!["1".to_string()],
+                users: vec!["2".to_string()],
+                here: true,
+                when: FilterConfig {
+                    categories: vec!["Root-Server".to_string()],
+                    ..Default::default()
+                },
+                ..Default::default()
+            }),
+            create_rule(MentionConfig {
+                everyone: true,
+                when: FilterConfig {
+                    max_price: Some(1.0),
+                    ..Default::default()
+                },
+                ..Default::default()
+            }),
+        ];
+
+        let mentions = MentionRule::matching(&rules, &create_item());
+
+        assert_eq!(mentions.to_content().unwrap(), "@here <@&1> <@2>");
+        assert_eq!(
+            mentions.allowed_mentions(),
+            AllowedMentions {
+                parse: vec!["everyone"],
+                roles: vec!["1".to_string()],
+                users: vec!["2".to_string()],
+            }
+        );
+    }
+
+    #[test]
+    fn test_neutralize() {
+        let mentions = Mentions {
+            here: true,
+            ..Default::default()
+        };
+
+        assert_eq!(
+            mentions.neutralize("@everyone @here"),
+            "@\u{200b}everyone @here"
+        );
+        assert_eq!(
+            Mentions::default().neutralize("@everyone @here"),
+            "@\u{200b}everyone @\u{200b}here"
+        );
+    }
+
+    #[test]
+    fn test_no_match() {
+        let mentions = Mentions::default();
+
+        assert_eq!(mentions.to_content(), None);
+        assert_eq!(
+            serde_json::to_value(mentions.allowed_mentions()).unwrap(),
+            serde_json::json!({"parse": []})
+        );
+    }
+
+    #[test]
+    fn test_invalid_rules() {
+        assert!(MentionRule::try_from(MentionConfig {
+            roles: vec!["Admins".to_string()],
+            ..Default::default()
+        })
+        .is_err());
+        assert!(MentionRule::try_from(MentionConfig::default()).is_err());
+    }
+}
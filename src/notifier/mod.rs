@@ -0,0 +1,594 @@
+use std::fmt::Debug;
+
+use async_trait::async_trait;
+use reqwest::StatusCode;
+use reqwest_middleware::{ClientWithMiddleware, RequestBuilder};
+use secrecy::ExposeSecret;
+
+use crate::config::NotifierConfig;
+use crate::diff::line_diff;
+use crate::error::Error;
+use crate::feed::Feed;
+use crate::feed_item::FeedItem;
+use crate::notifier::markdown::html_to_text;
+use crate::Result;
+
+pub mod direct_message;
+pub mod discord;
+pub mod markdown;
+pub mod matrix;
+pub mod mention;
+pub mod slack;
+pub mod telegram;
+pub mod template;
+pub mod webhook;
+
+// Room for the formatted title of a digest next to its lines
+const DIGEST_TITLE_RESERVE: usize = 32;
+
+/// Backend that announces feed items
+#[async_trait]
+pub trait Notifier: Debug + Send + Sync {
+    async fn send_message(&self, feed: &Feed, message: &Message) -> Result<()>;
+
+    async fn notify(&self, feed: &Feed, item: &FeedItem) -> Result<()> {
+        self.send_message(feed, &Message::new(item)).await
+    }
+
+    /// Sends multiple items, backends that can combine items into one message override this
+    async fn notify_all(&self, feed: &Feed, items: &[&FeedItem]) -> Vec<Delivery> {
+        notify_each(self, feed, items).await
+    }
+
+    /// Sends a summary of the items collected for a scheduled digest,
+    /// split into multiple messages if it exceeds [`Notifier::max_message_length`]
+    async fn notify_digest(&self, feed: &Feed, items: &[&FeedItem]) -> Vec<Delivery> {
+        let mut deliveries = Vec::new();
+        for (indices, message) in Message::digests(items, self.max_message_length()) {
+            deliveries.push(Delivery {
+                items: indices,
+                result: self.send_message(feed, &message).await,
+            });
+        }
+        deliveries
+    }
+
+    /// Characters of formatted text a single message can hold
+    fn max_message_length(&self) -> usize {
+        usize::MAX
+    }
+}
+
+/// Result of a sent message with the indices of the items it contained
+#[derive(Debug)]
+pub struct Delivery {
+    pub items: Vec<usize>,
+    pub result: Result<()>,
+}
+
+async fn notify_each<N: Notifier + ?Sized>(
+    notifier: &N,
+    feed: &Feed,
+    items: &[&FeedItem],
+) -> Vec<Delivery> {
+    let mut deliveries = Vec::with_capacity(items.len());
+    for (index, item) in items.iter().enumerate() {
+        deliveries.push(Delivery {
+            items: vec![index],
+            result: notifier.notify(feed, item).await,
+        });
+    }
+    deliveries
+}
+
+pub fn from_config(config: &NotifierConfig, client: ClientWithMiddleware) -> Box<dyn Notifier> {
+    match config {
+        NotifierConfig::Discord {
+            web_hook,
+            batch,
+            template,
+            mentions,
+        } => Box::new(
+            discord::DiscordWebhook::new(client, web_hook.expose_secret())
+                .with_batching(*batch)
+                .with_template(template.clone())
+                .with_mentions(mentions.clone()),
+        ),
+        NotifierConfig::Slack { web_hook } => {
+            Box::new(slack::SlackWebhook::new(client, web_hook.expose_secret()))
+        }
+        NotifierConfig::Telegram {
+            api_url,
+            bot_token,
+            chat_id,
+        } => Box::new(telegram::TelegramBot::new(
+            client,
+            api_url,
+            bot_token.expose_secret(),
+            chat_id,
+        )),
+        NotifierConfig::Matrix {
+            homeserver,
+            access_token,
+            room_id,
+        } => Box::new(matrix::MatrixRoom::new(
+            client,
+            homeserver,
+            access_token.expose_secret(),
+            room_id,
+        )),
+        NotifierConfig::Webhook { web_hook, body } => Box::new(webhook::JsonWebhook::new(
+            client,
+            web_hook.expose_secret(),
+            body,
+        )),
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub struct Field {
+    pub name: &'static str,
+    pub value: String,
+    pub inline: bool,
+}
+
+/// Backend independent content of an announcement
+#[derive(Debug, PartialEq)]
+pub struct Message {
+    pub title: String,
+    pub link: Option<String>,
+    /// Plain text, HTML of the feed is converted with [`html_to_text`]
+    pub description: String,
+    /// Original HTML of the description, for backends with their own conversion
+    pub html_description: Option<String>,
+    /// Whether the description is a line diff of an updated item
+    pub is_diff: bool,
+    pub fields: Vec<Field>,
+    /// Links to the item in other languages as (locale, url)
+    pub alternates: Vec<(String, String)>,
+}
+
+impl Message {
+    pub fn new(item: &FeedItem) -> Self {
+        let title = item.title.as_deref().unwrap_or("No title");
+        let (title, description) = match &item.previous {
+            Some(previous) => {
+                // Descriptions are HTML, the diff works on their text lines
+                let before = format!(
+                    "{}\n{}",
+                    previous.title.as_deref().unwrap_or_default(),
+                    html_to_text(previous.description.as_deref().unwrap_or_default())
+                );
+                let after = format!(
+                    "{}\n{}",
+                    item.title.as_deref().unwrap_or_default(),
+                    html_to_text(item.description.as_deref().unwrap_or_default())
+                );
+                (
+                    format!("Updated offer: {title}"),
+                    line_diff(&before, &after),
+                )
+            }
+            None => (
+                title.to_string(),
+                item.description
+                    .as_deref()
+                    .map(html_to_text)
+                    .unwrap_or_else(|| "No description".to_string()),
+            ),
+        };
+
+        let mut fields = Vec::new();
+        if !item.is_update() {
+            for (name, value) in item.offer().fields() {
+                fields.push(Field {
+                    name,
+                    value,
+                    inline: true,
+                });
+            }
+        }
+
+        if let Some(date) = item.pub_date {
+            fields.push(Field {
+                name: "Date",
+                value: date.to_rfc2822(),
+                inline: false,
+            });
+        }
+
+        let categories = item.categories.join(", ");
+        if !categories.is_empty() {
+            fields.push(Field {
+                name: "Categories",
+                value: categories,
+                inline: false,
+            });
+        }
+
+        Self {
+            title,
+            link: item.link.clone(),
+            description,
+            html_description: item.description.clone().filter(|_| !item.is_update()),
+            is_diff: item.is_update(),
+            fields,
+            alternates: item
+                .alternates
+                .iter()
+                .map(|alternate| (alternate.locale.to_string(), alternate.url.clone()))
+                .collect(),
+        }
+    }
+
+    /// Summaries with one line per item and the indices of their items.
+    /// Items are split over multiple summaries to keep each within `max` characters
+    pub fn digests(items: &[&FeedItem], max: usize) -> Vec<(Vec<usize>, Self)> {
+        let budget = max.saturating_sub(DIGEST_TITLE_RESERVE);
+        let mut digests = Vec::new();
+        let mut indices = Vec::new();
+        let mut lines: Vec<String> = Vec::new();
+        let mut length = 0;
+
+        let mut flush = |indices: &mut Vec<usize>, lines: &mut Vec<String>| {
+            if lines.is_empty() {
+                return;
+            }
+            digests.push((std::mem::take(indices), Self::digest(lines)));
+            lines.clear();
+        };
+
+        for (index, item) in items.iter().enumerate() {
+            let line = digest_line(item);
+            // Measured escaped, backends escape the description before sending it
+            let line_length = escape_html(&line).chars().count() + 1;
+            if length + line_length > budget {
+                flush(&mut indices, &mut lines);
+                length = 0;
+            }
+            length += line_length;
+            indices.push(index);
+            lines.push(line);
+        }
+        flush(&mut indices, &mut lines);
+
+        digests
+    }
+
+    fn digest(lines: &[String]) -> Self {
+        Self {
+            title: format!("{} offers", lines.len()),
+            link: None,
+            description: lines.join("\n"),
+            html_description: None,
+            is_diff: false,
+            fields: Vec::new(),
+            alternates: Vec::new(),
+        }
+    }
+
+    /// Plain text version for clients without formatting support
+    pub fn to_plain_text(&self) -> String {
+        let mut lines = vec![self.title.clone()];
+        if let Some(link) = &self.link {
+            lines.push(link.clone());
+        }
+        lines.push(String::new());
+        lines.push(self.description.clone());
+        for field in &self.fields {
+            lines.push(format!("{}: {}", field.name, field.value));
+        }
+        for (locale, url) in &self.alternates {
+            lines.push(format!("{locale}: {url}"));
+        }
+        lines.join("\n")
+    }
+
+    /// Html version using the subset supported by Telegram and Matrix.
+    /// The description is cut so the text has at most `max` characters
+    pub fn to_html(&self, max: usize) -> String {
+        let title = match &self.link {
+            Some(link) => format!(
+                "<b><a href=\"{}\">{}</a></b>",
+                escape_html(link),
+                escape_html(&self.title)
+            ),
+            None => format!("<b>{}</b>", escape_html(&self.title)),
+        };
+
+        let description = escape_html(&self.description);
+        let wrap = if self.is_diff {
+            ("<pre>", "</pre>")
+        } else {
+            ("", "")
+        };
+
+        let mut lines = Vec::new();
+        for field in &self.fields {
+            lines.push(format!(
+                "<b>{}:</b> {}",
+                field.name,
+                escape_html(&field.value)
+            ));
+        }
+
+        let alternates = self
+            .alternates
+            .iter()
+            .map(|(locale, url)| format!("<a href=\"{}\">{locale}</a>", escape_html(url)))
+            .collect::<Vec<String>>()
+            .join(" | ");
+        if !alternates.is_empty() {
+            lines.push(format!("<b>Other languages:</b> {alternates}"));
+        }
+
+        join_lines(title, &description, wrap, lines, max)
+    }
+}
+
+/// Joins the lines of a message, the escaped description is cut to keep the text within `max` characters
+fn join_lines(
+    title: String,
+    description: &str,
+    (open, close): (&str, &str),
+    lines: Vec<String>,
+    max: usize,
+) -> String {
+    // Every line after the title adds a newline
+    let length = title.chars().count()
+        + open.chars().count()
+        + close.chars().count()
+        + 1
+        + lines
+            .iter()
+            .map(|line| line.chars().count() + 1)
+            .sum::<usize>();
+    let description = truncate_escaped(description, max.saturating_sub(length));
+
+    let mut text = vec![title, format!("{open}{description}{close}")];
+    text.extend(lines);
+    text.join("\n")
+}
+
+/// Cuts escaped text to at most `max` characters without splitting an entity
+fn truncate_escaped(text: &str, max: usize) -> String {
+    if text.chars().count() <= max {
+        return text.to_string();
+    }
+    if max == 0 {
+        return String::new();
+    }
+
+    let mut truncated = text.chars().take(max - 1).collect::<String>();
+    if let Some(position) = truncated
+        .rfind('&')
+        .filter(|position| !truncated[*position..].contains(';'))
+    {
+        truncated.truncate(position);
+    }
+    truncated.push('…');
+    truncated
+}
+
+fn digest_line(item: &FeedItem) -> String {
+    let mut line = format!("• {}", item.title.as_deref().unwrap_or("No title"));
+    if item.is_update() {
+        line.push_str(" (updated)");
+    } else if let Some(price) = item.offer().price {
+        line.push_str(&format!(" - {price}"));
+    }
+    if let Some(link) = &item.link {
+        line.push_str(&format!("\n  {link}"));
+    }
+    line
+}
+
+pub fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+/// Sends the request and turns non success responses into errors.
+/// Urls are removed from errors because they can contain tokens
+async fn send(backend: &str, request: RequestBuilder) -> Result<reqwest::Response> {
+    let response = request.send().await.map_err(|e| match e {
+        reqwest_middleware::Error::Reqwest(e) => Error::Reqwest(e.without_url()),
+        e => Error::ReqwestMiddleware(e),
+    })?;
+
+    let status = response.status();
+    if !status.is_success() {
+        let body = response.text().await.unwrap_or_default();
+        if is_too_long(status, &body) {
+            return Err(Error::MessageTooLong(format!(
+                "{backend} responded with {status}: {body}"
+            )));
+        }
+        return Err(Error::Notifier(format!(
+            "{backend} responded with {status}: {body}"
+        )));
+    }
+
+    Ok(response)
+}
+
+/// Whether the backend rejected the message for its size, sending it again can't succeed
+fn is_too_long(status: StatusCode, body: &str) -> bool {
+    let body = body.to_lowercase();
+    status == StatusCode::PAYLOAD_TOO_LARGE
+        || (status.is_client_error()
+            && ["too long", "too_long", "too large", "too_large"]
+                .iter()
+                .any(|pattern| body.contains(pattern)))
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use crate::feed_item::{AlternateLink, ItemSnapshot};
+    use crate::locale::Locale;
+    use reqwest_middleware::ClientBuilder;
+
+    use super::*;
+
+    pub fn create_client() -> ClientWithMiddleware {
+        ClientBuilder::new(reqwest::Client::new()).build()
+    }
+
+    pub fn create_feed() -> Feed {
+        Feed::new("netcup", "Netcup", "https://example.com/feed.xml")
+    }
+
+    pub fn create_item() -> FeedItem {
+        FeedItem {
+            title: Some("RS 1000 <G11>".to_string()),
+            link: Some("https://example.com/rs-1000".to_string()),
+            description: Some("4 vCore, 8 GB RAM, 9,99 € / Monat".to_string()),
+            categories: vec!["Root-Server".to_string()],
+            alternates: vec![AlternateLink {
+                locale: Locale::En,
+                url: "https://example.com/en/rs-1000".to_string(),
+            }],
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_message_new() {
+        let message = Message::new(&create_item());
+
+        assert_eq!(message.title, "RS 1000 <G11>");
+        assert!(!message.is_diff);
+        assert!(message
+            .fields
+            .iter()
+            .any(|field| field.name == "Price" && field.inline));
+        assert_eq!(
+            message.fields.last().map(|field| field.name),
+            Some("Categories")
+        );
+        assert_eq!(
+            message.alternates,
+            vec![(
+                "EN".to_string(),
+                "https://example.com/en/rs-1000".to_string()
+            )]
+        );
+    }
+
+    #[test]
+    fn test_message_update() {
+        let item = FeedItem {
+            previous: Some(ItemSnapshot {
+                title: Some("RS 1000 <G11>".to_string()),
+                description: Some("old".to_string()),
+            }),
+            ..create_item()
+        };
+
+        let message = Message::new(&item);
+
+        assert_eq!(message.title, "Updated offer: RS 1000 <G11>");
+        assert!(message.is_diff);
+        assert!(message.description.contains("- old"));
+        assert!(!message.fields.iter().any(|field| field.inline));
+
+        let item = FeedItem {
+            previous: Some(ItemSnapshot {
+                title: item.title.clone(),
+                description: Some("<p><b>RS 1000</b></p><ul><li>8 GB RAM</li></ul>".to_string()),
+            }),
+            description: Some("<p><b>RS 1000</b></p><ul><li>16 GB RAM</li></ul>".to_string()),
+            ..create_item()
+        };
+        let message = Message::new(&item);
+        assert_eq!(
+            message.description,
+            "  RS 1000 <G11>\n  RS 1000\n  \n- - 8 GB RAM\n+ - 16 GB RAM"
+        );
+        assert!(!message.fields.iter().any(|field| field.inline));
+    }
+
+    #[test]
+    fn test_message_html_description() {
+        let item = FeedItem {
+            description: Some(
+                "<p><strong>RS 1000 G11</strong></p><ul><li>8 GB RAM &amp; 256 GB SSD</li></ul>"
+                    .to_string(),
+            ),
+            ..create_item()
+        };
+
+        let message = Message::new(&item);
+
+        assert_eq!(
+            message.description,
+            "RS 1000 G11\n\n- 8 GB RAM & 256 GB SSD"
+        );
+        assert_eq!(message.html_description, item.description);
+        let html = message.to_html(usize::MAX);
+        assert!(html.contains("RS 1000 G11\n\n- 8 GB RAM &amp; 256 GB SSD"));
+        assert!(!html.contains("&lt;p&gt;"));
+        assert!(!message.to_plain_text().contains("<p>"));
+    }
+
+    #[test]
+    fn test_message_digest() {
+        let item = create_item();
+        let update = FeedItem {
+            previous: Some(ItemSnapshot::default()),
+            link: None,
+            ..create_item()
+        };
+
+        let digests = Message::digests(&[&item, &update], usize::MAX);
+
+        assert_eq!(digests.len(), 1);
+        let (indices, message) = &digests[0];
+        assert_eq!(indices, &vec![0, 1]);
+        assert_eq!(message.title, "2 offers");
+        assert_eq!(
+            message.description,
+            "• RS 1000 <G11> - 9.99 € / month\n  https://example.com/rs-1000\n• RS 1000 <G11> (updated)"
+        );
+    }
+
+    #[test]
+    fn test_message_digests_split() {
+        let items = (0..200)
+            .map(|index| FeedItem {
+                title: Some(format!("RS {index} <G11>")),
+                link: Some(format!("https://example.com/rs-{index}")),
+                ..create_item()
+            })
+            .collect::<Vec<FeedItem>>();
+        let items = items.iter().collect::<Vec<&FeedItem>>();
+
+        let digests = Message::digests(&items, 4096);
+
+        assert!(digests.len() > 1);
+        assert_eq!(
+            digests
+                .iter()
+                .flat_map(|(indices, _)| indices.clone())
+                .collect::<Vec<usize>>(),
+            (0..200).collect::<Vec<usize>>()
+        );
+        for (indices, message) in &digests {
+            assert_eq!(message.title, format!("{} offers", indices.len()));
+            let html = message.to_html(usize::MAX);
+            assert!(html.chars().count() <= 4096, "{}", html.len());
+        }
+    }
+
+    #[test]
+    fn test_message_to_html() {
+        let html = Message::new(&create_item()).to_html(usize::MAX);
+
+        assert!(html
+            .starts_with("<b><a href=\"https://example.com/rs-1000\">RS 1000 &lt;G11&gt;</a></b>"));
+        assert!(html.contains("<b>Categories:</b> Root-Server"));
+        assert!(html.contains("<a href=\"https://example.com/en/rs-1000\">EN</a>"));
+    }
+}
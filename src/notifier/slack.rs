@@ -0,0 +1,178 @@
+use async_trait::async_trait;
+use reqwest_middleware::ClientWithMiddleware;
+
+use crate::feed::Feed;
+use crate::notifier::{join_lines, send, Message, Notifier};
+use crate::Result;
+
+// https://api.slack.com/reference/block-kit/blocks#section
+const MAX_SECTION_LENGTH: usize = 3000;
+
+/// Slack incoming webhook
+pub struct SlackWebhook {
+    client: ClientWithMiddleware,
+    url: String,
+}
+
+impl std::fmt::Debug for SlackWebhook {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("SlackWebhook").finish()
+    }
+}
+
+impl SlackWebhook {
+    pub fn new(client: ClientWithMiddleware, url: &str) -> Self {
+        Self {
+            client,
+            url: url.to_string(),
+        }
+    }
+}
+
+/// Slack only requires &, < and > to be escaped
+fn escape_mrkdwn(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+}
+
+/// The description is cut so the text has at most `max` characters
+fn to_mrkdwn(message: &Message, max: usize) -> String {
+    let title = match &message.link {
+        Some(link) => format!("*<{link}|{}>*", escape_mrkdwn(&message.title)),
+        None => format!("*{}*", escape_mrkdwn(&message.title)),
+    };
+
+    let description = escape_mrkdwn(&message.description);
+    let wrap = if message.is_diff {
+        ("```", "```")
+    } else {
+        ("", "")
+    };
+
+    let mut lines = Vec::new();
+    for field in &message.fields {
+        lines.push(format!("*{}:* {}", field.name, escape_mrkdwn(&field.value)));
+    }
+
+    let alternates = message
+        .alternates
+        .iter()
+        .map(|(locale, url)| format!("<{url}|{locale}>"))
+        .collect::<Vec<String>>()
+        .join(" | ");
+    if !alternates.is_empty() {
+        lines.push(format!("*Other languages:* {alternates}"));
+    }
+
+    join_lines(title, &description, wrap, lines, max)
+}
+
+#[async_trait]
+impl Notifier for SlackWebhook {
+    #[tracing::instrument(skip(message))]
+    async fn send_message(&self, feed: &Feed, message: &Message) -> Result<()> {
+        info!(
+            "Sending Slack message for feed {} with title \"{}\"",
+            feed.name(),
+            message.title
+        );
+
+        let body = serde_json::json!({
+            "text": message.title,
+            "blocks": [{
+                "type": "section",
+                "text": {"type": "mrkdwn", "text": to_mrkdwn(message, MAX_SECTION_LENGTH)}
+            }]
+        });
+        send("Slack", self.client.post(&self.url).json(&body)).await?;
+
+        Ok(())
+    }
+
+    fn max_message_length(&self) -> usize {
+        MAX_SECTION_LENGTH
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use wiremock::matchers::{body_partial_json, method, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    use crate::feed_item::FeedItem;
+    use crate::notifier::tests::{create_client, create_feed, create_item};
+
+    use super::*;
+
+    #[test]
+    fn test_to_mrkdwn() {
+        let text = to_mrkdwn(&Message::new(&create_item()), MAX_SECTION_LENGTH);
+
+        assert!(text.starts_with("*<https://example.com/rs-1000|RS 1000 &lt;G11&gt;>*\n"));
+        assert!(text.contains("*Other languages:* <https://example.com/en/rs-1000|EN>"));
+
+        let item = FeedItem {
+            description: Some("<p><strong>RS 1000</strong></p>".to_string()),
+            ..create_item()
+        };
+        let text = to_mrkdwn(&Message::new(&item), MAX_SECTION_LENGTH);
+        assert!(text.contains("\nRS 1000\n"));
+        assert!(!text.contains("&lt;p&gt;"));
+    }
+
+    #[test]
+    fn test_to_mrkdwn_long_description() {
+        let item = FeedItem {
+            description: Some("8 GB RAM & 256 GB SSD, ".repeat(500)),
+            ..create_item()
+        };
+
+        let text = to_mrkdwn(&Message::new(&item), MAX_SECTION_LENGTH);
+
+        assert!(text.chars().count() <= MAX_SECTION_LENGTH);
+        assert!(text.ends_with("*Other languages:* <https://example.com/en/rs-1000|EN>"));
+        assert!(text.contains("…\n*RAM:* 8 GB"));
+    }
+
+    #[tokio::test]
+    async fn test_notify() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/services/T000/B000/XXX"))
+            .and(body_partial_json(
+                serde_json::json!({"text": "RS 1000 <G11>"}),
+            ))
+            .respond_with(ResponseTemplate::new(200).set_body_string("ok"))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let notifier = SlackWebhook::new(
+            create_client(),
+            &format!("{}/services/T000/B000/XXX", server.uri()),
+        );
+
+        notifier
+            .notify(&create_feed(), &create_item())
+            .await
+            .unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_notify_error() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .respond_with(ResponseTemplate::new(404).set_body_string("no_service"))
+            .mount(&server)
+            .await;
+
+        let notifier = SlackWebhook::new(create_client(), &server.uri());
+
+        let error = notifier
+            .notify(&create_feed(), &create_item())
+            .await
+            .unwrap_err();
+        assert!(error.to_string().contains("no_service"));
+    }
+}
@@ -0,0 +1,204 @@
+use async_trait::async_trait;
+use reqwest_middleware::ClientWithMiddleware;
+
+use crate::feed::Feed;
+use crate::notifier::{send, Message, Notifier};
+use crate::Result;
+
+pub const DEFAULT_API_URL: &str = "https://api.telegram.org";
+// https://core.telegram.org/bots/api#sendmessage
+const MAX_MESSAGE_LENGTH: usize = 4096;
+
+/// Telegram Bot API, sends html formatted messages to a single chat
+pub struct TelegramBot {
+    client: ClientWithMiddleware,
+    api_url: String,
+    bot_token: String,
+    chat_id: String,
+}
+
+impl std::fmt::Debug for TelegramBot {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("TelegramBot")
+            .field("chat_id", &self.chat_id)
+            .finish()
+    }
+}
+
+impl TelegramBot {
+    pub fn new(
+        client: ClientWithMiddleware,
+        api_url: &str,
+        bot_token: &str,
+        chat_id: &str,
+    ) -> Self {
+        Self {
+            client,
+            api_url: api_url.trim_end_matches('/').to_string(),
+            bot_token: bot_token.to_string(),
+            chat_id: chat_id.to_string(),
+        }
+    }
+}
+
+#[async_trait]
+impl Notifier for TelegramBot {
+    #[tracing::instrument(skip(message))]
+    async fn send_message(&self, feed: &Feed, message: &Message) -> Result<()> {
+        info!(
+            "Sending Telegram message for feed {} with title \"{}\"",
+            feed.name(),
+            message.title
+        );
+
+        let url = format!("{}/bot{}/sendMessage", self.api_url, self.bot_token);
+        let body = serde_json::json!({
+            "chat_id": self.chat_id,
+            "text": message.to_html(MAX_MESSAGE_LENGTH),
+            "parse_mode": "HTML",
+            "disable_web_page_preview": true,
+        });
+        send("Telegram", self.client.post(url).json(&body)).await?;
+
+        Ok(())
+    }
+
+    fn max_message_length(&self) -> usize {
+        MAX_MESSAGE_LENGTH
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use wiremock::matchers::{body_partial_json, method, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    use crate::feed_item::FeedItem;
+    use crate::notifier::tests::{create_client, create_feed, create_item};
+
+    use super::*;
+
+    #[tokio::test]
+    async fn test_notify() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/bot123:abc/sendMessage"))
+            .and(body_partial_json(serde_json::json!({
+                "chat_id": "-100123",
+                "parse_mode": "HTML"
+            })))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({"ok": true})))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let notifier = TelegramBot::new(create_client(), &server.uri(), "123:abc", "-100123");
+
+        notifier
+            .notify(&create_feed(), &create_item())
+            .await
+            .unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_notify_long_description() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({"ok": true})))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let notifier = TelegramBot::new(create_client(), &server.uri(), "123:abc", "-100123");
+        let item = FeedItem {
+            description: Some("8 GB RAM & 256 GB SSD, ".repeat(500)),
+            ..create_item()
+        };
+        notifier.notify(&create_feed(), &item).await.unwrap();
+
+        let requests = server.received_requests().await.unwrap();
+        let body = serde_json::from_slice::<serde_json::Value>(&requests[0].body).unwrap();
+        let text = body["text"].as_str().unwrap();
+        assert!(text.chars().count() <= MAX_MESSAGE_LENGTH);
+        assert!(text.contains("…\n<b>RAM:</b> 8 GB"));
+        assert!(!text.contains("&…"));
+    }
+
+    #[tokio::test]
+    async fn test_notify_digest_split() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({"ok": true})))
+            .mount(&server)
+            .await;
+
+        let notifier = TelegramBot::new(create_client(), &server.uri(), "123:abc", "-100123");
+        let items = (0..200)
+            .map(|index| FeedItem {
+                title: Some(format!("RS {index} G11")),
+                link: Some(format!("https://example.com/rs-{index}")),
+                ..create_item()
+            })
+            .collect::<Vec<FeedItem>>();
+        let items = items.iter().collect::<Vec<&FeedItem>>();
+
+        let deliveries = notifier.notify_digest(&create_feed(), &items).await;
+
+        assert!(deliveries.len() > 1);
+        assert!(deliveries.iter().all(|delivery| delivery.result.is_ok()));
+        assert_eq!(
+            deliveries
+                .iter()
+                .map(|delivery| delivery.items.len())
+                .sum::<usize>(),
+            200
+        );
+        for request in server.received_requests().await.unwrap() {
+            let body = serde_json::from_slice::<serde_json::Value>(&request.body).unwrap();
+            assert!(body["text"].as_str().unwrap().chars().count() <= MAX_MESSAGE_LENGTH);
+            assert!(!body["text"].as_str().unwrap().contains('…'));
+        }
+    }
+
+    #[tokio::test]
+    async fn test_notify_too_long() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .respond_with(ResponseTemplate::new(400).set_body_json(serde_json::json!({
+                "ok": false,
+                "description": "Bad Request: message is too long"
+            })))
+            .mount(&server)
+            .await;
+
+        let notifier = TelegramBot::new(create_client(), &server.uri(), "123:abc", "-100123");
+
+        let error = notifier
+            .notify(&create_feed(), &create_item())
+            .await
+            .unwrap_err();
+        assert!(error.is_permanent());
+    }
+
+    #[tokio::test]
+    async fn test_notify_error() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .respond_with(ResponseTemplate::new(400).set_body_json(serde_json::json!({
+                "ok": false,
+                "description": "Bad Request: chat not found"
+            })))
+            .mount(&server)
+            .await;
+
+        let notifier = TelegramBot::new(create_client(), &server.uri(), "123:abc", "-100123");
+
+        let error = notifier
+            .notify(&create_feed(), &create_item())
+            .await
+            .unwrap_err();
+        assert!(error.to_string().contains("chat not found"));
+        assert!(!error.to_string().contains("123:abc"));
+        assert!(!error.is_permanent());
+    }
+}
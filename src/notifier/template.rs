@@ -0,0 +1,339 @@
+use std::sync::Arc;
+
+use chrono::{FixedOffset, TimeZone};
+use minijinja::value::Value;
+use minijinja::{context, Environment, UndefinedBehavior};
+use serde::Deserialize;
+
+use crate::error::Error;
+use crate::feed::Feed;
+use crate::feed_item::FeedItem;
+use crate::notifier::markdown::html_to_markdown;
+use crate::offer::{format_size, strip_html};
+use crate::Result;
+
+/// Message layout of a destination, every value is a minijinja template.
+/// Parts that aren't set keep the default layout
+#[derive(Debug, Default, Clone, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct TemplateConfig {
+    pub username: Option<String>,
+    pub avatar_url: Option<String>,
+    /// Text above the embed
+    pub content: Option<String>,
+    pub title: Option<String>,
+    pub description: Option<String>,
+    /// Hex (`#00ff00`) or decimal color of the embed
+    pub color: Option<String>,
+    pub footer: Option<String>,
+    /// Replaces the offer, date and categories fields
+    pub fields: Option<Vec<FieldTemplateConfig>>,
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct FieldTemplateConfig {
+    pub name: String,
+    pub value: String,
+    #[serde(default)]
+    pub inline: bool,
+}
+
+/// Rendered parts of a template, `None` if the part isn't templated
+#[derive(Debug, Default, PartialEq)]
+pub struct Rendered {
+    pub username: Option<String>,
+    pub avatar_url: Option<String>,
+    pub content: Option<String>,
+    pub title: Option<String>,
+    pub description: Option<String>,
+    pub color: Option<u32>,
+    pub footer: Option<String>,
+    pub fields: Option<Vec<RenderedField>>,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct RenderedField {
+    pub name: String,
+    pub value: String,
+    pub inline: bool,
+}
+
+/// Compiled templates, validated against a sample offer when created
+#[derive(Debug, Clone)]
+pub struct MessageTemplate {
+    environment: Arc<Environment<'static>>,
+    fields: Vec<bool>,
+}
+
+const PARTS: [&str; 7] = [
+    "username",
+    "avatar_url",
+    "content",
+    "title",
+    "description",
+    "color",
+    "footer",
+];
+
+fn create_environment() -> Environment<'static> {
+    let mut environment = Environment::new();
+    // Typos in variable names are reported at startup instead of rendering as empty text
+    environment.set_undefined_behavior(UndefinedBehavior::Strict);
+    // Missing offer values render as empty text instead of "none"
+    environment.set_formatter(|out, state, value| {
+        if value.is_none() {
+            Ok(())
+        } else {
+            minijinja::escape_formatter(out, state, value)
+        }
+    });
+    environment
+}
+
+fn parse_color(color: &str) -> Result<u32> {
+    let color = color.trim();
+    let parsed = match color.strip_prefix('#') {
+        Some(hex) => u32::from_str_radix(hex, 16).ok(),
+        None => color.parse::<u32>().ok(),
+    };
+    parsed
+        .filter(|color| *color <= 0xFFFFFF)
+        .ok_or_else(|| Error::Notifier(format!("Invalid embed color: {color}")))
+}
+
+/// Variables available in the templates
+fn create_context(feed: &Feed, item: &FeedItem) -> Value {
+    let offer = item.offer();
+    let fields = offer
+        .fields()
+        .into_iter()
+        .map(|(name, value)| context! { name, value })
+        .collect::<Vec<Value>>();
+
+    context! {
+        feed => context! {
+            id => feed.id(),
+            name => feed.name(),
+        },
+        item => context! {
+            title => item.title.as_deref().unwrap_or("No title"),
+            link => item.link,
+            description => item.description.as_deref().map(strip_html),
+            markdown => item.description.as_deref().map(html_to_markdown),
+            html => item.description,
+            date => item.pub_date.map(|date| date.to_rfc2822()),
+            categories => item.categories,
+            locale => item.locale.map(|locale| locale.to_string()),
+            is_update => item.is_update(),
+        },
+        offer => context! {
+            price => offer.price.map(|price| price.to_string()),
+            price_amount => offer.price.map(|price| price.amount),
+            setup_fee => offer.setup_fee,
+            vcores => offer.vcores,
+            ram => offer.ram_gb.map(format_size),
+            ram_gb => offer.ram_gb,
+            storage => offer.storage_gb.map(format_size),
+            storage_gb => offer.storage_gb,
+            storage_type => offer.storage_type,
+            traffic => offer.traffic.map(|traffic| traffic.to_string()),
+            contract_term_months => offer.contract_term_months,
+            fields,
+        },
+    }
+}
+
+fn sample_item() -> FeedItem {
+    FeedItem {
+        title: Some("RS 1000 G11".to_string()),
+        link: Some("https://www.netcup.com/de/server/root-server".to_string()),
+        description: Some(
+            "<p>4 vCore, 8 GB RAM, 256 GB SSD, Traffic inklusive, 9,99 € / Monat</p>".to_string(),
+        ),
+        pub_date: FixedOffset::east_opt(0)
+            .and_then(|offset| offset.with_ymd_and_hms(2025, 1, 1, 0, 0, 0).single()),
+        categories: vec!["Root-Server".to_string()],
+        ..Default::default()
+    }
+}
+
+impl TryFrom<TemplateConfig> for MessageTemplate {
+    type Error = Error;
+
+    fn try_from(config: TemplateConfig) -> std::result::Result<Self, Self::Error> {
+        let mut environment = create_environment();
+        let parts = [
+            config.username,
+            config.avatar_url,
+            config.content,
+            config.title,
+            config.description,
+            config.color,
+            config.footer,
+        ];
+        for (name, source) in PARTS.iter().zip(parts) {
+            if let Some(source) = source {
+                environment
+                    .add_template_owned(*name, source)
+                    .map_err(|e| Error::ConfigVar(format!("Invalid template: {e}")))?;
+            }
+        }
+
+        let fields = config.fields.unwrap_or_default();
+        for (index, field) in fields.iter().enumerate() {
+            environment
+                .add_template_owned(format!("fields.{index}.name"), field.name.clone())
+                .and_then(|_| {
+                    environment
+                        .add_template_owned(format!("fields.{index}.value"), field.value.clone())
+                })
+                .map_err(|e| Error::ConfigVar(format!("Invalid template: {e}")))?;
+        }
+
+        let template = Self {
+            environment: Arc::new(environment),
+            fields: fields.iter().map(|field| field.inline).collect(),
+        };
+        template
+            .render(&Feed::new("netcup", "Netcup", ""), &sample_item())
+            .map_err(|e| Error::ConfigVar(format!("Invalid template: {e}")))?;
+
+        Ok(template)
+    }
+}
+
+impl MessageTemplate {
+    fn render_part(&self, name: &str, context: &Value) -> Result<Option<String>> {
+        match self.environment.get_template(name) {
+            Ok(template) => Ok(Some(template.render(context)?.trim().to_string())),
+            Err(_) => Ok(None),
+        }
+    }
+
+    pub fn render(&self, feed: &Feed, item: &FeedItem) -> Result<Rendered> {
+        let context = create_context(feed, item);
+        // Empty optional parts are left out of the message
+        let optional = |name| -> Result<Option<String>> {
+            Ok(self
+                .render_part(name, &context)?
+                .filter(|value| !value.is_empty()))
+        };
+
+        let fields = if self.fields.is_empty() {
+            None
+        } else {
+            let mut fields = Vec::with_capacity(self.fields.len());
+            for (index, inline) in self.fields.iter().enumerate() {
+                let name = self.render_part(&format!("fields.{index}.name"), &context)?;
+                let value = self.render_part(&format!("fields.{index}.value"), &context)?;
+                // Fields without a value are skipped, e.g. if the offer couldn't be parsed
+                if let (Some(name), Some(value)) = (name, value.filter(|v| !v.is_empty())) {
+                    fields.push(RenderedField {
+                        name,
+                        value,
+                        inline: *inline,
+                    });
+                }
+            }
+            Some(fields)
+        };
+
+        Ok(Rendered {
+            username: optional("username")?,
+            avatar_url: optional("avatar_url")?,
+            content: optional("content")?,
+            title: self.render_part("title", &context)?,
+            description: self.render_part("description", &context)?,
+            color: optional("color")?.as_deref().map(parse_color).transpose()?,
+            footer: optional("footer")?,
+            fields,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::notifier::tests::{create_feed, create_item};
+
+    use super::*;
+
+    fn create_template(config: TemplateConfig) -> Result<MessageTemplate> {
+        MessageTemplate::try_from(config)
+    }
+
+    #[test]
+    fn test_render() {
+        let template = create_template(TemplateConfig {
+            username: Some("{{ feed.name }} offers".to_string()),
+            title: Some("{{ item.title }} for {{ offer.price }}".to_string()),
+            color: Some("#00ff00".to_string()),
+            footer: Some("{{ item.categories | join(', ') }}".to_string()),
+            fields: Some(vec![
+                FieldTemplateConfig {
+                    name: "RAM".to_string(),
+                    value: "{{ offer.ram }}".to_string(),
+                    inline: true,
+                },
+                FieldTemplateConfig {
+                    name: "Setup".to_string(),
+                    value: "{{ offer.setup_fee }}".to_string(),
+                    inline: false,
+                },
+            ]),
+            ..Default::default()
+        })
+        .unwrap();
+
+        let rendered = template.render(&create_feed(), &create_item()).unwrap();
+
+        assert_eq!(rendered.username.as_deref(), Some("Netcup offers"));
+        assert_eq!(
+            rendered.title.as_deref(),
+            Some("RS 1000 <G11> for 9.99 € / month")
+        );
+        assert_eq!(rendered.color, Some(0x00ff00));
+        assert_eq!(rendered.description, None);
+        // The setup fee isn't part of the description, its field is skipped
+        assert_eq!(
+            rendered.fields,
+            Some(vec![RenderedField {
+                name: "RAM".to_string(),
+                value: "8 GB".to_string(),
+                inline: true,
+            }])
+        );
+    }
+
+    #[test]
+    fn test_syntax_error() {
+        let error = create_template(TemplateConfig {
+            title: Some("{{ item.title".to_string()),
+            ..Default::default()
+        })
+        .unwrap_err();
+
+        assert!(error.to_string().contains("title"));
+    }
+
+    #[test]
+    fn test_unknown_variable() {
+        let error = create_template(TemplateConfig {
+            footer: Some("{{ offer.cpu }}".to_string()),
+            ..Default::default()
+        })
+        .unwrap_err();
+
+        assert!(error.to_string().contains("footer"));
+    }
+
+    #[test]
+    fn test_invalid_color() {
+        assert!(create_template(TemplateConfig {
+            color: Some("green".to_string()),
+            ..Default::default()
+        })
+        .is_err());
+        assert_eq!(parse_color("16711680").unwrap(), 0xFF0000);
+    }
+}
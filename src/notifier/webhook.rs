@@ -0,0 +1,231 @@
+use async_trait::async_trait;
+use reqwest_middleware::ClientWithMiddleware;
+
+use crate::error::Error;
+use crate::feed::Feed;
+use crate::feed_item::FeedItem;
+use crate::notifier::{send, Message, Notifier};
+use crate::Result;
+
+pub const DEFAULT_BODY_TEMPLATE: &str = r#"{"feed": "{{feed}}", "title": "{{title}}", "link": "{{link}}", "description": "{{description}}", "price": "{{price}}", "date": "{{date}}"}"#;
+
+const PLACEHOLDERS: [&str; 8] = [
+    "feed_id",
+    "feed",
+    "title",
+    "link",
+    "description",
+    "price",
+    "date",
+    "categories",
+];
+
+/// Generic webhook that posts a json body rendered from a template
+pub struct JsonWebhook {
+    client: ClientWithMiddleware,
+    url: String,
+    template: String,
+}
+
+impl std::fmt::Debug for JsonWebhook {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("JsonWebhook").finish()
+    }
+}
+
+impl JsonWebhook {
+    pub fn new(client: ClientWithMiddleware, url: &str, template: &str) -> Self {
+        Self {
+            client,
+            url: url.to_string(),
+            template: template.to_string(),
+        }
+    }
+}
+
+fn message_values(feed: &Feed, message: &Message) -> Vec<(&'static str, String)> {
+    vec![
+        ("feed_id", feed.id().to_string()),
+        ("feed", feed.name().to_string()),
+        ("title", message.title.clone()),
+        ("link", message.link.clone().unwrap_or_default()),
+        ("description", message.description.clone()),
+        ("price", String::new()),
+        ("date", String::new()),
+        ("categories", String::new()),
+    ]
+}
+
+fn item_values(feed: &Feed, item: &FeedItem) -> Vec<(&'static str, String)> {
+    let mut values = message_values(feed, &Message::new(item));
+    for (key, value) in values.iter_mut() {
+        match *key {
+            "price" => {
+                *value = item
+                    .offer()
+                    .price
+                    .map(|price| price.to_string())
+                    .unwrap_or_default()
+            }
+            "date" => {
+                *value = item
+                    .pub_date
+                    .map(|date| date.to_rfc3339())
+                    .unwrap_or_default()
+            }
+            "categories" => *value = item.categories.join(", "),
+            _ => {}
+        }
+    }
+    values
+}
+
+/// Replaces `{{name}}` placeholders with json escaped values, the template is responsible for the quotes
+fn render(template: &str, values: &[(&str, String)]) -> Result<String> {
+    let mut output = String::with_capacity(template.len());
+    let mut rest = template;
+    while let Some(start) = rest.find("{{") {
+        output.push_str(&rest[..start]);
+        let end = rest[start..]
+            .find("}}")
+            .ok_or_else(|| Error::Notifier("Unclosed placeholder in body template".to_string()))?;
+        let name = rest[start + 2..start + end].trim();
+        let (_, value) = values.iter().find(|(key, _)| *key == name).ok_or_else(|| {
+            Error::Notifier(format!("Unknown placeholder {name} in body template"))
+        })?;
+
+        // Strip the quotes of the json string, the template already contains them
+        let escaped = serde_json::to_string(value)?;
+        output.push_str(&escaped[1..escaped.len() - 1]);
+        rest = &rest[start + end + 2..];
+    }
+    output.push_str(rest);
+
+    Ok(output)
+}
+
+/// Checks that the template only uses known placeholders and renders to valid json
+pub fn validate_template(template: &str) -> Result<()> {
+    let values = PLACEHOLDERS
+        .iter()
+        .map(|name| (*name, "\"value\"\n".to_string()))
+        .collect::<Vec<(&str, String)>>();
+    let body = render(template, &values)?;
+    serde_json::from_str::<serde_json::Value>(&body)
+        .map_err(|e| Error::Notifier(format!("Body template is not valid json: {e}")))?;
+
+    Ok(())
+}
+
+impl JsonWebhook {
+    async fn post(&self, values: &[(&str, String)]) -> Result<()> {
+        let body = render(&self.template, values)?;
+        send(
+            "Webhook",
+            self.client
+                .post(&self.url)
+                .header(reqwest::header::CONTENT_TYPE, "application/json")
+                .body(body),
+        )
+        .await?;
+
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl Notifier for JsonWebhook {
+    #[tracing::instrument(skip(message))]
+    async fn send_message(&self, feed: &Feed, message: &Message) -> Result<()> {
+        info!(
+            "Sending webhook for feed {} with title \"{}\"",
+            feed.name(),
+            message.title
+        );
+
+        self.post(&message_values(feed, message)).await
+    }
+
+    /// Uses the item directly to fill in the price, date and categories
+    #[tracing::instrument(skip(item))]
+    async fn notify(&self, feed: &Feed, item: &FeedItem) -> Result<()> {
+        info!(
+            "Sending webhook for feed {} with title \"{}\"",
+            feed.name(),
+            item.title.as_deref().unwrap_or("No title")
+        );
+
+        self.post(&item_values(feed, item)).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use wiremock::matchers::{body_json, header, method, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    use crate::notifier::tests::{create_client, create_feed, create_item};
+
+    use super::*;
+
+    #[test]
+    fn test_render_escapes() {
+        let values = vec![("title", "Say \"hi\"\n".to_string())];
+
+        assert_eq!(
+            render(r#"{"text": "{{ title }}"}"#, &values).unwrap(),
+            r#"{"text": "Say \"hi\"\n"}"#
+        );
+    }
+
+    #[test]
+    fn test_validate_template() {
+        assert!(validate_template(DEFAULT_BODY_TEMPLATE).is_ok());
+        assert!(validate_template(r#"{"text": "{{unknown}}"}"#).is_err());
+        assert!(validate_template(r#"{"text": "{{title}"}"#).is_err());
+        assert!(validate_template(r#"{"text": {{title}}}"#).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_notify() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/hook"))
+            .and(header("content-type", "application/json"))
+            .and(body_json(serde_json::json!({
+                "content": "Netcup: RS 1000 <G11> (9.99 € / month)",
+                "url": "https://example.com/rs-1000"
+            })))
+            .respond_with(ResponseTemplate::new(202))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let notifier = JsonWebhook::new(
+            create_client(),
+            &format!("{}/hook", server.uri()),
+            r#"{"content": "{{feed}}: {{title}} ({{price}})", "url": "{{link}}"}"#,
+        );
+
+        notifier
+            .notify(&create_feed(), &create_item())
+            .await
+            .unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_notify_error() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .respond_with(ResponseTemplate::new(500))
+            .mount(&server)
+            .await;
+
+        let notifier = JsonWebhook::new(create_client(), &server.uri(), DEFAULT_BODY_TEMPLATE);
+
+        assert!(notifier
+            .notify(&create_feed(), &create_item())
+            .await
+            .is_err());
+    }
+}
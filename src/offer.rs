@@ -0,0 +1,469 @@
+use std::fmt;
+
+use lazy_static::lazy_static;
+use regex::Regex;
+
+use crate::notifier::markdown::decode_entities;
+
+// Amount of characters before a price that are checked for one-time keywords
+const PRICE_CONTEXT_LENGTH: usize = 40;
+
+lazy_static! {
+    static ref TAG: Regex = Regex::new(r"<[^>]*>").unwrap();
+    static ref PRICE: Regex = Regex::new(
+        r"(?i)(?:(?:€|EUR)\s*(?P<before>\d+(?:[.,]\d{3})*(?:[.,]\d{1,2})?)|(?P<after>\d+(?:[.,]\d{3})*(?:[.,]\d{1,2})?)\s*(?:€|EUR))"
+    )
+    .unwrap();
+    static ref MONTHLY: Regex =
+        Regex::new(r"(?i)^\s*(?:/|pro|per|im|a)?\s*(?:monat|month|mtl|mon\b|mo\b)").unwrap();
+    static ref ONE_TIME: Regex =
+        Regex::new(r"(?i)(?:einmalig|one-time|one time|once|setup|einrichtung)").unwrap();
+    static ref VCORES: Regex = Regex::new(
+        r"(?i)(\d+)\s*(?:x\s*)?(?:dedizierte\s+|dedicated\s+)?(?:v-?cores?|vcpus?|kerne|cores?)\b"
+    )
+    .unwrap();
+    static ref RAM: [Regex; 2] = [
+        Regex::new(
+            r"(?i)(\d+(?:[.,]\d+)?)\s*(GB|GiB|TB|MB)\s*(?:DDR\d\s*)?(?:ECC\s*)?(?:RAM|Arbeitsspeicher|memory)"
+        )
+        .unwrap(),
+        Regex::new(r"(?i)(?:RAM|Arbeitsspeicher|memory)\s*:?\s*(\d+(?:[.,]\d+)?)\s*(GB|GiB|TB|MB)")
+            .unwrap(),
+    ];
+    static ref STORAGE: [Regex; 2] = [
+        Regex::new(
+            r"(?i)(\d+(?:[.,]\d+)?)\s*(GB|TB)\s*(?:(NVMe|SSD|HDD)\b|(?:Speicher|storage|Festplatte|disk))"
+        )
+        .unwrap(),
+        Regex::new(
+            r"(?i)(?:Speicherplatz|Speicher|storage|Festplatte|disk)\s*:?\s*(\d+(?:[.,]\d+)?)\s*(GB|TB)(?:\s*(NVMe|SSD|HDD)\b)?"
+        )
+        .unwrap(),
+    ];
+    static ref TRAFFIC_UNLIMITED: Regex = Regex::new(
+        r"(?i)(?:traffic\s+(?:inklusive|inklusiv|inclusive|included|unbegrenzt|unlimited|flat))|(?:(?:unlimited|unbegrenzter?|inklusive)\s+traffic)"
+    )
+    .unwrap();
+    static ref TRAFFIC: [Regex; 2] = [
+        Regex::new(r"(?i)(\d+(?:[.,]\d+)?)\s*(GB|TB)\s*traffic").unwrap(),
+        Regex::new(r"(?i)traffic\s*:?\s*(\d+(?:[.,]\d+)?)\s*(GB|TB)").unwrap(),
+    ];
+    static ref CONTRACT_TERM: [Regex; 2] = [
+        Regex::new(
+            r"(?i)(?:vertragslaufzeit|laufzeit|contract term|minimum term)\s*:?\s*(\d+)\s*(?:monate?n?|months?)"
+        )
+        .unwrap(),
+        Regex::new(r"(?i)(\d+)\s*(?:monate?n?|months?)\s*(?:vertragslaufzeit|laufzeit|contract|minimum term)")
+            .unwrap(),
+    ];
+}
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum BillingPeriod {
+    Monthly,
+    OneTime,
+}
+
+/// Price in EUR
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct Price {
+    pub amount: f64,
+    pub period: BillingPeriod,
+}
+
+impl fmt::Display for Price {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.period {
+            BillingPeriod::Monthly => write!(f, "{:.2} € / month", self.amount),
+            BillingPeriod::OneTime => write!(f, "{:.2} € one-time", self.amount),
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum Traffic {
+    Unlimited,
+    Limited { gb: f64 },
+}
+
+impl fmt::Display for Traffic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Traffic::Unlimited => write!(f, "Unlimited"),
+            Traffic::Limited { gb } => write!(f, "{}", format_size(*gb)),
+        }
+    }
+}
+
+/// Price and specs parsed from the description of a netcup offer, fields are missing if not found
+#[derive(Debug, PartialEq, Clone, Default)]
+pub struct Offer {
+    pub price: Option<Price>,
+    pub setup_fee: Option<f64>,
+    pub vcores: Option<u32>,
+    pub ram_gb: Option<f64>,
+    pub storage_gb: Option<f64>,
+    pub storage_type: Option<String>,
+    pub traffic: Option<Traffic>,
+    pub contract_term_months: Option<u32>,
+}
+
+impl Offer {
+    pub fn parse(description: &str) -> Self {
+        let text = strip_html(description);
+
+        let (price, setup_fee) = parse_price(&text);
+        let (storage_gb, storage_type) = match parse_storage(&text) {
+            Some((size, kind)) => (Some(size), kind),
+            None => (None, None),
+        };
+
+        Self {
+            price,
+            setup_fee,
+            vcores: VCORES
+                .captures(&text)
+                .and_then(|captures| captures[1].parse().ok()),
+            ram_gb: RAM
+                .iter()
+                .find_map(|regex| regex.captures(&text))
+                .and_then(|captures| to_gb(&captures[1], &captures[2], 1024.0)),
+            storage_gb,
+            storage_type,
+            traffic: parse_traffic(&text),
+            contract_term_months: CONTRACT_TERM
+                .iter()
+                .find_map(|regex| regex.captures(&text))
+                .and_then(|captures| captures[1].parse().ok()),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        *self == Offer::default()
+    }
+
+    /// Display name and value of all found specs
+    pub fn fields(&self) -> Vec<(&'static str, String)> {
+        let mut fields = Vec::new();
+        if let Some(price) = self.price {
+            fields.push(("Price", price.to_string()));
+        }
+        if let Some(setup_fee) = self.setup_fee {
+            fields.push(("Setup fee", format!("{setup_fee:.2} €")));
+        }
+        if let Some(vcores) = self.vcores {
+            fields.push(("vCores", vcores.to_string()));
+        }
+        if let Some(ram) = self.ram_gb {
+            fields.push(("RAM", format_size(ram)));
+        }
+        if let Some(storage) = self.storage_gb {
+            let storage = match &self.storage_type {
+                Some(kind) => format!("{} {kind}", format_size(storage)),
+                None => format_size(storage),
+            };
+            fields.push(("Storage", storage));
+        }
+        if let Some(traffic) = self.traffic {
+            fields.push(("Traffic", traffic.to_string()));
+        }
+        if let Some(term) = self.contract_term_months {
+            fields.push(("Contract term", format!("{term} months")));
+        }
+        fields
+    }
+}
+
+/// Text of the HTML on a single line, tags are replaced by spaces
+pub fn strip_html(html: &str) -> String {
+    let text = decode_entities(&TAG.replace_all(html, " "));
+    text.split_whitespace().collect::<Vec<&str>>().join(" ")
+}
+
+/// Parses german and english number formats like `1.234,56` and `1234.56`
+fn parse_number(value: &str) -> Option<f64> {
+    let decimal = value.rfind([',', '.']).filter(|position| {
+        // A separator followed by three digits is a thousands separator
+        value.len() - position - 1 != 3
+    });
+
+    let normalized = value
+        .char_indices()
+        .filter_map(|(position, c)| match c {
+            ',' | '.' if Some(position) == decimal => Some('.'),
+            ',' | '.' => None,
+            c => Some(c),
+        })
+        .collect::<String>();
+
+    normalized.parse().ok()
+}
+
+fn to_gb(value: &str, unit: &str, factor: f64) -> Option<f64> {
+    let value = parse_number(value)?;
+    match unit.to_ascii_uppercase().as_str() {
+        "MB" => Some(value / factor),
+        "TB" => Some(value * factor),
+        _ => Some(value),
+    }
+}
+
+pub fn format_size(gb: f64) -> String {
+    if gb >= 1000.0 && (gb / 1000.0).fract() == 0.0 {
+        format!("{} TB", gb / 1000.0)
+    } else {
+        format!("{gb} GB")
+    }
+}
+
+fn parse_price(text: &str) -> (Option<Price>, Option<f64>) {
+    let mut monthly = None;
+    let mut one_time = None;
+    let mut unknown = None;
+
+    for captures in PRICE.captures_iter(text) {
+        let full = captures.get(0).unwrap();
+        let amount = match captures
+            .name("before")
+            .or_else(|| captures.name("after"))
+            .and_then(|amount| parse_number(amount.as_str()))
+        {
+            Some(amount) => amount,
+            None => continue,
+        };
+
+        let context_start = text[..full.start()]
+            .char_indices()
+            .rev()
+            .nth(PRICE_CONTEXT_LENGTH - 1)
+            .map_or(0, |(position, _)| position);
+        let before = &text[context_start..full.start()];
+        // Only look at the current sentence part
+        let before = before
+            .rfind([';', ':'])
+            .or_else(|| before.rfind(", "))
+            .map_or(before, |position| &before[position + 1..]);
+
+        if MONTHLY.is_match(&text[full.end()..]) {
+            monthly.get_or_insert(amount);
+        } else if ONE_TIME.is_match(before) {
+            one_time.get_or_insert(amount);
+        } else {
+            unknown.get_or_insert(amount);
+        }
+    }
+
+    // Netcup offers are billed monthly unless stated otherwise
+    match (monthly.or(unknown), one_time) {
+        (Some(amount), setup_fee) => (
+            Some(Price {
+                amount,
+                period: BillingPeriod::Monthly,
+            }),
+            setup_fee,
+        ),
+        (None, Some(amount)) => (
+            Some(Price {
+                amount,
+                period: BillingPeriod::OneTime,
+            }),
+            None,
+        ),
+        (None, None) => (None, None),
+    }
+}
+
+fn parse_storage(text: &str) -> Option<(f64, Option<String>)> {
+    STORAGE.iter().find_map(|regex| {
+        let captures = regex.captures(text)?;
+        let size = to_gb(&captures[1], &captures[2], 1000.0)?;
+        let kind = captures.get(3).map(|kind| {
+            let kind = kind.as_str();
+            if kind.eq_ignore_ascii_case("nvme") {
+                "NVMe".to_string()
+            } else {
+                kind.to_ascii_uppercase()
+            }
+        });
+        Some((size, kind))
+    })
+}
+
+fn parse_traffic(text: &str) -> Option<Traffic> {
+    if TRAFFIC_UNLIMITED.is_match(text) {
+        return Some(Traffic::Unlimited);
+    }
+
+    TRAFFIC
+        .iter()
+        .find_map(|regex| regex.captures(text))
+        .and_then(|captures| to_gb(&captures[1], &captures[2], 1000.0))
+        .map(|gb| Traffic::Limited { gb })
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::feed_item::FeedItem;
+    use crate::feed_parser::FeedFormat;
+
+    use super::*;
+
+    const NETCUP_DE: &[u8] = include_bytes!("../tests/fixtures/netcup_de.xml");
+    const NETCUP_EN: &[u8] = include_bytes!("../tests/fixtures/netcup_en.xml");
+
+    fn parse_fixture(content: &[u8]) -> Vec<FeedItem> {
+        FeedFormat::Rss.parse(content).unwrap()
+    }
+
+    fn monthly(amount: f64) -> Option<Price> {
+        Some(Price {
+            amount,
+            period: BillingPeriod::Monthly,
+        })
+    }
+
+    #[test]
+    fn test_parse_number() {
+        assert_eq!(parse_number("8,74"), Some(8.74));
+        assert_eq!(parse_number("8.74"), Some(8.74));
+        assert_eq!(parse_number("1.234,56"), Some(1234.56));
+        assert_eq!(parse_number("1,234.56"), Some(1234.56));
+        assert_eq!(parse_number("1.234"), Some(1234.0));
+        assert_eq!(parse_number("42"), Some(42.0));
+    }
+
+    #[test]
+    fn test_parse_price_one_time_only() {
+        let offer = Offer::parse("Domain .de für einmalig 4,99 €");
+
+        assert_eq!(
+            offer.price,
+            Some(Price {
+                amount: 4.99,
+                period: BillingPeriod::OneTime,
+            })
+        );
+        assert_eq!(offer.setup_fee, None);
+    }
+
+    #[test]
+    fn test_parse_price_without_period() {
+        let offer = Offer::parse("Preis: 5 EUR");
+
+        assert_eq!(offer.price, monthly(5.0));
+    }
+
+    #[test]
+    fn test_parse_empty() {
+        let offer = Offer::parse("<p>Nothing to see here</p>");
+
+        assert!(offer.is_empty());
+        assert!(offer.fields().is_empty());
+    }
+
+    #[test]
+    fn test_parse_ram_units() {
+        assert_eq!(Offer::parse("RAM: 512 MB").ram_gb, Some(0.5));
+        assert_eq!(Offer::parse("1 TB DDR5 RAM").ram_gb, Some(1024.0));
+    }
+
+    #[test]
+    fn test_fixture_de_root_server() {
+        let items = parse_fixture(NETCUP_DE);
+        let offer = Offer::parse(items[0].description.as_deref().unwrap());
+
+        assert_eq!(
+            offer,
+            Offer {
+                price: monthly(8.74),
+                setup_fee: Some(0.0),
+                vcores: Some(4),
+                ram_gb: Some(8.0),
+                storage_gb: Some(256.0),
+                storage_type: Some("NVMe".to_string()),
+                traffic: Some(Traffic::Unlimited),
+                contract_term_months: Some(12),
+            }
+        );
+    }
+
+    #[test]
+    fn test_fixture_de_vps() {
+        let items = parse_fixture(NETCUP_DE);
+        let offer = Offer::parse(items[1].description.as_deref().unwrap());
+
+        assert_eq!(
+            offer,
+            Offer {
+                price: monthly(13.99),
+                setup_fee: None,
+                vcores: Some(12),
+                ram_gb: Some(16.0),
+                storage_gb: Some(1000.0),
+                storage_type: Some("NVMe".to_string()),
+                traffic: Some(Traffic::Limited { gb: 120_000.0 }),
+                contract_term_months: Some(6),
+            }
+        );
+    }
+
+    #[test]
+    fn test_fixture_de_storage() {
+        let items = parse_fixture(NETCUP_DE);
+        let offer = Offer::parse(items[2].description.as_deref().unwrap());
+
+        assert_eq!(offer.price, monthly(12.5));
+        assert_eq!(offer.setup_fee, Some(29.0));
+        assert_eq!(offer.storage_gb, Some(4000.0));
+        assert_eq!(offer.storage_type, None);
+    }
+
+    #[test]
+    fn test_fixture_en_root_server() {
+        let items = parse_fixture(NETCUP_EN);
+        let offer = Offer::parse(items[0].description.as_deref().unwrap());
+
+        assert_eq!(
+            offer,
+            Offer {
+                price: monthly(8.74),
+                setup_fee: Some(0.0),
+                vcores: Some(4),
+                ram_gb: Some(8.0),
+                storage_gb: Some(256.0),
+                storage_type: Some("NVMe".to_string()),
+                traffic: Some(Traffic::Unlimited),
+                contract_term_months: Some(12),
+            }
+        );
+    }
+
+    #[test]
+    fn test_fixture_en_webhosting() {
+        let items = parse_fixture(NETCUP_EN);
+        let offer = Offer::parse(items[1].description.as_deref().unwrap());
+
+        assert_eq!(offer.price, monthly(5.0));
+        assert_eq!(offer.storage_gb, Some(250.0));
+        assert_eq!(offer.storage_type, Some("SSD".to_string()));
+        assert_eq!(offer.contract_term_months, Some(1));
+    }
+
+    #[test]
+    fn test_fields() {
+        let items = parse_fixture(NETCUP_DE);
+        let offer = Offer::parse(items[1].description.as_deref().unwrap());
+
+        assert_eq!(
+            offer.fields(),
+            vec![
+                ("Price", "13.99 € / month".to_string()),
+                ("vCores", "12".to_string()),
+                ("RAM", "16 GB".to_string()),
+                ("Storage", "1 TB NVMe".to_string()),
+                ("Traffic", "120 TB".to_string()),
+                ("Contract term", "6 months".to_string()),
+            ]
+        );
+    }
+}
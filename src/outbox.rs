@@ -0,0 +1,116 @@
+use chrono::serde::{ts_seconds, ts_seconds_option};
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::feed_item::FeedItem;
+
+// Delay after the first failed attempt, doubled for every further failure
+const BACKOFF_BASE_SECONDS: i64 = 60;
+const MAX_BACKOFF_SECONDS: i64 = 60 * 60;
+// Items that couldn't be delivered for this long are dropped
+const PENDING_EXPIRY_DAYS: i64 = 7;
+
+/// Item waiting to be delivered to a destination
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
+pub struct PendingItem {
+    pub item: FeedItem,
+    #[serde(with = "ts_seconds")]
+    pub queued: DateTime<Utc>,
+    #[serde(default)]
+    pub attempts: u32,
+    #[serde(with = "ts_seconds_option", default)]
+    pub next_attempt: Option<DateTime<Utc>>,
+}
+
+impl PendingItem {
+    pub fn new(item: FeedItem, now: DateTime<Utc>) -> Self {
+        Self {
+            item,
+            queued: now,
+            attempts: 0,
+            next_attempt: None,
+        }
+    }
+
+    pub fn is_due(&self, now: DateTime<Utc>) -> bool {
+        self.next_attempt.is_none_or(|next| next <= now)
+    }
+
+    /// Only failed items expire, items waiting for a digest are kept until it is sent
+    pub fn is_expired(&self, now: DateTime<Utc>) -> bool {
+        self.attempts > 0 && now - self.queued >= Duration::days(PENDING_EXPIRY_DAYS)
+    }
+
+    pub fn record_failure(&mut self, now: DateTime<Utc>) {
+        self.attempts += 1;
+        self.next_attempt = Some(now + backoff(self.attempts));
+    }
+}
+
+fn backoff(attempts: u32) -> Duration {
+    let exponent = attempts.saturating_sub(1).min(16);
+    let seconds = BACKOFF_BASE_SECONDS.saturating_mul(1 << exponent);
+    Duration::seconds(seconds.min(MAX_BACKOFF_SECONDS))
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::TimeZone;
+
+    use super::*;
+
+    fn create_pending(now: DateTime<Utc>) -> PendingItem {
+        PendingItem::new(
+            FeedItem {
+                title: Some("RS 1000".to_string()),
+                ..Default::default()
+            },
+            now,
+        )
+    }
+
+    #[test]
+    fn test_backoff() {
+        assert_eq!(backoff(1), Duration::minutes(1));
+        assert_eq!(backoff(2), Duration::minutes(2));
+        assert_eq!(backoff(4), Duration::minutes(8));
+        assert_eq!(backoff(7), Duration::hours(1));
+        assert_eq!(backoff(u32::MAX), Duration::hours(1));
+    }
+
+    #[test]
+    fn test_record_failure() {
+        let now = Utc::now();
+        let mut pending = create_pending(now);
+        assert!(pending.is_due(now));
+
+        pending.record_failure(now);
+
+        assert_eq!(pending.attempts, 1);
+        assert!(!pending.is_due(now));
+        assert!(pending.is_due(now + Duration::minutes(1)));
+    }
+
+    #[test]
+    fn test_is_expired() {
+        let now = Utc::now();
+        let mut pending = create_pending(now - Duration::days(PENDING_EXPIRY_DAYS));
+        assert!(!pending.is_expired(now));
+
+        pending.record_failure(now);
+        assert!(pending.is_expired(now));
+        assert!(!create_pending(now).is_expired(now));
+    }
+
+    #[test]
+    fn test_serde_round_trip() {
+        let now = Utc.timestamp_opt(1_700_000_000, 0).unwrap();
+        let mut pending = create_pending(now);
+        pending.record_failure(now);
+
+        let json = serde_json::to_string(&pending).unwrap();
+        let parsed: PendingItem = serde_json::from_str(&json).unwrap();
+
+        assert_eq!(parsed, pending);
+    }
+}
@@ -0,0 +1,222 @@
+use std::str::FromStr;
+
+use chrono::{DateTime, Utc};
+use chrono_tz::Tz;
+use cron::Schedule;
+use serde::Deserialize;
+
+use crate::error::Error;
+
+/// Raw digest settings as found in the config
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct DigestConfig {
+    /// Cron expression with optional seconds, e.g. `0 9 * * *` or `@daily`.
+    /// Days of the week are numbered like standard cron, 0 and 7 are Sunday
+    pub schedule: String,
+    /// IANA time zone of the schedule [Default: UTC]
+    pub timezone: Option<String>,
+}
+
+/// Times at which the collected items of a destination are sent as one summary
+#[derive(Debug, Clone)]
+pub struct DigestSchedule {
+    schedule: Schedule,
+    timezone: Tz,
+}
+
+const WEEKDAYS: [&str; 8] = ["SUN", "MON", "TUE", "WED", "THU", "FRI", "SAT", "SUN"];
+
+/// Standard cron numbers the days of the week from 0 (or 7) for Sunday, the cron crate from 1.
+/// Numbers are replaced by names, which both understand the same way
+fn translate_weekdays(field: &str) -> Result<String, String> {
+    let day = |value: &str| match value.parse::<usize>() {
+        Ok(number) => WEEKDAYS
+            .get(number)
+            .map(|day| day.to_string())
+            .ok_or_else(|| format!("day of the week {number} is not between 0 and 7")),
+        Err(_) => Ok(value.to_string()),
+    };
+
+    let mut parts = Vec::new();
+    for part in field.split(',') {
+        let (range, step) = match part.split_once('/') {
+            Some((range, step)) => (range, Some(step)),
+            None => (part, None),
+        };
+        let range = match range.split_once('-') {
+            Some(("0", "7")) => "SUN-SAT".to_string(),
+            // Sunday as 7 ends the week, the cron crate can't wrap around from Saturday
+            Some((start, "7")) if start != "0" => match step {
+                None => format!("{}-SAT,SUN", day(start)?),
+                Some(_) => return Err("ranges to 7 with a step are not supported".to_string()),
+            },
+            Some((start, end)) => format!("{}-{}", day(start)?, day(end)?),
+            None => day(range)?,
+        };
+        parts.push(match step {
+            Some(step) => format!("{range}/{step}"),
+            None => range,
+        });
+    }
+    Ok(parts.join(","))
+}
+
+impl TryFrom<DigestConfig> for DigestSchedule {
+    type Error = Error;
+
+    fn try_from(config: DigestConfig) -> Result<Self, Self::Error> {
+        let expression = config.schedule.trim();
+        let mut fields = expression.split_whitespace().collect::<Vec<&str>>();
+        // The cron crate requires seconds, standard 5 field expressions start at the full minute
+        if fields.len() == 5 {
+            fields.insert(0, "0");
+        }
+        // The day of the week follows the seconds, an optional year comes after it
+        let expression = if fields.len() >= 6 {
+            let weekdays = translate_weekdays(fields[5]).map_err(|e| {
+                Error::ConfigVar(format!("Invalid digest schedule {}: {e}", config.schedule))
+            })?;
+            fields[5] = &weekdays;
+            fields.join(" ")
+        } else {
+            expression.to_string()
+        };
+
+        let schedule = Schedule::from_str(&expression).map_err(|e| {
+            Error::ConfigVar(format!("Invalid digest schedule {}: {e}", config.schedule))
+        })?;
+        let timezone = match config.timezone {
+            Some(timezone) => timezone.parse::<Tz>().map_err(|e| {
+                Error::ConfigVar(format!("Invalid digest timezone {timezone}: {e}"))
+            })?,
+            None => Tz::UTC,
+        };
+
+        let digest = Self { schedule, timezone };
+        if digest.next_after(Utc::now()).is_none() {
+            return Err(Error::ConfigVar(format!(
+                "Digest schedule {} never fires",
+                config.schedule
+            )));
+        }
+
+        Ok(digest)
+    }
+}
+
+impl DigestSchedule {
+    pub fn next_after(&self, now: DateTime<Utc>) -> Option<DateTime<Utc>> {
+        self.schedule
+            .after(&now.with_timezone(&self.timezone))
+            .next()
+            .map(|next| next.with_timezone(&Utc))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::TimeZone;
+
+    use super::*;
+
+    fn create_schedule(schedule: &str, timezone: Option<&str>) -> crate::Result<DigestSchedule> {
+        DigestSchedule::try_from(DigestConfig {
+            schedule: schedule.to_string(),
+            timezone: timezone.map(str::to_string),
+        })
+    }
+
+    #[test]
+    fn test_next_after_timezone() {
+        let schedule = create_schedule("0 9 * * *", Some("Europe/Berlin")).unwrap();
+        let now = Utc.with_ymd_and_hms(2025, 1, 14, 10, 0, 0).unwrap();
+
+        // 09:00 CET is 08:00 UTC, today's run already passed
+        assert_eq!(
+            schedule.next_after(now),
+            Some(Utc.with_ymd_and_hms(2025, 1, 15, 8, 0, 0).unwrap())
+        );
+    }
+
+    #[test]
+    fn test_next_after_with_seconds() {
+        let schedule = create_schedule("30 0 * * * *", None).unwrap();
+        let now = Utc.with_ymd_and_hms(2025, 1, 14, 10, 0, 0).unwrap();
+
+        assert_eq!(
+            schedule.next_after(now),
+            Some(Utc.with_ymd_and_hms(2025, 1, 14, 10, 0, 30).unwrap())
+        );
+    }
+
+    #[test]
+    fn test_standard_weekdays() {
+        // Saturday
+        let now = Utc.with_ymd_and_hms(2025, 1, 18, 10, 0, 0).unwrap();
+        let sunday = Utc.with_ymd_and_hms(2025, 1, 19, 9, 0, 0).unwrap();
+        let monday = Utc.with_ymd_and_hms(2025, 1, 20, 9, 0, 0).unwrap();
+
+        let weekdays = create_schedule("0 9 * * 1-5", None).unwrap();
+        assert_eq!(weekdays.next_after(now), Some(monday));
+
+        for expression in ["0 9 * * 0", "0 9 * * 7", "0 9 * * 5-7", "0 9 * * SUN"] {
+            let schedule = create_schedule(expression, None).unwrap();
+            assert_eq!(schedule.next_after(now), Some(sunday), "{expression}");
+        }
+        assert_eq!(
+            create_schedule("0 9 * * 1,3", None)
+                .unwrap()
+                .next_after(now),
+            Some(monday)
+        );
+        assert!(create_schedule("0 9 * * 8", None).is_err());
+
+        // With seconds and year the days are numbered the same way
+        for expression in ["0 0 9 * * 1", "0 0 9 * * 1 *", "0 0 9 * * MON"] {
+            let schedule = create_schedule(expression, None).unwrap();
+            assert_eq!(schedule.next_after(now), Some(monday), "{expression}");
+        }
+        assert_eq!(
+            create_schedule("0 0 9 * * 0", None)
+                .unwrap()
+                .next_after(now),
+            Some(sunday)
+        );
+
+        // The whole week, also from the middle of it
+        let tuesday = Utc.with_ymd_and_hms(2025, 1, 14, 10, 0, 0).unwrap();
+        let wednesday = Utc.with_ymd_and_hms(2025, 1, 15, 9, 0, 0).unwrap();
+        for expression in ["0 9 * * 0-7", "0 0 9 * * 0-7"] {
+            let schedule = create_schedule(expression, None).unwrap();
+            assert_eq!(
+                schedule.next_after(tuesday),
+                Some(wednesday),
+                "{expression}"
+            );
+        }
+        assert_eq!(
+            create_schedule("0 9 * * 0-7/2", None)
+                .unwrap()
+                .next_after(tuesday),
+            Some(Utc.with_ymd_and_hms(2025, 1, 16, 9, 0, 0).unwrap())
+        );
+    }
+
+    #[test]
+    fn test_alias() {
+        let schedule = create_schedule("@hourly", None).unwrap();
+        let now = Utc.with_ymd_and_hms(2025, 1, 14, 10, 15, 0).unwrap();
+
+        assert_eq!(
+            schedule.next_after(now),
+            Some(Utc.with_ymd_and_hms(2025, 1, 14, 11, 0, 0).unwrap())
+        );
+    }
+
+    #[test]
+    fn test_invalid() {
+        assert!(create_schedule("every day", None).is_err());
+        assert!(create_schedule("0 9 * * *", Some("Mars/Olympus")).is_err());
+    }
+}
@@ -0,0 +1,255 @@
+use std::path::{Path, PathBuf};
+
+use async_trait::async_trait;
+use tokio::io::AsyncWriteExt;
+use tokio::sync::Mutex;
+
+use crate::error::Error;
+use crate::feed_state::{self, FeedState, FeedStates};
+use crate::state_store::StateStore;
+use crate::Result;
+
+/// States of all feeds in one JSON file.
+/// Writes go to a temporary file that replaces the state file, the previous state is kept as backup
+#[derive(Debug)]
+pub struct JsonFileStore {
+    file: PathBuf,
+    /// Updates read and rewrite the file
+    lock: Mutex<()>,
+    /// Nothing is written, not even a migrated or recovered state
+    read_only: bool,
+}
+
+fn with_extension(file: &Path, extension: &str) -> PathBuf {
+    let mut name = file.as_os_str().to_owned();
+    name.push(".");
+    name.push(extension);
+    PathBuf::from(name)
+}
+
+/// Replaces the file atomically, a crash leaves either the old or the new content.
+/// The previous content is kept with a `.bak` extension
+pub(crate) async fn write_atomic(file: &Path, content: &str) -> Result<()> {
+    // Ensure that the path exists
+    let directory = match file.parent() {
+        Some(prefix) if !prefix.as_os_str().is_empty() => prefix.to_path_buf(),
+        _ => PathBuf::from("."),
+    };
+    tokio::fs::create_dir_all(&directory).await?;
+
+    let temporary = with_extension(file, "tmp");
+    let mut writer = tokio::fs::File::create(&temporary).await?;
+    writer.write_all(content.as_bytes()).await?;
+    writer.sync_all().await?;
+    drop(writer);
+
+    if file.exists() {
+        tokio::fs::copy(file, with_extension(file, "bak")).await?;
+    }
+    tokio::fs::rename(&temporary, file).await?;
+
+    // Persists the rename, not supported on every platform
+    if let Ok(directory) = tokio::fs::File::open(&directory).await {
+        let _ = directory.sync_all().await;
+    }
+
+    Ok(())
+}
+
+impl JsonFileStore {
+    pub fn new(file: impl AsRef<Path>) -> Self {
+        Self {
+            file: file.as_ref().to_path_buf(),
+            lock: Mutex::new(()),
+            read_only: false,
+        }
+    }
+
+    pub fn read_only(file: impl AsRef<Path>) -> Self {
+        Self {
+            read_only: true,
+            ..Self::new(file)
+        }
+    }
+
+    fn backup_file(&self) -> PathBuf {
+        with_extension(&self.file, "bak")
+    }
+
+    async fn read(file: &Path) -> Result<serde_json::Value> {
+        let content = tokio::fs::read_to_string(file).await?;
+        Ok(serde_json::from_str(&content)?)
+    }
+
+    /// Content of the state file, restored from the backup if the file is corrupt
+    async fn read_or_recover(&self) -> Result<Option<serde_json::Value>> {
+        if !self.file.exists() {
+            return Ok(None);
+        }
+
+        let error = match Self::read(&self.file).await {
+            Ok(content) => return Ok(Some(content)),
+            Err(e) => e,
+        };
+        error!("Feed state file is corrupt, restoring the backup: {error}");
+
+        let backup = self.backup_file();
+        let content = Self::read(&backup).await.map_err(|e| {
+            Error::custom(format!(
+                "Feed state file {} is corrupt ({error}) and the backup can't be used: {e}",
+                self.file.display()
+            ))
+        })?;
+        if self.read_only {
+            return Ok(Some(content));
+        }
+
+        // Kept for inspection, the backup must not be replaced by the corrupt file on the next write
+        let corrupt = with_extension(&self.file, "corrupt");
+        tokio::fs::rename(&self.file, &corrupt).await?;
+        warn!("Moved the corrupt feed state to {}", corrupt.display());
+        self.write(serde_json::to_string_pretty(&content)?).await?;
+
+        Ok(Some(content))
+    }
+
+    /// Content of the state file upgraded to the current version, see [`feed_state::migrate`]
+    async fn read_current(&self) -> Result<Option<serde_json::Value>> {
+        let Some(mut content) = self.read_or_recover().await? else {
+            return Ok(None);
+        };
+
+        if feed_state::migrate(&mut content)? && !self.read_only {
+            // The previous version is kept as backup
+            info!("Writing the migrated feed state");
+            self.write(serde_json::to_string_pretty(&content)?).await?;
+        }
+        Ok(Some(content))
+    }
+
+    /// See [`write_atomic`]
+    async fn write(&self, content: String) -> Result<()> {
+        if self.read_only {
+            return Err(Error::custom(format!(
+                "Feed state file {} is opened read only",
+                self.file.display()
+            )));
+        }
+
+        write_atomic(&self.file, &content).await
+    }
+}
+
+#[async_trait]
+impl StateStore for JsonFileStore {
+    #[tracing::instrument]
+    async fn load(&self) -> Result<FeedStates> {
+        let _lock = self.lock.lock().await;
+        info!("Loading feed state from file");
+        match self.read_current().await? {
+            Some(content) => Ok(serde_json::from_value(content)?),
+            None => Ok(FeedStates::default()),
+        }
+    }
+
+    #[tracing::instrument(skip(states))]
+    async fn save(&self, states: &FeedStates) -> Result<()> {
+        let _lock = self.lock.lock().await;
+        self.write(serde_json::to_string_pretty(states)?).await
+    }
+
+    #[tracing::instrument(skip(feeds))]
+    async fn update(&self, feeds: &[(&str, &FeedState)]) -> Result<()> {
+        let _lock = self.lock.lock().await;
+        let mut content = match self.read_current().await? {
+            Some(content) => content,
+            None => serde_json::to_value(FeedStates::default())?,
+        };
+        for (feed_id, state) in feeds {
+            content["feeds"][*feed_id] = serde_json::to_value(state)?;
+        }
+        self.write(serde_json::to_string_pretty(&content)?).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::state_store::tests::create_states;
+
+    use super::*;
+
+    fn create_store(dir: &tempfile::TempDir) -> JsonFileStore {
+        JsonFileStore::new(dir.path().join("feed_state.json"))
+    }
+
+    #[tokio::test]
+    async fn test_write_keeps_backup() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = create_store(&dir);
+
+        store.save(&FeedStates::default()).await.unwrap();
+        assert!(!store.backup_file().exists());
+
+        store.save(&create_states()).await.unwrap();
+        assert!(!with_extension(&store.file, "tmp").exists());
+        assert_eq!(
+            serde_json::from_str::<FeedStates>(
+                &std::fs::read_to_string(store.backup_file()).unwrap()
+            )
+            .unwrap(),
+            FeedStates::default()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_recover_from_backup() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = create_store(&dir);
+        store.save(&create_states()).await.unwrap();
+        store.save(&create_states()).await.unwrap();
+
+        // Truncated by a crash during a write without the temporary file
+        std::fs::write(&store.file, r#"{"feeds":{"netcup":{"last_upd"#).unwrap();
+
+        assert_eq!(store.load().await.unwrap(), create_states());
+        assert!(with_extension(&store.file, "corrupt").exists());
+        // The state file is valid again
+        assert_eq!(
+            serde_json::from_str::<FeedStates>(&std::fs::read_to_string(&store.file).unwrap())
+                .unwrap(),
+            create_states()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_read_only() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = create_store(&dir);
+        store.save(&create_states()).await.unwrap();
+        store.save(&create_states()).await.unwrap();
+        std::fs::write(&store.file, "").unwrap();
+
+        let read_only = JsonFileStore::read_only(&store.file);
+        assert_eq!(read_only.load().await.unwrap(), create_states());
+        assert!(read_only.save(&create_states()).await.is_err());
+        // The corrupt file is neither moved nor replaced
+        assert_eq!(std::fs::read_to_string(&store.file).unwrap(), "");
+        assert!(!with_extension(&store.file, "corrupt").exists());
+    }
+
+    #[tokio::test]
+    async fn test_corrupt_without_backup() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = create_store(&dir);
+        std::fs::write(&store.file, "").unwrap();
+
+        assert!(store.load().await.is_err());
+        assert!(store
+            .update(&[("netcup", &FeedState::default())])
+            .await
+            .is_err());
+        // Nothing is overwritten
+        assert!(store.file.exists());
+        assert!(!store.backup_file().exists());
+    }
+}
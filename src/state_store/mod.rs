@@ -0,0 +1,137 @@
+use std::fmt::Debug;
+
+use async_trait::async_trait;
+use secrecy::ExposeSecret;
+
+use crate::config::StateConfig;
+use crate::feed_state::{self, FeedState, FeedStates, STATE_VERSION};
+use crate::Result;
+
+pub mod json;
+pub mod redis;
+pub mod sqlite;
+
+/// Default file names in the data directory
+pub const FEED_STATE_FILE: &str = "feed_state.json";
+pub const FEED_STATE_DATABASE: &str = "feed_state.db";
+
+/// Storage of the feed states
+#[async_trait]
+pub trait StateStore: Debug + Send + Sync {
+    /// Stored states, empty if nothing was stored yet
+    async fn load(&self) -> Result<FeedStates>;
+
+    /// Replaces the stored states
+    async fn save(&self, states: &FeedStates) -> Result<()>;
+
+    /// Stores the states of the given feeds in one write, the other feeds are kept
+    async fn update(&self, feeds: &[(&str, &FeedState)]) -> Result<()>;
+}
+
+/// States of the stores with a JSON encoded state per feed, upgraded like the JSON file, see [`feed_state::migrate`].
+/// Returns whether an older version was migrated and has to be written back
+fn migrate_feeds(
+    version: Option<u64>,
+    feeds: impl IntoIterator<Item = (String, String)>,
+) -> Result<(FeedStates, bool)> {
+    let mut states = serde_json::Map::new();
+    for (feed_id, state) in feeds {
+        states.insert(feed_id, serde_json::from_str(&state)?);
+    }
+
+    // Nothing stored yet, the version is written with the first save
+    let version = match version {
+        None if states.is_empty() => Some(STATE_VERSION),
+        version => version,
+    };
+    let mut content = serde_json::json!({ "feeds": states });
+    if let Some(version) = version {
+        content["version"] = version.into();
+    }
+
+    let migrated = feed_state::migrate(&mut content)?;
+    Ok((serde_json::from_value(content)?, migrated))
+}
+
+pub fn from_config(config: &StateConfig) -> Result<Box<dyn StateStore>> {
+    Ok(match config {
+        StateConfig::Json { path } => Box::new(json::JsonFileStore::new(path)),
+        StateConfig::Sqlite { path } => Box::new(sqlite::SqliteStore::open(path)?),
+        StateConfig::Redis { url, key } => {
+            Box::new(redis::RedisStore::open(url.expose_secret(), key)?)
+        }
+    })
+}
+
+/// Store for commands that must not change anything, older formats are only migrated in memory
+pub fn open_read_only(config: &StateConfig) -> Result<Box<dyn StateStore>> {
+    Ok(match config {
+        StateConfig::Json { path } => Box::new(json::JsonFileStore::read_only(path)),
+        StateConfig::Sqlite { path } => Box::new(sqlite::SqliteStore::open_read_only(path)?),
+        StateConfig::Redis { url, key } => {
+            Box::new(redis::RedisStore::open_read_only(url.expose_secret(), key)?)
+        }
+    })
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+
+    pub fn create_states() -> FeedStates {
+        serde_json::from_value(serde_json::json!({
+            "feeds": {
+                "netcup": {"last_update": 1736848800},
+                "other": {"last_update": null}
+            }
+        }))
+        .unwrap()
+    }
+
+    /// Behaviour every store has to implement
+    pub async fn test_store(store: &dyn StateStore) {
+        assert_eq!(store.load().await.unwrap(), FeedStates::default());
+
+        // The version is stored with the first update, the feed isn't migrated again
+        let mut fresh = FeedState::default();
+        fresh.set_last_update(chrono::DateTime::from_timestamp(1736848800, 0).unwrap());
+        store.update(&[("Netcup", &fresh)]).await.unwrap();
+        let loaded = store.load().await.unwrap();
+        assert_eq!(
+            loaded.feeds().keys().collect::<Vec<&String>>(),
+            vec!["Netcup"]
+        );
+        assert_eq!(loaded.feeds()["Netcup"].last_update(), fresh.last_update());
+
+        let states = create_states();
+        store.save(&states).await.unwrap();
+        assert_eq!(store.load().await.unwrap(), states);
+
+        let mut updated = FeedState::default();
+        updated.set_last_update(chrono::DateTime::from_timestamp(1736852400, 0).unwrap());
+        store.update(&[("netcup", &updated)]).await.unwrap();
+        let loaded = store.load().await.unwrap();
+        assert_eq!(
+            loaded.feeds()["netcup"].last_update(),
+            updated.last_update()
+        );
+        assert!(loaded.feeds().contains_key("other"));
+
+        // Saving replaces all feeds
+        let feeds: FeedStates =
+            serde_json::from_value(serde_json::json!({"feeds": {"other": {"last_update": null}}}))
+                .unwrap();
+        store.save(&feeds).await.unwrap();
+        assert_eq!(store.load().await.unwrap(), feeds);
+    }
+
+    #[tokio::test]
+    async fn test_json_store() {
+        let dir = tempfile::tempdir().unwrap();
+
+        test_store(&json::JsonFileStore::new(
+            dir.path().join("feed_state.json"),
+        ))
+        .await;
+    }
+}
@@ -0,0 +1,311 @@
+use std::collections::HashMap;
+
+use async_trait::async_trait;
+use redis::aio::MultiplexedConnection;
+
+use crate::feed_state::{FeedState, FeedStates, STATE_VERSION};
+use crate::state_store::{migrate_feeds, StateStore};
+use crate::Result;
+
+pub const DEFAULT_KEY: &str = "netcup-offer-bot:feed_states";
+
+/// One hash with a JSON encoded field per feed, the format version is stored in `<key>:version`
+pub struct RedisStore {
+    client: redis::Client,
+    key: String,
+    /// Older states are only migrated in memory
+    read_only: bool,
+}
+
+// The connection info contains the password
+impl std::fmt::Debug for RedisStore {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("RedisStore")
+            .field("key", &self.key)
+            .field("read_only", &self.read_only)
+            .finish()
+    }
+}
+
+impl RedisStore {
+    pub fn open(url: &str, key: &str) -> Result<Self> {
+        Ok(Self {
+            client: redis::Client::open(url)?,
+            key: key.to_string(),
+            read_only: false,
+        })
+    }
+
+    pub fn open_read_only(url: &str, key: &str) -> Result<Self> {
+        Ok(Self {
+            read_only: true,
+            ..Self::open(url, key)?
+        })
+    }
+
+    fn check_writable(&self) -> Result<()> {
+        if self.read_only {
+            return Err(crate::Error::custom(format!(
+                "Feed state key {} is opened read only",
+                self.key
+            )));
+        }
+        Ok(())
+    }
+
+    fn version_key(&self) -> String {
+        format!("{}:version", self.key)
+    }
+
+    async fn connection(&self) -> Result<MultiplexedConnection> {
+        Ok(self.client.get_multiplexed_async_connection().await?)
+    }
+}
+
+#[async_trait]
+impl StateStore for RedisStore {
+    #[tracing::instrument]
+    async fn load(&self) -> Result<FeedStates> {
+        let (fields, version): (HashMap<String, String>, Option<u64>) = redis::pipe()
+            .hgetall(&self.key)
+            .get(self.version_key())
+            .query_async(&mut self.connection().await?)
+            .await?;
+
+        let (states, migrated) = migrate_feeds(version, fields)?;
+        if migrated && !self.read_only {
+            info!("Writing the migrated feed state");
+            self.save(&states).await?;
+        }
+        Ok(states)
+    }
+
+    #[tracing::instrument(skip(states))]
+    async fn save(&self, states: &FeedStates) -> Result<()> {
+        self.check_writable()?;
+        let mut fields = Vec::with_capacity(states.feeds().len());
+        for (feed_id, state) in states.feeds() {
+            fields.push((feed_id.as_str(), serde_json::to_string(state)?));
+        }
+
+        // Written to a temporary key first, the rename replaces the states at once
+        let temporary = format!("{}:tmp", self.key);
+        let mut pipe = redis::pipe();
+        pipe.del(&temporary).ignore();
+        if fields.is_empty() {
+            pipe.del(&self.key).ignore();
+        } else {
+            pipe.hset_multiple(&temporary, &fields)
+                .ignore()
+                .rename(&temporary, &self.key)
+                .ignore();
+        }
+        pipe.set(self.version_key(), STATE_VERSION).ignore();
+        pipe.query_async::<()>(&mut self.connection().await?)
+            .await?;
+        Ok(())
+    }
+
+    #[tracing::instrument(skip(feeds))]
+    async fn update(&self, feeds: &[(&str, &FeedState)]) -> Result<()> {
+        self.check_writable()?;
+        if feeds.is_empty() {
+            return Ok(());
+        }
+
+        let mut fields = Vec::with_capacity(feeds.len());
+        for (feed_id, state) in feeds {
+            fields.push((*feed_id, serde_json::to_string(state)?));
+        }
+        // A fresh key gets its version with the first update
+        redis::pipe()
+            .hset_multiple(&self.key, &fields)
+            .ignore()
+            .set(self.version_key(), STATE_VERSION)
+            .ignore()
+            .query_async::<()>(&mut self.connection().await?)
+            .await?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{Arc, Mutex};
+
+    use tokio::io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader};
+    use tokio::net::{TcpListener, TcpStream};
+
+    use crate::state_store::tests::test_store;
+
+    use super::*;
+
+    #[derive(Debug, Default)]
+    struct Keys {
+        hashes: HashMap<String, HashMap<String, String>>,
+        strings: HashMap<String, String>,
+    }
+
+    type Shared = Arc<Mutex<Keys>>;
+
+    /// Reads a command sent as RESP array of bulk strings
+    async fn read_command(reader: &mut BufReader<TcpStream>) -> Option<Vec<String>> {
+        async fn read_line(reader: &mut BufReader<TcpStream>) -> Option<String> {
+            let mut line = String::new();
+            match reader.read_line(&mut line).await {
+                Ok(0) | Err(_) => None,
+                Ok(_) => Some(line.trim_end().to_string()),
+            }
+        }
+
+        let count = read_line(reader).await?.strip_prefix('*')?.parse().ok()?;
+        let mut command = Vec::with_capacity(count);
+        for _ in 0..count {
+            let length: usize = read_line(reader).await?.strip_prefix('$')?.parse().ok()?;
+            let mut value = vec![0; length + 2];
+            reader.read_exact(&mut value).await.ok()?;
+            value.truncate(length);
+            command.push(String::from_utf8(value).ok()?);
+        }
+        Some(command)
+    }
+
+    fn bulk(value: &str) -> String {
+        format!("${}\r\n{value}\r\n", value.len())
+    }
+
+    fn execute(keys: &Shared, command: &[String]) -> String {
+        let mut keys = keys.lock().unwrap();
+        let Keys { hashes, strings } = &mut *keys;
+        match command[0].to_uppercase().as_str() {
+            "HGETALL" => {
+                let hash = hashes.get(&command[1]).cloned().unwrap_or_default();
+                let mut reply = format!("*{}\r\n", hash.len() * 2);
+                for (field, value) in hash {
+                    reply.push_str(&bulk(&field));
+                    reply.push_str(&bulk(&value));
+                }
+                reply
+            }
+            name @ ("HSET" | "HMSET") => {
+                let hash = hashes.entry(command[1].clone()).or_default();
+                let mut added = 0;
+                for pair in command[2..].chunks(2) {
+                    if hash.insert(pair[0].clone(), pair[1].clone()).is_none() {
+                        added += 1;
+                    }
+                }
+                match name {
+                    "HSET" => format!(":{added}\r\n"),
+                    _ => "+OK\r\n".to_string(),
+                }
+            }
+            "GET" => match strings.get(&command[1]) {
+                Some(value) => bulk(value),
+                None => "$-1\r\n".to_string(),
+            },
+            "SET" => {
+                strings.insert(command[1].clone(), command[2].clone());
+                "+OK\r\n".to_string()
+            }
+            "DEL" => {
+                let removed = command[1..]
+                    .iter()
+                    .filter(|key| hashes.remove(*key).is_some() || strings.remove(*key).is_some())
+                    .count();
+                format!(":{removed}\r\n")
+            }
+            "RENAME" => match hashes.remove(&command[1]) {
+                Some(hash) => {
+                    hashes.insert(command[2].clone(), hash);
+                    "+OK\r\n".to_string()
+                }
+                None => "-ERR no such key\r\n".to_string(),
+            },
+            // Connection setup like CLIENT SETINFO
+            _ => "+OK\r\n".to_string(),
+        }
+    }
+
+    /// In-process fake of the Redis commands used by the store
+    async fn start_fake_redis() -> (String, Shared) {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = listener.local_addr().unwrap();
+        let keys = Shared::default();
+
+        let shared = keys.clone();
+        tokio::spawn(async move {
+            while let Ok((stream, _)) = listener.accept().await {
+                let keys = shared.clone();
+                tokio::spawn(async move {
+                    let mut reader = BufReader::new(stream);
+                    while let Some(command) = read_command(&mut reader).await {
+                        let reply = execute(&keys, &command);
+                        if reader.get_mut().write_all(reply.as_bytes()).await.is_err() {
+                            break;
+                        }
+                    }
+                });
+            }
+        });
+
+        (format!("redis://{address}"), keys)
+    }
+
+    #[tokio::test]
+    async fn test_redis_store() {
+        let (url, keys) = start_fake_redis().await;
+        let store = RedisStore::open(&url, DEFAULT_KEY).unwrap();
+
+        test_store(&store).await;
+
+        let keys = keys.lock().unwrap();
+        assert_eq!(
+            keys.hashes.keys().collect::<Vec<&String>>(),
+            vec![DEFAULT_KEY]
+        );
+        assert_eq!(
+            keys.strings[&store.version_key()],
+            STATE_VERSION.to_string()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_redis_store_migrates() {
+        let (url, keys) = start_fake_redis().await;
+        let legacy = HashMap::from([(
+            "Netcup".to_string(),
+            r#"{"last_update": 1736848800}"#.to_string(),
+        )]);
+        keys.lock()
+            .unwrap()
+            .hashes
+            .insert(DEFAULT_KEY.to_string(), legacy.clone());
+
+        // Read only stores migrate in memory
+        let read_only = RedisStore::open_read_only(&url, DEFAULT_KEY).unwrap();
+        let states = read_only.load().await.unwrap();
+        assert!(states.feeds().contains_key("netcup"));
+        assert!(read_only.save(&states).await.is_err());
+        assert_eq!(keys.lock().unwrap().hashes[DEFAULT_KEY], legacy);
+
+        let store = RedisStore::open(&url, DEFAULT_KEY).unwrap();
+        assert_eq!(store.load().await.unwrap(), states);
+        let keys = keys.lock().unwrap();
+        assert_eq!(
+            keys.hashes[DEFAULT_KEY].keys().collect::<Vec<&String>>(),
+            vec!["netcup"]
+        );
+        assert_eq!(
+            keys.strings[&store.version_key()],
+            STATE_VERSION.to_string()
+        );
+    }
+
+    #[test]
+    fn test_debug_hides_password() {
+        let store = RedisStore::open("redis://:secret@localhost:6379", DEFAULT_KEY).unwrap();
+
+        assert!(!format!("{store:?}").contains("secret"));
+    }
+}
@@ -0,0 +1,241 @@
+use std::path::Path;
+use std::sync::Mutex;
+
+use async_trait::async_trait;
+use rusqlite::{params, Connection, OpenFlags, OptionalExtension};
+
+use crate::feed_state::{FeedState, FeedStates, STATE_VERSION};
+use crate::history::migrate;
+use crate::state_store::{migrate_feeds, StateStore};
+use crate::Result;
+
+const MIGRATIONS: [&str; 2] = [
+    "CREATE TABLE feed_states (
+        feed_id TEXT PRIMARY KEY,
+        state TEXT NOT NULL
+    );",
+    // Format version of the states, see `feed_state::migrate`
+    "CREATE TABLE state_version (
+        id INTEGER PRIMARY KEY CHECK (id = 0),
+        version INTEGER NOT NULL
+    );",
+];
+
+/// One JSON encoded row per feed
+#[derive(Debug)]
+pub struct SqliteStore {
+    connection: Mutex<Connection>,
+    /// Older states are only migrated in memory
+    read_only: bool,
+}
+
+impl SqliteStore {
+    #[tracing::instrument]
+    pub fn open(file: &Path) -> Result<Self> {
+        if let Some(prefix) = file.parent() {
+            std::fs::create_dir_all(prefix)?;
+        }
+        Self::from_connection(Connection::open(file)?)
+    }
+
+    /// Opens the database without creating or migrating it, a missing database is empty
+    #[tracing::instrument]
+    pub fn open_read_only(file: &Path) -> Result<Self> {
+        if !file.exists() {
+            return Self::from_connection(Connection::open_in_memory()?);
+        }
+
+        let connection = Connection::open_with_flags(file, OpenFlags::SQLITE_OPEN_READ_ONLY)?;
+        let version: usize = connection.query_row("PRAGMA user_version", [], |row| row.get(0))?;
+        if version == 0 || version > MIGRATIONS.len() {
+            return Err(crate::Error::custom(format!(
+                "Feed state database has schema version {version}, expected 1 to {}",
+                MIGRATIONS.len()
+            )));
+        }
+        Ok(Self {
+            connection: Mutex::new(connection),
+            read_only: true,
+        })
+    }
+
+    fn from_connection(mut connection: Connection) -> Result<Self> {
+        migrate(&mut connection, "Feed state database", &MIGRATIONS)?;
+        Ok(Self {
+            connection: Mutex::new(connection),
+            read_only: false,
+        })
+    }
+
+    /// Stored format version, missing before it was stored
+    fn state_version(connection: &Connection) -> Result<Option<u64>> {
+        // Read only databases can have a schema from before the version table
+        let exists: bool = connection.query_row(
+            "SELECT EXISTS (SELECT 1 FROM sqlite_master WHERE type = 'table' AND name = 'state_version')",
+            [],
+            |row| row.get(0),
+        )?;
+        if !exists {
+            return Ok(None);
+        }
+
+        Ok(connection
+            .query_row("SELECT version FROM state_version", [], |row| row.get(0))
+            .optional()?)
+    }
+
+    fn write(connection: &mut Connection, states: &FeedStates) -> Result<()> {
+        let transaction = connection.transaction()?;
+        transaction.execute("DELETE FROM feed_states", [])?;
+        for (feed_id, state) in states.feeds() {
+            transaction.execute(
+                "INSERT INTO feed_states (feed_id, state) VALUES (?1, ?2)",
+                params![feed_id, serde_json::to_string(state)?],
+            )?;
+        }
+        transaction.execute(
+            "INSERT INTO state_version (id, version) VALUES (0, ?1)
+            ON CONFLICT (id) DO UPDATE SET version = excluded.version",
+            params![STATE_VERSION],
+        )?;
+        transaction.commit()?;
+        Ok(())
+    }
+
+    fn connection(&self) -> std::sync::MutexGuard<'_, Connection> {
+        self.connection
+            .lock()
+            .expect("Feed state database lock is poisoned")
+    }
+}
+
+#[async_trait]
+impl StateStore for SqliteStore {
+    #[tracing::instrument]
+    async fn load(&self) -> Result<FeedStates> {
+        let mut connection = self.connection();
+        let version = Self::state_version(&connection)?;
+        let feeds = {
+            let mut statement = connection.prepare("SELECT feed_id, state FROM feed_states")?;
+            let rows = statement.query_map([], |row| {
+                Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?))
+            })?;
+            rows.collect::<rusqlite::Result<Vec<(String, String)>>>()?
+        };
+
+        let (states, migrated) = migrate_feeds(version, feeds)?;
+        if migrated && !self.read_only {
+            info!("Writing the migrated feed state");
+            Self::write(&mut connection, &states)?;
+        }
+        Ok(states)
+    }
+
+    #[tracing::instrument(skip(states))]
+    async fn save(&self, states: &FeedStates) -> Result<()> {
+        Self::write(&mut self.connection(), states)
+    }
+
+    #[tracing::instrument(skip(feeds))]
+    async fn update(&self, feeds: &[(&str, &FeedState)]) -> Result<()> {
+        let mut connection = self.connection();
+        let transaction = connection.transaction()?;
+        for (feed_id, state) in feeds {
+            transaction.execute(
+                "INSERT INTO feed_states (feed_id, state) VALUES (?1, ?2)
+                ON CONFLICT (feed_id) DO UPDATE SET state = excluded.state",
+                params![feed_id, serde_json::to_string(state)?],
+            )?;
+        }
+        // A fresh database gets its version with the first update
+        transaction.execute(
+            "INSERT INTO state_version (id, version) VALUES (0, ?1)
+            ON CONFLICT (id) DO UPDATE SET version = excluded.version",
+            params![STATE_VERSION],
+        )?;
+        transaction.commit()?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::state_store::tests::{create_states, test_store};
+
+    use super::*;
+
+    #[tokio::test]
+    async fn test_sqlite_store() {
+        let store = SqliteStore::from_connection(Connection::open_in_memory().unwrap()).unwrap();
+
+        test_store(&store).await;
+    }
+
+    #[tokio::test]
+    async fn test_sqlite_store_reopen() {
+        let dir = tempfile::tempdir().unwrap();
+        let file = dir.path().join("state").join("feed_state.db");
+
+        SqliteStore::open(&file)
+            .unwrap()
+            .save(&create_states())
+            .await
+            .unwrap();
+
+        let loaded = SqliteStore::open(&file).unwrap().load().await.unwrap();
+        assert_eq!(loaded, create_states());
+
+        let read_only = SqliteStore::open_read_only(&file).unwrap();
+        assert_eq!(read_only.load().await.unwrap(), create_states());
+        assert!(read_only.save(&FeedStates::default()).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_sqlite_store_read_only_missing() {
+        let dir = tempfile::tempdir().unwrap();
+        let file = dir.path().join("state").join("feed_state.db");
+
+        let store = SqliteStore::open_read_only(&file).unwrap();
+
+        assert_eq!(store.load().await.unwrap(), FeedStates::default());
+        assert!(!dir.path().join("state").exists());
+    }
+
+    #[tokio::test]
+    async fn test_sqlite_store_migrates() {
+        let dir = tempfile::tempdir().unwrap();
+        let file = dir.path().join("feed_state.db");
+        // Schema and states from before the version was stored
+        let mut connection = Connection::open(&file).unwrap();
+        migrate(&mut connection, "Feed state database", &MIGRATIONS[..1]).unwrap();
+        connection
+            .execute(
+                "INSERT INTO feed_states (feed_id, state) VALUES ('Netcup', '{\"last_update\": 1736848800}')",
+                [],
+            )
+            .unwrap();
+        let legacy_rows = |connection: &Connection| -> i64 {
+            connection
+                .query_row(
+                    "SELECT COUNT(*) FROM feed_states WHERE feed_id = 'Netcup'",
+                    [],
+                    |row| row.get(0),
+                )
+                .unwrap()
+        };
+
+        // Read only stores migrate in memory
+        let read_only = SqliteStore::open_read_only(&file).unwrap();
+        let states = read_only.load().await.unwrap();
+        assert!(states.feeds().contains_key("netcup"));
+        assert_eq!(legacy_rows(&connection), 1);
+
+        let store = SqliteStore::open(&file).unwrap();
+        assert_eq!(store.load().await.unwrap(), states);
+        assert_eq!(legacy_rows(&connection), 0);
+        let version: u64 = connection
+            .query_row("SELECT version FROM state_version", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(version, STATE_VERSION);
+    }
+}
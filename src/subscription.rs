@@ -0,0 +1,271 @@
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, RwLock};
+
+use serde::{Deserialize, Serialize};
+
+use crate::config::DEFAULT_DATA_DIR;
+use crate::feed_item::FeedItem;
+use crate::offer::strip_html;
+use crate::state_store::json::write_atomic;
+
+/// File name in the data directory
+const SUBSCRIPTIONS_FILE: &str = "subscriptions.json";
+
+pub const MAX_KEYWORD_LENGTH: usize = 100;
+pub const MAX_KEYWORDS_PER_USER: usize = 25;
+
+#[derive(Serialize, Deserialize, Debug, Default, PartialEq)]
+struct SubscriptionMap {
+    /// Keywords keyed by Discord user id
+    users: HashMap<String, Vec<String>>,
+    #[serde(skip)]
+    dirty: bool,
+}
+
+#[derive(Debug, PartialEq)]
+pub enum Subscribed {
+    /// All keywords of the user, including the new one
+    Added(Vec<String>),
+    Duplicate,
+    LimitReached,
+}
+
+/// Keywords users subscribed to with `/subscribe`, shared with the interactions endpoint
+#[derive(Debug, Clone)]
+pub struct Subscriptions {
+    map: Arc<RwLock<SubscriptionMap>>,
+    file: PathBuf,
+}
+
+impl Default for Subscriptions {
+    fn default() -> Self {
+        Self {
+            map: Arc::default(),
+            file: Path::new(DEFAULT_DATA_DIR).join(SUBSCRIPTIONS_FILE),
+        }
+    }
+}
+
+impl Subscriptions {
+    pub fn load(data_dir: &Path) -> crate::Result<Self> {
+        Self::load_from_path(&data_dir.join(SUBSCRIPTIONS_FILE))
+    }
+
+    #[tracing::instrument]
+    pub(crate) fn load_from_path(file: &Path) -> crate::Result<Self> {
+        let map = if file.exists() {
+            info!("Loading subscriptions from file");
+            serde_json::from_str(&std::fs::read_to_string(file)?)?
+        } else {
+            SubscriptionMap::default()
+        };
+
+        Ok(Self {
+            map: Arc::new(RwLock::new(map)),
+            file: file.to_path_buf(),
+        })
+    }
+
+    #[tracing::instrument]
+    pub async fn save(&self) -> crate::Result<()> {
+        // Serialized under the lock, written without holding it
+        let content = {
+            let mut map = self.map.write().expect("Subscriptions lock is poisoned");
+            if !map.dirty {
+                return Ok(());
+            }
+            map.dirty = false;
+            serde_json::to_string_pretty(&*map)?
+        };
+
+        debug!("Saving subscriptions to file");
+        if let Err(e) = write_atomic(&self.file, &content).await {
+            self.map
+                .write()
+                .expect("Subscriptions lock is poisoned")
+                .dirty = true;
+            return Err(e);
+        }
+
+        Ok(())
+    }
+
+    /// Adds the keyword, keywords are compared case insensitive
+    pub fn subscribe(&self, user: &str, keyword: &str) -> Subscribed {
+        let mut map = self.map.write().expect("Subscriptions lock is poisoned");
+        let keywords = map.users.entry(user.to_string()).or_default();
+        if keywords
+            .iter()
+            .any(|existing| existing.to_lowercase() == keyword.to_lowercase())
+        {
+            return Subscribed::Duplicate;
+        }
+        if keywords.len() >= MAX_KEYWORDS_PER_USER {
+            return Subscribed::LimitReached;
+        }
+
+        keywords.push(keyword.to_string());
+        let keywords = keywords.clone();
+        map.dirty = true;
+        Subscribed::Added(keywords)
+    }
+
+    /// Removes the keyword, returns the remaining keywords or `None` if the user didn't subscribe to it
+    pub fn unsubscribe(&self, user: &str, keyword: &str) -> Option<Vec<String>> {
+        let mut map = self.map.write().expect("Subscriptions lock is poisoned");
+        let keywords = map.users.get_mut(user)?;
+        let index = keywords
+            .iter()
+            .position(|existing| existing.to_lowercase() == keyword.to_lowercase())?;
+
+        keywords.remove(index);
+        let keywords = keywords.clone();
+        if keywords.is_empty() {
+            map.users.remove(user);
+        }
+        map.dirty = true;
+        Some(keywords)
+    }
+
+    pub fn keywords(&self, user: &str) -> Vec<String> {
+        self.map
+            .read()
+            .expect("Subscriptions lock is poisoned")
+            .users
+            .get(user)
+            .cloned()
+            .unwrap_or_default()
+    }
+
+    /// Users with a keyword in the title or description of the item
+    pub fn matching(&self, item: &FeedItem) -> Vec<String> {
+        let text = format!(
+            "{}\n{}",
+            item.title.as_deref().unwrap_or_default(),
+            item.description
+                .as_deref()
+                .map(strip_html)
+                .unwrap_or_default()
+        )
+        .to_lowercase();
+
+        let map = self.map.read().expect("Subscriptions lock is poisoned");
+        let mut users = map
+            .users
+            .iter()
+            .filter(|(_, keywords)| {
+                keywords
+                    .iter()
+                    .any(|keyword| text.contains(&keyword.to_lowercase()))
+            })
+            .map(|(user, _)| user.clone())
+            .collect::<Vec<String>>();
+        users.sort();
+        users
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use tempfile::tempdir;
+
+    use super::*;
+
+    fn create_item(title: &str, description: &str) -> FeedItem {
+        FeedItem {
+            title: Some(title.to_string()),
+            description: Some(description.to_string()),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_subscribe() {
+        let subscriptions = Subscriptions::default();
+
+        assert_eq!(
+            subscriptions.subscribe("1", "RS 8000"),
+            Subscribed::Added(vec!["RS 8000".to_string()])
+        );
+        assert_eq!(
+            subscriptions.subscribe("1", "rs 8000"),
+            Subscribed::Duplicate
+        );
+        for index in 1..MAX_KEYWORDS_PER_USER {
+            subscriptions.subscribe("1", &format!("keyword {index}"));
+        }
+        assert_eq!(
+            subscriptions.subscribe("1", "ARM"),
+            Subscribed::LimitReached
+        );
+        assert_eq!(subscriptions.keywords("2"), Vec::<String>::new());
+    }
+
+    #[test]
+    fn test_unsubscribe() {
+        let subscriptions = Subscriptions::default();
+        subscriptions.subscribe("1", "RS 8000");
+        subscriptions.subscribe("1", "ARM");
+
+        assert_eq!(
+            subscriptions.unsubscribe("1", "rs 8000"),
+            Some(vec!["ARM".to_string()])
+        );
+        assert_eq!(subscriptions.unsubscribe("1", "RS 8000"), None);
+        assert_eq!(subscriptions.unsubscribe("2", "ARM"), None);
+
+        assert_eq!(subscriptions.unsubscribe("1", "ARM"), Some(Vec::new()));
+        assert!(subscriptions
+            .matching(&create_item("VPS ARM", ""))
+            .is_empty());
+    }
+
+    #[test]
+    fn test_matching() {
+        let subscriptions = Subscriptions::default();
+        subscriptions.subscribe("1", "RS 8000");
+        subscriptions.subscribe("2", "arm");
+        subscriptions.subscribe("3", "ARM");
+
+        assert_eq!(
+            subscriptions.matching(&create_item("VPS 1000", "<p>Ampere <b>ARM</b> CPU</p>")),
+            vec!["2", "3"]
+        );
+        assert_eq!(
+            subscriptions.matching(&create_item("RS 8000 G11", "")),
+            vec!["1"]
+        );
+        assert!(subscriptions
+            .matching(&create_item("RS 1000", "x86"))
+            .is_empty());
+        assert_eq!(
+            subscriptions.matching(&create_item("Root-Server", "<p>RS&#160;8000</p>")),
+            vec!["1"]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_save_and_load() {
+        let dir = tempdir().unwrap();
+        let file = dir.path().join("subscriptions.json");
+        let subscriptions = Subscriptions::load_from_path(&file).unwrap();
+
+        // Nothing to save yet
+        subscriptions.save().await.unwrap();
+        assert!(!file.exists());
+
+        subscriptions.subscribe("1", "ARM");
+        subscriptions.save().await.unwrap();
+
+        let loaded = Subscriptions::load_from_path(&file).unwrap();
+        assert_eq!(loaded.keywords("1"), vec!["ARM"]);
+
+        // The previous content is kept as backup
+        loaded.unsubscribe("1", "ARM");
+        loaded.save().await.unwrap();
+        let loaded = Subscriptions::load_from_path(&file).unwrap();
+        assert!(loaded.keywords("1").is_empty());
+        assert!(dir.path().join("subscriptions.json.bak").exists());
+    }
+}